@@ -9,6 +9,8 @@ async fn main() {
         println!("Specify MASTODON_URL!!");
         return
     };
-    let sns = detector(url.as_str()).await;
-    println!("{:#?}", sns);
+    match detector(url.as_str()).await {
+        Ok(server) => println!("{:#?}", server),
+        Err(err) => println!("{:#?}", err),
+    }
 }
@@ -43,6 +43,7 @@ async fn main() {
                     client_secret,
                     code.trim().to_string(),
                     megalodon::default::NO_REDIRECT.to_string(),
+                    app_data.code_verifier,
                 )
                 .await
             {
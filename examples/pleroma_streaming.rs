@@ -46,6 +46,21 @@ async fn streaming(url: &str, access_token: String) {
             Message::Heartbeat() => {
                 println!("heartbeat");
             }
+            Message::Connected() => {
+                println!("connected");
+            }
+            Message::Reconnecting { attempt } => {
+                println!("reconnecting: {}", attempt);
+            }
+            Message::Disconnected() => {
+                println!("disconnected");
+            }
+            Message::Unknown { event, payload } => {
+                println!("unknown event {}: {}", event, payload);
+            }
+            mes => {
+                println!("{:#?}", mes);
+            }
         }))
         .await;
 }
@@ -0,0 +1,1102 @@
+use crate::capabilities::Capabilities;
+use crate::pleroma::api_client::APIClient;
+use crate::pleroma::{entities, Pleroma};
+use crate::rate_limit::RetryPolicy;
+use crate::streaming::{MultiplexedStreaming, StreamingOptions};
+use crate::token_manager::TokenManager;
+use crate::{
+    entities as MegalodonEntities, error::Error, megalodon, oauth as MegalodonOAuth,
+    response::Response,
+};
+use crate::{Streaming, SNS};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+
+/// Akkoma API Client which satisfies megalodon trait.
+/// Akkoma is a fork of Pleroma, so requests are sent by [`Pleroma`] except endpoints which only Akkoma has.
+#[derive(Debug, Clone)]
+pub struct Akkoma {
+    pleroma: Pleroma,
+    client: APIClient,
+}
+
+impl Akkoma {
+    /// Create a new [`Akkoma`].
+    pub fn new(
+        base_url: String,
+        access_token: Option<String>,
+        user_agent: Option<String>,
+    ) -> Akkoma {
+        let pleroma = Pleroma::new(base_url.clone(), access_token.clone(), user_agent.clone());
+        let client = APIClient::new(base_url, access_token, user_agent);
+        Akkoma { pleroma, client }
+    }
+
+    /// Create a new [`Akkoma`] which sends all requests with the given HTTP client.
+    /// The user agent is used for streaming connections, because the HTTP client has its own one.
+    pub fn with_client(
+        base_url: String,
+        access_token: Option<String>,
+        user_agent: Option<String>,
+        http_client: reqwest::Client,
+    ) -> Akkoma {
+        let pleroma = Pleroma::with_client(
+            base_url.clone(),
+            access_token.clone(),
+            user_agent,
+            http_client.clone(),
+        );
+        let client = APIClient::with_client(base_url, access_token, http_client);
+        Akkoma { pleroma, client }
+    }
+
+    /// Set a policy to retry failed requests. Requests are not retried when it is `None`.
+    pub fn set_retry_policy(&mut self, retry_policy: Option<RetryPolicy>) {
+        self.pleroma.set_retry_policy(retry_policy.clone());
+        self.client.set_retry_policy(retry_policy);
+    }
+
+    /// Set a manager which refreshes the access token. When it is set, the access token of the manager is used instead.
+    pub fn set_token_manager(&mut self, token_manager: Option<TokenManager>) {
+        let managed = token_manager.map(|manager| self.pleroma.managed_token(manager));
+        self.pleroma.set_managed_token(managed.clone());
+        self.client.set_token_manager(managed);
+    }
+
+    /// Set options of streaming connections, which are used for streaming objects created after this call.
+    pub fn set_streaming_options(&mut self, streaming_options: StreamingOptions) {
+        self.pleroma.set_streaming_options(streaming_options);
+    }
+}
+
+#[async_trait]
+impl megalodon::Megalodon for Akkoma {
+    async fn register_app(
+        &self,
+        client_name: String,
+        options: &megalodon::AppInputOptions,
+    ) -> Result<MegalodonOAuth::AppData, Error> {
+        self.pleroma.register_app(client_name, options).await
+    }
+
+    async fn create_app(
+        &self,
+        client_name: String,
+        options: &megalodon::AppInputOptions,
+    ) -> Result<MegalodonOAuth::AppData, Error> {
+        self.pleroma.create_app(client_name, options).await
+    }
+
+    async fn fetch_access_token(
+        &self,
+        client_id: String,
+        client_secret: String,
+        code: String,
+        redirect_uri: String,
+        code_verifier: Option<String>,
+    ) -> Result<MegalodonOAuth::TokenData, Error> {
+        self.pleroma
+            .fetch_access_token(client_id, client_secret, code, redirect_uri, code_verifier)
+            .await
+    }
+
+    async fn refresh_access_token(
+        &self,
+        client_id: String,
+        client_secret: String,
+        refresh_token: String,
+    ) -> Result<MegalodonOAuth::TokenData, Error> {
+        self.pleroma
+            .refresh_access_token(client_id, client_secret, refresh_token)
+            .await
+    }
+
+    async fn fetch_app_token(
+        &self,
+        client_id: String,
+        client_secret: String,
+        scopes: Vec<String>,
+    ) -> Result<MegalodonOAuth::TokenData, Error> {
+        self.pleroma
+            .fetch_app_token(client_id, client_secret, scopes)
+            .await
+    }
+
+    async fn revoke_access_token(
+        &self,
+        client_id: String,
+        client_secret: String,
+        access_token: String,
+    ) -> Result<Response<()>, Error> {
+        self.pleroma
+            .revoke_access_token(client_id, client_secret, access_token)
+            .await
+    }
+
+    async fn verify_app_credentials(
+        &self,
+    ) -> Result<Response<MegalodonEntities::Application>, Error> {
+        self.pleroma.verify_app_credentials().await
+    }
+
+    async fn register_account(
+        &self,
+        username: String,
+        email: String,
+        password: String,
+        agreement: String,
+        locale: String,
+        reason: Option<String>,
+    ) -> Result<Response<MegalodonEntities::Token>, Error> {
+        self.pleroma
+            .register_account(username, email, password, agreement, locale, reason)
+            .await
+    }
+
+    async fn verify_account_credentials(
+        &self,
+    ) -> Result<Response<MegalodonEntities::Account>, Error> {
+        self.pleroma.verify_account_credentials().await
+    }
+
+    async fn update_credentials(
+        &self,
+        options: Option<&megalodon::UpdateCredentialsInputOptions>,
+    ) -> Result<Response<MegalodonEntities::Account>, Error> {
+        self.pleroma.update_credentials(options).await
+    }
+
+    async fn get_account(&self, id: String) -> Result<Response<MegalodonEntities::Account>, Error> {
+        self.pleroma.get_account(id).await
+    }
+
+    async fn get_account_statuses(
+        &self,
+        id: String,
+        options: Option<&megalodon::GetAccountStatusesInputOptions>,
+    ) -> Result<Response<Vec<MegalodonEntities::Status>>, Error> {
+        self.pleroma.get_account_statuses(id, options).await
+    }
+
+    async fn subscribe_account(
+        &self,
+        id: String,
+    ) -> Result<Response<MegalodonEntities::Relationship>, Error> {
+        self.pleroma.subscribe_account(id).await
+    }
+
+    async fn unsubscribe_account(
+        &self,
+        id: String,
+    ) -> Result<Response<MegalodonEntities::Relationship>, Error> {
+        self.pleroma.unsubscribe_account(id).await
+    }
+
+    async fn get_account_followers(
+        &self,
+        id: String,
+        options: Option<&megalodon::AccountFollowersInputOptions>,
+    ) -> Result<Response<Vec<MegalodonEntities::Account>>, Error> {
+        self.pleroma.get_account_followers(id, options).await
+    }
+
+    async fn get_account_following(
+        &self,
+        id: String,
+        options: Option<&megalodon::AccountFollowersInputOptions>,
+    ) -> Result<Response<Vec<MegalodonEntities::Account>>, Error> {
+        self.pleroma.get_account_following(id, options).await
+    }
+
+    async fn get_account_lists(
+        &self,
+        id: String,
+    ) -> Result<Response<Vec<MegalodonEntities::List>>, Error> {
+        self.pleroma.get_account_lists(id).await
+    }
+
+    async fn get_identity_proofs(
+        &self,
+        id: String,
+    ) -> Result<Response<Vec<MegalodonEntities::IdentityProof>>, Error> {
+        self.pleroma.get_identity_proofs(id).await
+    }
+
+    async fn follow_account(
+        &self,
+        id: String,
+        options: Option<&megalodon::FollowAccountInputOptions>,
+    ) -> Result<Response<MegalodonEntities::Relationship>, Error> {
+        self.pleroma.follow_account(id, options).await
+    }
+
+    async fn unfollow_account(
+        &self,
+        id: String,
+    ) -> Result<Response<MegalodonEntities::Relationship>, Error> {
+        self.pleroma.unfollow_account(id).await
+    }
+
+    async fn block_account(
+        &self,
+        id: String,
+    ) -> Result<Response<MegalodonEntities::Relationship>, Error> {
+        self.pleroma.block_account(id).await
+    }
+
+    async fn unblock_account(
+        &self,
+        id: String,
+    ) -> Result<Response<MegalodonEntities::Relationship>, Error> {
+        self.pleroma.unblock_account(id).await
+    }
+
+    async fn mute_account(
+        &self,
+        id: String,
+        notifications: bool,
+    ) -> Result<Response<MegalodonEntities::Relationship>, Error> {
+        self.pleroma.mute_account(id, notifications).await
+    }
+
+    async fn unmute_account(
+        &self,
+        id: String,
+    ) -> Result<Response<MegalodonEntities::Relationship>, Error> {
+        self.pleroma.unmute_account(id).await
+    }
+
+    async fn pin_account(
+        &self,
+        id: String,
+    ) -> Result<Response<MegalodonEntities::Relationship>, Error> {
+        self.pleroma.pin_account(id).await
+    }
+
+    async fn unpin_account(
+        &self,
+        id: String,
+    ) -> Result<Response<MegalodonEntities::Relationship>, Error> {
+        self.pleroma.unpin_account(id).await
+    }
+
+    async fn get_relationships(
+        &self,
+        ids: Vec<String>,
+    ) -> Result<Response<Vec<MegalodonEntities::Relationship>>, Error> {
+        self.pleroma.get_relationships(ids).await
+    }
+
+    async fn search_account(
+        &self,
+        q: String,
+        options: Option<&megalodon::SearchAccountInputOptions>,
+    ) -> Result<Response<Vec<MegalodonEntities::Account>>, Error> {
+        self.pleroma.search_account(q, options).await
+    }
+
+    async fn get_bookmarks(
+        &self,
+        options: Option<&megalodon::GetBookmarksInputOptions>,
+    ) -> Result<Response<Vec<MegalodonEntities::Status>>, Error> {
+        self.pleroma.get_bookmarks(options).await
+    }
+
+    async fn get_favourites(
+        &self,
+        options: Option<&megalodon::GetFavouritesInputOptions>,
+    ) -> Result<Response<Vec<MegalodonEntities::Status>>, Error> {
+        self.pleroma.get_favourites(options).await
+    }
+
+    async fn get_mutes(
+        &self,
+        options: Option<&megalodon::GetMutesInputOptions>,
+    ) -> Result<Response<Vec<MegalodonEntities::Account>>, Error> {
+        self.pleroma.get_mutes(options).await
+    }
+
+    async fn get_blocks(
+        &self,
+        options: Option<&megalodon::GetBlocksInputOptions>,
+    ) -> Result<Response<Vec<MegalodonEntities::Account>>, Error> {
+        self.pleroma.get_blocks(options).await
+    }
+
+    async fn get_domain_blocks(
+        &self,
+        options: Option<&megalodon::GetDomainBlocksInputOptions>,
+    ) -> Result<Response<Vec<String>>, Error> {
+        self.pleroma.get_domain_blocks(options).await
+    }
+
+    async fn block_domain(&self, domain: String) -> Result<Response<()>, Error> {
+        self.pleroma.block_domain(domain).await
+    }
+
+    async fn unblock_domain(&self, domain: String) -> Result<Response<()>, Error> {
+        self.pleroma.unblock_domain(domain).await
+    }
+
+    async fn get_filters(&self) -> Result<Response<Vec<MegalodonEntities::Filter>>, Error> {
+        self.pleroma.get_filters().await
+    }
+
+    async fn get_filter(&self, id: String) -> Result<Response<MegalodonEntities::Filter>, Error> {
+        self.pleroma.get_filter(id).await
+    }
+
+    async fn create_filter(
+        &self,
+        phrase: String,
+        context: Vec<MegalodonEntities::filter::FilterContext>,
+        options: Option<&megalodon::FilterInputOptions>,
+    ) -> Result<Response<MegalodonEntities::Filter>, Error> {
+        self.pleroma.create_filter(phrase, context, options).await
+    }
+
+    async fn update_filter(
+        &self,
+        id: String,
+        phrase: String,
+        context: Vec<MegalodonEntities::filter::FilterContext>,
+        options: Option<&megalodon::FilterInputOptions>,
+    ) -> Result<Response<MegalodonEntities::Filter>, Error> {
+        self.pleroma
+            .update_filter(id, phrase, context, options)
+            .await
+    }
+
+    async fn delete_filter(&self, id: String) -> Result<Response<()>, Error> {
+        self.pleroma.delete_filter(id).await
+    }
+
+    async fn get_filters_v2(&self) -> Result<Response<Vec<MegalodonEntities::FilterV2>>, Error> {
+        self.pleroma.get_filters_v2().await
+    }
+
+    async fn get_filter_v2(
+        &self,
+        id: String,
+    ) -> Result<Response<MegalodonEntities::FilterV2>, Error> {
+        self.pleroma.get_filter_v2(id).await
+    }
+
+    async fn create_filter_v2(
+        &self,
+        title: String,
+        context: Vec<MegalodonEntities::filter::FilterContext>,
+        options: Option<&megalodon::FilterV2InputOptions>,
+    ) -> Result<Response<MegalodonEntities::FilterV2>, Error> {
+        self.pleroma.create_filter_v2(title, context, options).await
+    }
+
+    async fn update_filter_v2(
+        &self,
+        id: String,
+        options: Option<&megalodon::UpdateFilterV2InputOptions>,
+    ) -> Result<Response<MegalodonEntities::FilterV2>, Error> {
+        self.pleroma.update_filter_v2(id, options).await
+    }
+
+    async fn delete_filter_v2(&self, id: String) -> Result<Response<()>, Error> {
+        self.pleroma.delete_filter_v2(id).await
+    }
+
+    async fn get_filter_keywords(
+        &self,
+        filter_id: String,
+    ) -> Result<Response<Vec<MegalodonEntities::FilterKeyword>>, Error> {
+        self.pleroma.get_filter_keywords(filter_id).await
+    }
+
+    async fn add_filter_keyword(
+        &self,
+        filter_id: String,
+        keyword: String,
+        whole_word: Option<bool>,
+    ) -> Result<Response<MegalodonEntities::FilterKeyword>, Error> {
+        self.pleroma
+            .add_filter_keyword(filter_id, keyword, whole_word)
+            .await
+    }
+
+    async fn get_filter_keyword(
+        &self,
+        id: String,
+    ) -> Result<Response<MegalodonEntities::FilterKeyword>, Error> {
+        self.pleroma.get_filter_keyword(id).await
+    }
+
+    async fn update_filter_keyword(
+        &self,
+        id: String,
+        keyword: String,
+        whole_word: Option<bool>,
+    ) -> Result<Response<MegalodonEntities::FilterKeyword>, Error> {
+        self.pleroma
+            .update_filter_keyword(id, keyword, whole_word)
+            .await
+    }
+
+    async fn remove_filter_keyword(&self, id: String) -> Result<Response<()>, Error> {
+        self.pleroma.remove_filter_keyword(id).await
+    }
+
+    async fn get_filter_statuses(
+        &self,
+        filter_id: String,
+    ) -> Result<Response<Vec<MegalodonEntities::FilterStatus>>, Error> {
+        self.pleroma.get_filter_statuses(filter_id).await
+    }
+
+    async fn add_filter_status(
+        &self,
+        filter_id: String,
+        status_id: String,
+    ) -> Result<Response<MegalodonEntities::FilterStatus>, Error> {
+        self.pleroma.add_filter_status(filter_id, status_id).await
+    }
+
+    async fn get_filter_status(
+        &self,
+        id: String,
+    ) -> Result<Response<MegalodonEntities::FilterStatus>, Error> {
+        self.pleroma.get_filter_status(id).await
+    }
+
+    async fn remove_filter_status(&self, id: String) -> Result<Response<()>, Error> {
+        self.pleroma.remove_filter_status(id).await
+    }
+
+    async fn report(
+        &self,
+        account_id: String,
+        options: Option<&megalodon::ReportInputOptions>,
+    ) -> Result<Response<MegalodonEntities::Report>, Error> {
+        self.pleroma.report(account_id, options).await
+    }
+
+    async fn get_follow_requests(
+        &self,
+        limit: Option<u32>,
+    ) -> Result<Response<Vec<MegalodonEntities::Account>>, Error> {
+        self.pleroma.get_follow_requests(limit).await
+    }
+
+    async fn accept_follow_request(
+        &self,
+        id: String,
+    ) -> Result<Response<MegalodonEntities::Relationship>, Error> {
+        self.pleroma.accept_follow_request(id).await
+    }
+
+    async fn reject_follow_request(
+        &self,
+        id: String,
+    ) -> Result<Response<MegalodonEntities::Relationship>, Error> {
+        self.pleroma.reject_follow_request(id).await
+    }
+
+    async fn get_endorsements(
+        &self,
+        options: Option<&megalodon::GetEndorsementsInputOptions>,
+    ) -> Result<Response<Vec<MegalodonEntities::Account>>, Error> {
+        self.pleroma.get_endorsements(options).await
+    }
+
+    async fn get_featured_tags(
+        &self,
+    ) -> Result<Response<Vec<MegalodonEntities::FeaturedTag>>, Error> {
+        self.pleroma.get_featured_tags().await
+    }
+
+    async fn create_featured_tag(
+        &self,
+        name: String,
+    ) -> Result<Response<MegalodonEntities::FeaturedTag>, Error> {
+        self.pleroma.create_featured_tag(name).await
+    }
+
+    async fn delete_featured_tag(&self, id: String) -> Result<Response<()>, Error> {
+        self.pleroma.delete_featured_tag(id).await
+    }
+
+    async fn get_suggested_tags(&self) -> Result<Response<Vec<MegalodonEntities::Tag>>, Error> {
+        self.pleroma.get_suggested_tags().await
+    }
+
+    async fn get_preferences(&self) -> Result<Response<MegalodonEntities::Preferences>, Error> {
+        self.pleroma.get_preferences().await
+    }
+
+    async fn get_suggestions(
+        &self,
+        limit: Option<u32>,
+    ) -> Result<Response<Vec<MegalodonEntities::Account>>, Error> {
+        self.pleroma.get_suggestions(limit).await
+    }
+
+    async fn get_tag(&self, id: String) -> Result<Response<MegalodonEntities::Tag>, Error> {
+        self.pleroma.get_tag(id).await
+    }
+
+    async fn follow_tag(&self, id: String) -> Result<Response<MegalodonEntities::Tag>, Error> {
+        self.pleroma.follow_tag(id).await
+    }
+
+    async fn unfollow_tag(&self, id: String) -> Result<Response<MegalodonEntities::Tag>, Error> {
+        self.pleroma.unfollow_tag(id).await
+    }
+
+    async fn post_status(
+        &self,
+        status: String,
+        options: Option<&megalodon::PostStatusInputOptions>,
+    ) -> Result<Response<MegalodonEntities::Status>, Error> {
+        self.pleroma.post_status(status, options).await
+    }
+
+    async fn get_status(&self, id: String) -> Result<Response<MegalodonEntities::Status>, Error> {
+        self.pleroma.get_status(id).await
+    }
+
+    async fn edit_status(
+        &self,
+        id: String,
+        options: &megalodon::EditStatusInputOptions,
+    ) -> Result<Response<MegalodonEntities::Status>, Error> {
+        self.pleroma.edit_status(id, options).await
+    }
+
+    async fn delete_status(&self, id: String) -> Result<Response<()>, Error> {
+        self.pleroma.delete_status(id).await
+    }
+
+    async fn get_status_context(
+        &self,
+        id: String,
+        options: Option<&megalodon::GetStatusContextInputOptions>,
+    ) -> Result<Response<MegalodonEntities::Context>, Error> {
+        self.pleroma.get_status_context(id, options).await
+    }
+
+    async fn get_status_reblogged_by(
+        &self,
+        id: String,
+    ) -> Result<Response<Vec<MegalodonEntities::Account>>, Error> {
+        self.pleroma.get_status_reblogged_by(id).await
+    }
+
+    async fn get_status_favourited_by(
+        &self,
+        id: String,
+    ) -> Result<Response<Vec<MegalodonEntities::Account>>, Error> {
+        self.pleroma.get_status_favourited_by(id).await
+    }
+
+    async fn favourite_status(
+        &self,
+        id: String,
+    ) -> Result<Response<MegalodonEntities::Status>, Error> {
+        self.pleroma.favourite_status(id).await
+    }
+
+    async fn unfavourite_status(
+        &self,
+        id: String,
+    ) -> Result<Response<MegalodonEntities::Status>, Error> {
+        self.pleroma.unfavourite_status(id).await
+    }
+
+    async fn reblog_status(
+        &self,
+        id: String,
+    ) -> Result<Response<MegalodonEntities::Status>, Error> {
+        self.pleroma.reblog_status(id).await
+    }
+
+    async fn unreblog_status(
+        &self,
+        id: String,
+    ) -> Result<Response<MegalodonEntities::Status>, Error> {
+        self.pleroma.unreblog_status(id).await
+    }
+
+    async fn bookmark_status(
+        &self,
+        id: String,
+    ) -> Result<Response<MegalodonEntities::Status>, Error> {
+        self.pleroma.bookmark_status(id).await
+    }
+
+    async fn unbookmark_status(
+        &self,
+        id: String,
+    ) -> Result<Response<MegalodonEntities::Status>, Error> {
+        self.pleroma.unbookmark_status(id).await
+    }
+
+    async fn mute_status(&self, id: String) -> Result<Response<MegalodonEntities::Status>, Error> {
+        self.pleroma.mute_status(id).await
+    }
+
+    async fn unmute_status(
+        &self,
+        id: String,
+    ) -> Result<Response<MegalodonEntities::Status>, Error> {
+        self.pleroma.unmute_status(id).await
+    }
+
+    async fn pin_status(&self, id: String) -> Result<Response<MegalodonEntities::Status>, Error> {
+        self.pleroma.pin_status(id).await
+    }
+
+    async fn unpin_status(&self, id: String) -> Result<Response<MegalodonEntities::Status>, Error> {
+        self.pleroma.unpin_status(id).await
+    }
+
+    async fn upload_media(
+        &self,
+        file_path: String,
+        options: Option<&megalodon::UploadMediaInputOptions>,
+    ) -> Result<Response<MegalodonEntities::UploadMedia>, Error> {
+        self.pleroma.upload_media(file_path, options).await
+    }
+
+    async fn get_media(
+        &self,
+        id: String,
+    ) -> Result<Response<MegalodonEntities::Attachment>, Error> {
+        self.pleroma.get_media(id).await
+    }
+
+    async fn update_media(
+        &self,
+        id: String,
+        options: Option<&megalodon::UpdateMediaInputOptions>,
+    ) -> Result<Response<MegalodonEntities::Attachment>, Error> {
+        self.pleroma.update_media(id, options).await
+    }
+
+    async fn get_poll(&self, id: String) -> Result<Response<MegalodonEntities::Poll>, Error> {
+        self.pleroma.get_poll(id).await
+    }
+
+    async fn vote_poll(
+        &self,
+        id: String,
+        choices: Vec<u32>,
+    ) -> Result<Response<MegalodonEntities::Poll>, Error> {
+        self.pleroma.vote_poll(id, choices).await
+    }
+
+    async fn get_scheduled_statuses(
+        &self,
+        options: Option<&megalodon::GetScheduledStatusesInputOptions>,
+    ) -> Result<Response<Vec<MegalodonEntities::ScheduledStatus>>, Error> {
+        self.pleroma.get_scheduled_statuses(options).await
+    }
+
+    async fn get_scheduled_status(
+        &self,
+        id: String,
+    ) -> Result<Response<MegalodonEntities::ScheduledStatus>, Error> {
+        self.pleroma.get_scheduled_status(id).await
+    }
+
+    async fn schedule_status(
+        &self,
+        id: String,
+        scheduled_at: Option<DateTime<Utc>>,
+    ) -> Result<Response<MegalodonEntities::ScheduledStatus>, Error> {
+        self.pleroma.schedule_status(id, scheduled_at).await
+    }
+
+    async fn cancel_scheduled_status(&self, id: String) -> Result<Response<()>, Error> {
+        self.pleroma.cancel_scheduled_status(id).await
+    }
+
+    async fn get_public_timeline(
+        &self,
+        options: Option<&megalodon::GetPublicTimelineInputOptions>,
+    ) -> Result<Response<Vec<MegalodonEntities::Status>>, Error> {
+        self.pleroma.get_public_timeline(options).await
+    }
+
+    async fn get_local_timeline(
+        &self,
+        options: Option<&megalodon::GetLocalTimelineInputOptions>,
+    ) -> Result<Response<Vec<MegalodonEntities::Status>>, Error> {
+        self.pleroma.get_local_timeline(options).await
+    }
+
+    async fn get_bubble_timeline(
+        &self,
+        options: Option<&megalodon::GetLocalTimelineInputOptions>,
+    ) -> Result<Response<Vec<MegalodonEntities::Status>>, Error> {
+        let mut params = Vec::<String>::new();
+        if let Some(options) = options {
+            if let Some(only_media) = options.only_media {
+                params.push(format!("only_media={}", only_media));
+            }
+            if let Some(limit) = options.limit {
+                params.push(format!("limit={}", limit));
+            }
+            if let Some(max_id) = &options.max_id {
+                params.push(format!("max_id={}", max_id));
+            }
+            if let Some(since_id) = &options.since_id {
+                params.push(format!("since_id={}", since_id));
+            }
+            if let Some(min_id) = &options.min_id {
+                params.push(format!("min_id={}", min_id));
+            }
+        }
+        let mut path = "/api/v1/timelines/bubble".to_string();
+        if !params.is_empty() {
+            path = path + "?" + params.join("&").as_str();
+        }
+        let res = self
+            .client
+            .get::<Vec<entities::Status>>(path.as_str(), None)
+            .await?;
+
+        Ok(Response::<Vec<MegalodonEntities::Status>>::new(
+            res.json.into_iter().map(|j| j.into()).collect(),
+            res.status,
+            res.status_text,
+            res.header,
+        ))
+    }
+
+    async fn get_tag_timeline(
+        &self,
+        hashtag: String,
+        options: Option<&megalodon::GetTagTimelineInputOptions>,
+    ) -> Result<Response<Vec<MegalodonEntities::Status>>, Error> {
+        self.pleroma.get_tag_timeline(hashtag, options).await
+    }
+
+    async fn get_home_timeline(
+        &self,
+        options: Option<&megalodon::GetHomeTimelineInputOptions>,
+    ) -> Result<Response<Vec<MegalodonEntities::Status>>, Error> {
+        self.pleroma.get_home_timeline(options).await
+    }
+
+    async fn get_list_timeline(
+        &self,
+        list_id: String,
+        options: Option<&megalodon::GetListTimelineInputOptions>,
+    ) -> Result<Response<Vec<MegalodonEntities::Status>>, Error> {
+        self.pleroma.get_list_timeline(list_id, options).await
+    }
+
+    async fn get_conversation_timeline(
+        &self,
+        options: Option<&megalodon::GetConversationTimelineInputOptions>,
+    ) -> Result<Response<Vec<MegalodonEntities::Status>>, Error> {
+        self.pleroma.get_conversation_timeline(options).await
+    }
+
+    async fn delete_conversation(&self, id: String) -> Result<Response<()>, Error> {
+        self.pleroma.delete_conversation(id).await
+    }
+
+    async fn read_conversation(
+        &self,
+        id: String,
+    ) -> Result<Response<MegalodonEntities::Conversation>, Error> {
+        self.pleroma.read_conversation(id).await
+    }
+
+    async fn get_lists(&self) -> Result<Response<Vec<MegalodonEntities::List>>, Error> {
+        self.pleroma.get_lists().await
+    }
+
+    async fn get_list(&self, id: String) -> Result<Response<MegalodonEntities::List>, Error> {
+        self.pleroma.get_list(id).await
+    }
+
+    async fn create_list(&self, title: String) -> Result<Response<MegalodonEntities::List>, Error> {
+        self.pleroma.create_list(title).await
+    }
+
+    async fn update_list(
+        &self,
+        id: String,
+        title: String,
+    ) -> Result<Response<MegalodonEntities::List>, Error> {
+        self.pleroma.update_list(id, title).await
+    }
+
+    async fn delete_list(&self, id: String) -> Result<Response<()>, Error> {
+        self.pleroma.delete_list(id).await
+    }
+
+    async fn get_accounts_in_list(
+        &self,
+        id: String,
+        options: Option<&megalodon::GetAccountsInListInputOptions>,
+    ) -> Result<Response<Vec<MegalodonEntities::Account>>, Error> {
+        self.pleroma.get_accounts_in_list(id, options).await
+    }
+
+    async fn add_accounts_to_list(
+        &self,
+        id: String,
+        account_ids: Vec<String>,
+    ) -> Result<Response<MegalodonEntities::List>, Error> {
+        self.pleroma.add_accounts_to_list(id, account_ids).await
+    }
+
+    async fn delete_accounts_from_list(
+        &self,
+        id: String,
+        account_ids: Vec<String>,
+    ) -> Result<Response<()>, Error> {
+        self.pleroma
+            .delete_accounts_from_list(id, account_ids)
+            .await
+    }
+
+    async fn get_markers(
+        &self,
+        timeline: Vec<String>,
+    ) -> Result<Response<MegalodonEntities::Marker>, Error> {
+        self.pleroma.get_markers(timeline).await
+    }
+
+    async fn save_markers(
+        &self,
+        options: Option<&megalodon::SaveMarkersInputOptions>,
+    ) -> Result<Response<MegalodonEntities::Marker>, Error> {
+        self.pleroma.save_markers(options).await
+    }
+
+    async fn get_notifications(
+        &self,
+        options: Option<&megalodon::GetNotificationsInputOptions>,
+    ) -> Result<Response<Vec<MegalodonEntities::Notification>>, Error> {
+        self.pleroma.get_notifications(options).await
+    }
+
+    async fn get_notification(
+        &self,
+        id: String,
+    ) -> Result<Response<MegalodonEntities::Notification>, Error> {
+        self.pleroma.get_notification(id).await
+    }
+
+    async fn dismiss_notifications(&self) -> Result<Response<()>, Error> {
+        self.pleroma.dismiss_notifications().await
+    }
+
+    async fn dismiss_notification(&self, id: String) -> Result<Response<()>, Error> {
+        self.pleroma.dismiss_notification(id).await
+    }
+
+    async fn subscribe_push_notification(
+        &self,
+        subscription: &megalodon::SubscribePushNotificationInputSubscription,
+        data: Option<&megalodon::SubscribePushNotificationInputData>,
+    ) -> Result<Response<MegalodonEntities::PushSubscription>, Error> {
+        self.pleroma
+            .subscribe_push_notification(subscription, data)
+            .await
+    }
+
+    async fn get_push_subscription(
+        &self,
+    ) -> Result<Response<MegalodonEntities::PushSubscription>, Error> {
+        self.pleroma.get_push_subscription().await
+    }
+
+    async fn update_push_subscription(
+        &self,
+        data: Option<&megalodon::SubscribePushNotificationInputData>,
+    ) -> Result<Response<MegalodonEntities::PushSubscription>, Error> {
+        self.pleroma.update_push_subscription(data).await
+    }
+
+    async fn delete_push_subscription(&self) -> Result<Response<()>, Error> {
+        self.pleroma.delete_push_subscription().await
+    }
+
+    async fn search(
+        &self,
+        q: String,
+        r#type: &megalodon::SearchType,
+        options: Option<&megalodon::SearchInputOptions>,
+    ) -> Result<Response<MegalodonEntities::Results>, Error> {
+        self.pleroma.search(q, r#type, options).await
+    }
+
+    async fn get_instance(&self) -> Result<Response<MegalodonEntities::Instance>, Error> {
+        self.pleroma.get_instance().await
+    }
+
+    async fn get_instance_v2(&self) -> Result<Response<MegalodonEntities::InstanceV2>, Error> {
+        self.pleroma.get_instance_v2().await
+    }
+
+    async fn capabilities(&self) -> Result<Capabilities, Error> {
+        let res = self
+            .client
+            .get::<entities::Instance>("/api/v1/instance", None)
+            .await?;
+        Ok(Capabilities::new(
+            &SNS::Akkoma,
+            &res.json.version,
+            &res.json.pleroma.metadata.features,
+        ))
+    }
+
+    async fn get_instance_peers(&self) -> Result<Response<Vec<String>>, Error> {
+        self.pleroma.get_instance_peers().await
+    }
+
+    async fn get_instance_activity(
+        &self,
+    ) -> Result<Response<Vec<MegalodonEntities::Activity>>, Error> {
+        self.pleroma.get_instance_activity().await
+    }
+
+    async fn get_instance_trends(
+        &self,
+        limit: Option<u32>,
+    ) -> Result<Response<Vec<MegalodonEntities::Tag>>, Error> {
+        self.pleroma.get_instance_trends(limit).await
+    }
+
+    async fn get_trend_tags(
+        &self,
+        options: Option<&megalodon::GetTrendsInputOptions>,
+    ) -> Result<Response<Vec<MegalodonEntities::Tag>>, Error> {
+        self.pleroma.get_trend_tags(options).await
+    }
+
+    async fn get_trend_statuses(
+        &self,
+        options: Option<&megalodon::GetTrendsInputOptions>,
+    ) -> Result<Response<Vec<MegalodonEntities::Status>>, Error> {
+        self.pleroma.get_trend_statuses(options).await
+    }
+
+    async fn get_trend_links(
+        &self,
+        options: Option<&megalodon::GetTrendsInputOptions>,
+    ) -> Result<Response<Vec<MegalodonEntities::TrendLink>>, Error> {
+        self.pleroma.get_trend_links(options).await
+    }
+
+    async fn get_instance_directory(
+        &self,
+        options: Option<&megalodon::GetInstanceDirectoryInputOptions>,
+    ) -> Result<Response<Vec<MegalodonEntities::Account>>, Error> {
+        self.pleroma.get_instance_directory(options).await
+    }
+
+    async fn get_instance_custom_emojis(
+        &self,
+    ) -> Result<Response<Vec<MegalodonEntities::Emoji>>, Error> {
+        self.pleroma.get_instance_custom_emojis().await
+    }
+
+    async fn get_announcements(
+        &self,
+    ) -> Result<Response<Vec<MegalodonEntities::Announcement>>, Error> {
+        self.pleroma.get_announcements().await
+    }
+
+    async fn dismiss_announcement(&self, id: String) -> Result<Response<()>, Error> {
+        self.pleroma.dismiss_announcement(id).await
+    }
+
+    async fn add_announcement_reaction(
+        &self,
+        id: String,
+        name: String,
+    ) -> Result<Response<()>, Error> {
+        self.pleroma.add_announcement_reaction(id, name).await
+    }
+
+    async fn remove_announcement_reaction(
+        &self,
+        id: String,
+        name: String,
+    ) -> Result<Response<()>, Error> {
+        self.pleroma.remove_announcement_reaction(id, name).await
+    }
+
+    async fn create_emoji_reaction(
+        &self,
+        id: String,
+        emoji: String,
+    ) -> Result<Response<MegalodonEntities::Status>, Error> {
+        self.pleroma.create_emoji_reaction(id, emoji).await
+    }
+
+    async fn delete_emoji_reaction(
+        &self,
+        id: String,
+        emoji: String,
+    ) -> Result<Response<MegalodonEntities::Status>, Error> {
+        self.pleroma.delete_emoji_reaction(id, emoji).await
+    }
+
+    async fn get_emoji_reactions(
+        &self,
+        id: String,
+    ) -> Result<Response<Vec<MegalodonEntities::Reaction>>, Error> {
+        self.pleroma.get_emoji_reactions(id).await
+    }
+
+    async fn get_emoji_reaction(
+        &self,
+        id: String,
+        emoji: String,
+    ) -> Result<Response<MegalodonEntities::Reaction>, Error> {
+        self.pleroma.get_emoji_reaction(id, emoji).await
+    }
+
+    fn user_streaming(&self, streaming_url: String) -> Box<dyn Streaming + Send + Sync> {
+        self.pleroma.user_streaming(streaming_url)
+    }
+
+    fn public_streaming(&self, streaming_url: String) -> Box<dyn Streaming + Send + Sync> {
+        self.pleroma.public_streaming(streaming_url)
+    }
+
+    fn local_streaming(&self, streaming_url: String) -> Box<dyn Streaming + Send + Sync> {
+        self.pleroma.local_streaming(streaming_url)
+    }
+
+    fn direct_streaming(&self, streaming_url: String) -> Box<dyn Streaming + Send + Sync> {
+        self.pleroma.direct_streaming(streaming_url)
+    }
+
+    fn tag_streaming(
+        &self,
+        streaming_url: String,
+        tag: String,
+    ) -> Box<dyn Streaming + Send + Sync> {
+        self.pleroma.tag_streaming(streaming_url, tag)
+    }
+
+    fn list_streaming(
+        &self,
+        streaming_url: String,
+        list_id: String,
+    ) -> Box<dyn Streaming + Send + Sync> {
+        self.pleroma.list_streaming(streaming_url, list_id)
+    }
+
+    fn multiplexed_streaming(
+        &self,
+        streaming_url: String,
+    ) -> Box<dyn MultiplexedStreaming + Send + Sync> {
+        self.pleroma.multiplexed_streaming(streaming_url)
+    }
+}
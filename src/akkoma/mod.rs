@@ -0,0 +1,7 @@
+//! Akkoma related modules
+
+/// Akkoma API client.
+#[allow(clippy::module_inception)]
+pub mod akkoma;
+
+pub use akkoma::Akkoma;
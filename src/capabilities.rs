@@ -0,0 +1,311 @@
+//! Capabilities of servers, which are used to check features before calling methods
+use crate::SNS;
+
+/// Features which the server and the client support.
+/// When a feature is `false`, the related methods return [`crate::error::Kind::NoImplementedError`] or fail on the server.
+/// Grouped notifications are not implemented by any client yet, so it is always `false`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Capabilities {
+    /// Statuses can be edited.
+    pub edit_status: bool,
+    /// Statuses can quote other statuses.
+    pub quote: bool,
+    /// Statuses can have emoji reactions.
+    pub emoji_reactions: bool,
+    /// Filters can be managed with v2 API.
+    pub filters_v2: bool,
+    /// Hashtags can be followed.
+    pub follow_tag: bool,
+    /// Notifications can be grouped.
+    pub grouped_notifications: bool,
+    /// Statuses can be bookmarked.
+    pub bookmarks: bool,
+    /// Statuses can be scheduled.
+    pub scheduled_statuses: bool,
+    /// Positions in timelines can be saved as markers.
+    pub markers: bool,
+    /// Direct conversations can be read and deleted.
+    pub conversations: bool,
+    /// Web Push API subscriptions are available.
+    pub push_notifications: bool,
+}
+
+impl Capabilities {
+    /// Build capabilities from the version and features, which are read from the instance or NodeInfo.
+    /// `features` are used only for Pleroma and Akkoma, which are `metadata.features` of the instance.
+    /// A feature is enabled only when both the server and the client for the SNS support it.
+    pub fn new(sns: &SNS, version: &str, features: &[String]) -> Self {
+        let server = match sns {
+            SNS::Mastodon => Self::mastodon(version),
+            SNS::Pleroma | SNS::Akkoma => Self::pleroma(features),
+            SNS::GoToSocial => Self::gotosocial(version),
+            SNS::Misskey => Self::misskey(),
+        };
+        server.intersect(&Self::implemented(sns))
+    }
+
+    /// Features which the client for the SNS implements, regardless of the server.
+    fn implemented(sns: &SNS) -> Self {
+        match sns {
+            // Mastodon client does not implement emoji reactions of Fedibird.
+            SNS::Mastodon => Self {
+                edit_status: true,
+                quote: true,
+                filters_v2: true,
+                follow_tag: true,
+                bookmarks: true,
+                scheduled_statuses: true,
+                markers: true,
+                conversations: true,
+                push_notifications: true,
+                ..Default::default()
+            },
+            // Pleroma client does not send `quote_id`, and Akkoma client is built on it.
+            SNS::Pleroma | SNS::Akkoma => Self {
+                edit_status: true,
+                emoji_reactions: true,
+                follow_tag: true,
+                bookmarks: true,
+                scheduled_statuses: true,
+                markers: true,
+                conversations: true,
+                push_notifications: true,
+                ..Default::default()
+            },
+            SNS::GoToSocial => Self {
+                edit_status: true,
+                quote: true,
+                filters_v2: true,
+                follow_tag: true,
+                bookmarks: true,
+                markers: true,
+                ..Default::default()
+            },
+            SNS::Misskey => Self {
+                quote: true,
+                emoji_reactions: true,
+                ..Default::default()
+            },
+        }
+    }
+
+    fn intersect(&self, other: &Self) -> Self {
+        Self {
+            edit_status: self.edit_status && other.edit_status,
+            quote: self.quote && other.quote,
+            emoji_reactions: self.emoji_reactions && other.emoji_reactions,
+            filters_v2: self.filters_v2 && other.filters_v2,
+            follow_tag: self.follow_tag && other.follow_tag,
+            grouped_notifications: self.grouped_notifications && other.grouped_notifications,
+            bookmarks: self.bookmarks && other.bookmarks,
+            scheduled_statuses: self.scheduled_statuses && other.scheduled_statuses,
+            markers: self.markers && other.markers,
+            conversations: self.conversations && other.conversations,
+            push_notifications: self.push_notifications && other.push_notifications,
+        }
+    }
+
+    fn mastodon(version: &str) -> Self {
+        let v = parse_version(version);
+        // Fedibird is a fork of Mastodon, which has quotes with `quote_id` and emoji reactions.
+        let fedibird = version.contains("fedibird");
+        Self {
+            edit_status: v >= (3, 5, 0),
+            quote: fedibird,
+            emoji_reactions: fedibird,
+            filters_v2: v >= (4, 0, 0),
+            follow_tag: v >= (4, 0, 0),
+            grouped_notifications: v >= (4, 3, 0),
+            bookmarks: v >= (3, 1, 0),
+            scheduled_statuses: v >= (2, 7, 0),
+            markers: v >= (3, 0, 0),
+            conversations: v >= (2, 6, 0),
+            push_notifications: v >= (2, 4, 0),
+        }
+    }
+
+    fn pleroma(features: &[String]) -> Self {
+        let has = |feature: &str| features.iter().any(|f| f == feature);
+        Self {
+            edit_status: has("editing"),
+            quote: has("quote_posting"),
+            emoji_reactions: has("pleroma_emoji_reactions"),
+            filters_v2: false,
+            follow_tag: true,
+            grouped_notifications: false,
+            bookmarks: true,
+            scheduled_statuses: true,
+            markers: true,
+            conversations: true,
+            push_notifications: true,
+        }
+    }
+
+    fn gotosocial(version: &str) -> Self {
+        let v = parse_version(version);
+        Self {
+            edit_status: v >= (0, 18, 0),
+            quote: false,
+            emoji_reactions: false,
+            filters_v2: v >= (0, 16, 0),
+            follow_tag: v >= (0, 16, 0),
+            grouped_notifications: false,
+            bookmarks: true,
+            scheduled_statuses: false,
+            markers: true,
+            conversations: false,
+            push_notifications: false,
+        }
+    }
+
+    fn misskey() -> Self {
+        Self {
+            quote: true,
+            emoji_reactions: true,
+            ..Default::default()
+        }
+    }
+}
+
+/// Parse leading `major.minor.patch` of a version, like `4.2.0-beta1` or `0.13.0 git-ccd5b34`.
+fn parse_version(version: &str) -> (u32, u32, u32) {
+    let mut numbers = version
+        .split(|c: char| !c.is_ascii_digit() && c != '.')
+        .next()
+        .unwrap_or_default()
+        .split('.')
+        .map(|n| n.parse::<u32>().unwrap_or_default());
+    (
+        numbers.next().unwrap_or_default(),
+        numbers.next().unwrap_or_default(),
+        numbers.next().unwrap_or_default(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::{Error, Kind};
+    use crate::megalodon::EditStatusInputOptions;
+
+    #[test]
+    fn test_parse_version() {
+        assert_eq!(parse_version("4.2.0-beta1"), (4, 2, 0));
+        assert_eq!(parse_version("3.4.1+fedibird"), (3, 4, 1));
+        assert_eq!(parse_version("0.13.0 git-ccd5b34"), (0, 13, 0));
+        assert_eq!(parse_version("unknown"), (0, 0, 0));
+    }
+
+    #[test]
+    fn test_mastodon_capabilities() {
+        let capabilities = Capabilities::new(&SNS::Mastodon, "4.1.2", &[]);
+        assert!(capabilities.edit_status);
+        assert!(capabilities.filters_v2);
+        assert!(!capabilities.grouped_notifications);
+        assert!(!capabilities.emoji_reactions);
+
+        let capabilities = Capabilities::new(&SNS::Mastodon, "4.3.0", &[]);
+        assert!(!capabilities.grouped_notifications);
+        assert!(!capabilities.quote);
+
+        let capabilities = Capabilities::new(&SNS::Mastodon, "3.4.1+fedibird", &[]);
+        assert!(capabilities.quote);
+        // Mastodon client does not implement emoji reactions.
+        assert!(!capabilities.emoji_reactions);
+        assert!(!capabilities.edit_status);
+    }
+
+    #[test]
+    fn test_pleroma_capabilities() {
+        let features = [
+            "pleroma_api".to_string(),
+            "pleroma_emoji_reactions".to_string(),
+        ];
+        let capabilities = Capabilities::new(
+            &SNS::Pleroma,
+            "2.7.2 (compatible; Pleroma 2.5.0)",
+            &features,
+        );
+        assert!(capabilities.emoji_reactions);
+        assert!(!capabilities.edit_status);
+        assert!(capabilities.follow_tag);
+        assert!(!capabilities.filters_v2);
+    }
+
+    fn is_implemented<T>(result: Result<T, Error>) -> bool {
+        !matches!(result, Err(Error::OwnError(own)) if matches!(own.kind, Kind::NoImplementedError))
+    }
+
+    /// Call a method of each feature against a closed port, and collect features which do not return [`Kind::NoImplementedError`].
+    /// `quote` is an option of `post_status`, so it is taken from the implemented features as it is.
+    async fn implemented_methods(sns: SNS) -> Capabilities {
+        let client = crate::generator(
+            sns.clone(),
+            "http://127.0.0.1:1".to_string(),
+            Some("token".to_string()),
+            None,
+        );
+        let id = "1".to_string();
+        Capabilities {
+            edit_status: is_implemented(
+                client
+                    .edit_status(id.clone(), &EditStatusInputOptions::default())
+                    .await,
+            ),
+            quote: Capabilities::implemented(&sns).quote,
+            emoji_reactions: is_implemented(
+                client
+                    .create_emoji_reaction(id.clone(), "+1".to_string())
+                    .await,
+            ),
+            filters_v2: is_implemented(client.get_filters_v2().await),
+            follow_tag: is_implemented(client.follow_tag(id.clone()).await),
+            grouped_notifications: false,
+            bookmarks: is_implemented(client.bookmark_status(id.clone()).await),
+            scheduled_statuses: is_implemented(client.get_scheduled_statuses(None).await),
+            markers: is_implemented(client.get_markers(vec!["home".to_string()]).await),
+            conversations: is_implemented(client.delete_conversation(id.clone()).await),
+            push_notifications: is_implemented(client.get_push_subscription().await),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_mastodon_implemented() {
+        assert_eq!(
+            implemented_methods(SNS::Mastodon).await,
+            Capabilities::implemented(&SNS::Mastodon)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_pleroma_implemented() {
+        assert_eq!(
+            implemented_methods(SNS::Pleroma).await,
+            Capabilities::implemented(&SNS::Pleroma)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_akkoma_implemented() {
+        assert_eq!(
+            implemented_methods(SNS::Akkoma).await,
+            Capabilities::implemented(&SNS::Akkoma)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_gotosocial_implemented() {
+        assert_eq!(
+            implemented_methods(SNS::GoToSocial).await,
+            Capabilities::implemented(&SNS::GoToSocial)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_misskey_implemented() {
+        assert_eq!(
+            implemented_methods(SNS::Misskey).await,
+            Capabilities::implemented(&SNS::Misskey)
+        );
+    }
+}
@@ -0,0 +1,303 @@
+//! Credential store modules, which persist registered applications and access tokens of accounts
+use crate::error::Error;
+use crate::oauth::{AppData, TokenData};
+use crate::SNS;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tokio::io::AsyncWriteExt;
+
+/// Key of a credential, which identifies an account on an instance.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct CredentialKey {
+    /// URL of the instance, like `https://mastodon.social`.
+    pub instance_url: String,
+    /// Account on the instance, like `username`.
+    pub account: String,
+}
+
+impl CredentialKey {
+    /// Create a new [`CredentialKey`].
+    pub fn new(instance_url: String, account: String) -> Self {
+        Self {
+            instance_url,
+            account,
+        }
+    }
+}
+
+/// Stored credential of an account.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Credential {
+    /// Which SNS the instance is.
+    pub sns: SNS,
+    /// Registered application.
+    pub app: AppData,
+    /// Access token of the account.
+    pub token: TokenData,
+}
+
+/// Storage of credentials, which is keyed by the instance URL and the account.
+/// It is used by [`crate::ClientBuilder::from_credential_store`] to build a client and persist refreshed tokens.
+#[async_trait]
+pub trait CredentialStore: Send + Sync {
+    /// Load the credential. Returns `None` if it is not stored.
+    async fn load(&self, key: &CredentialKey) -> Result<Option<Credential>, Error>;
+
+    /// Save the credential. The stored one is overwritten.
+    async fn save(&self, key: &CredentialKey, credential: &Credential) -> Result<(), Error>;
+
+    /// Delete the credential. It is not an error if it is not stored.
+    async fn delete(&self, key: &CredentialKey) -> Result<(), Error>;
+
+    /// List keys of all stored credentials.
+    async fn keys(&self) -> Result<Vec<CredentialKey>, Error>;
+}
+
+/// Credential store in memory. Credentials are lost when it is dropped.
+#[derive(Debug, Default)]
+pub struct MemoryCredentialStore {
+    credentials: Mutex<HashMap<CredentialKey, Credential>>,
+}
+
+impl MemoryCredentialStore {
+    /// Create a new empty [`MemoryCredentialStore`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl CredentialStore for MemoryCredentialStore {
+    async fn load(&self, key: &CredentialKey) -> Result<Option<Credential>, Error> {
+        Ok(self.credentials.lock().unwrap().get(key).cloned())
+    }
+
+    async fn save(&self, key: &CredentialKey, credential: &Credential) -> Result<(), Error> {
+        self.credentials
+            .lock()
+            .unwrap()
+            .insert(key.clone(), credential.clone());
+        Ok(())
+    }
+
+    async fn delete(&self, key: &CredentialKey) -> Result<(), Error> {
+        self.credentials.lock().unwrap().remove(key);
+        Ok(())
+    }
+
+    async fn keys(&self) -> Result<Vec<CredentialKey>, Error> {
+        Ok(self.credentials.lock().unwrap().keys().cloned().collect())
+    }
+}
+
+/// Credential store in a JSON file.
+/// The whole file is read for each operation, and it is rewritten through a temporary file for each change.
+/// Access tokens are written in plain text, so please protect the file.
+#[derive(Debug)]
+pub struct JsonFileCredentialStore {
+    path: PathBuf,
+    lock: tokio::sync::Mutex<()>,
+}
+
+/// An entry of the JSON file.
+#[derive(Serialize, Deserialize)]
+struct Entry {
+    #[serde(flatten)]
+    key: CredentialKey,
+    #[serde(flatten)]
+    credential: Credential,
+}
+
+impl JsonFileCredentialStore {
+    /// Create a new [`JsonFileCredentialStore`]. The file is created when a credential is saved at first.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            lock: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    async fn read(&self) -> Result<Vec<Entry>, Error> {
+        match tokio::fs::read(&self.path).await {
+            Ok(bytes) => Ok(serde_json::from_slice(&bytes)?),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    async fn write(&self, entries: &[Entry]) -> Result<(), Error> {
+        let bytes = serde_json::to_vec_pretty(entries)?;
+        let mut tmp = self.path.clone().into_os_string();
+        tmp.push(".tmp");
+        let mut options = tokio::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        // The file contains access tokens, so it is readable only by the owner.
+        #[cfg(unix)]
+        options.mode(0o600);
+        let mut file = options.open(&tmp).await?;
+        file.write_all(&bytes).await?;
+        file.sync_all().await?;
+        drop(file);
+        tokio::fs::rename(&tmp, &self.path).await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl CredentialStore for JsonFileCredentialStore {
+    async fn load(&self, key: &CredentialKey) -> Result<Option<Credential>, Error> {
+        let _guard = self.lock.lock().await;
+        let entries = self.read().await?;
+        Ok(entries
+            .into_iter()
+            .find(|entry| entry.key == *key)
+            .map(|entry| entry.credential))
+    }
+
+    async fn save(&self, key: &CredentialKey, credential: &Credential) -> Result<(), Error> {
+        let _guard = self.lock.lock().await;
+        let mut entries = self.read().await?;
+        let entry = Entry {
+            key: key.clone(),
+            credential: credential.clone(),
+        };
+        match entries.iter_mut().find(|e| e.key == *key) {
+            Some(stored) => *stored = entry,
+            None => entries.push(entry),
+        }
+        self.write(&entries).await
+    }
+
+    async fn delete(&self, key: &CredentialKey) -> Result<(), Error> {
+        let _guard = self.lock.lock().await;
+        let mut entries = self.read().await?;
+        let len = entries.len();
+        entries.retain(|entry| entry.key != *key);
+        if entries.len() == len {
+            return Ok(());
+        }
+        self.write(&entries).await
+    }
+
+    async fn keys(&self) -> Result<Vec<CredentialKey>, Error> {
+        let _guard = self.lock.lock().await;
+        let entries = self.read().await?;
+        Ok(entries.into_iter().map(|entry| entry.key).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn credential(access_token: &str) -> Credential {
+        Credential {
+            sns: SNS::Mastodon,
+            app: AppData::new(
+                "1".to_string(),
+                "megalodon".to_string(),
+                None,
+                "urn:ietf:wg:oauth:2.0:oob".to_string(),
+                "client_id".to_string(),
+                "client_secret".to_string(),
+            ),
+            token: TokenData::new(
+                access_token.to_string(),
+                "Bearer".to_string(),
+                "read".to_string(),
+                1000,
+                None,
+                None,
+            ),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_json_file_credential_store() {
+        let path =
+            std::env::temp_dir().join(format!("megalodon-credentials-{}.json", std::process::id()));
+        let store = JsonFileCredentialStore::new(&path);
+        let alice = CredentialKey::new("https://example.com".to_string(), "alice".to_string());
+        let bob = CredentialKey::new("https://example.com".to_string(), "bob".to_string());
+
+        assert!(store.load(&alice).await.unwrap().is_none());
+        store.save(&alice, &credential("a1")).await.unwrap();
+        store.save(&bob, &credential("b1")).await.unwrap();
+        store.save(&alice, &credential("a2")).await.unwrap();
+
+        let reopened = JsonFileCredentialStore::new(&path);
+        let loaded = reopened.load(&alice).await.unwrap().unwrap();
+        assert_eq!(loaded.token.access_token, "a2");
+        assert!(matches!(loaded.sns, SNS::Mastodon));
+        assert_eq!(reopened.keys().await.unwrap().len(), 2);
+
+        reopened.delete(&alice).await.unwrap();
+        assert!(reopened.load(&alice).await.unwrap().is_none());
+        assert_eq!(reopened.keys().await.unwrap(), vec![bob]);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_save_refreshed_token() {
+        let server = crate::test_server::StubServer::start().await;
+        server
+            .route(
+                "GET",
+                "/api/v1/instance/peers",
+                401,
+                r#"{"error":"The access token is invalid"}"#,
+            )
+            .route("GET", "/api/v1/instance/peers", 200, r#"["example.com"]"#)
+            .route(
+                "POST",
+                "/oauth/token",
+                200,
+                r#"{"access_token":"new","token_type":"Bearer","scope":"read","created_at":2000}"#,
+            );
+        let store = std::sync::Arc::new(MemoryCredentialStore::new());
+        let key = CredentialKey::new(server.base_url.clone(), "alice".to_string());
+        let mut stored = credential("old");
+        stored.token.refresh_token = Some("refresh".to_string());
+        store.save(&key, &stored).await.unwrap();
+
+        let client = crate::ClientBuilder::from_credential_store(store.clone(), &key)
+            .await
+            .unwrap()
+            .build()
+            .unwrap();
+        client.get_instance_peers().await.unwrap();
+
+        for _ in 0..100 {
+            let saved = store.load(&key).await.unwrap().unwrap();
+            if saved.token.access_token == "new" {
+                assert_eq!(saved.token.refresh_token, Some("refresh".to_string()));
+                return;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        panic!("The refreshed token is not saved");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_json_file_is_readable_only_by_owner() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = std::env::temp_dir().join(format!(
+            "megalodon-credentials-mode-{}.json",
+            std::process::id()
+        ));
+        let store = JsonFileCredentialStore::new(&path);
+        let key = CredentialKey::new("https://example.com".to_string(), "alice".to_string());
+        store.save(&key, &credential("a1")).await.unwrap();
+
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -7,3 +7,50 @@ pub static NO_REDIRECT: &str = "urn:ietf:wg:oauth:2.0:oob";
 pub static DEFAULT_UA: &str = "megalodon";
 /// Default scopes value for register app.
 pub static DEFAULT_SCOPES: &'static [&str] = &["read", "write", "follow"];
+
+/// Build an HTTP client which sends the User-Agent.
+/// When the User-Agent is not a valid header value, [`DEFAULT_UA`] is sent instead.
+pub(crate) fn http_client(user_agent: &str) -> reqwest::Client {
+    match reqwest::Client::builder().user_agent(user_agent).build() {
+        Ok(client) => client,
+        Err(err) => {
+            log::warn!(
+                "Failed to build an HTTP client with User-Agent {:?}, so {:?} is used: {}",
+                user_agent,
+                DEFAULT_UA,
+                err
+            );
+            reqwest::Client::builder()
+                .user_agent(DEFAULT_UA)
+                .build()
+                .expect("Failed to initialize the HTTP client")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::StubServer;
+
+    #[tokio::test]
+    async fn test_http_client_sends_user_agent() {
+        let server = StubServer::start().await;
+        server.route("GET", "/", 200, "{}");
+
+        http_client("my-app")
+            .get(&server.base_url)
+            .send()
+            .await
+            .unwrap();
+        http_client("invalid\nagent")
+            .get(&server.base_url)
+            .send()
+            .await
+            .unwrap();
+
+        let requests = server.requests();
+        assert_eq!(requests[0].headers["user-agent"], "my-app");
+        assert_eq!(requests[1].headers["user-agent"], DEFAULT_UA);
+    }
+}
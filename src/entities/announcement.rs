@@ -0,0 +1,36 @@
+use super::{Emoji, Mention, Tag};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Announcement {
+    pub id: String,
+    pub content: String,
+    pub starts_at: Option<DateTime<Utc>>,
+    pub ends_at: Option<DateTime<Utc>>,
+    pub published: bool,
+    pub all_day: bool,
+    pub published_at: DateTime<Utc>,
+    pub updated_at: Option<DateTime<Utc>>,
+    pub read: Option<bool>,
+    pub mentions: Vec<Mention>,
+    pub statuses: Vec<AnnouncementStatus>,
+    pub tags: Vec<Tag>,
+    pub emojis: Vec<Emoji>,
+    pub reactions: Vec<AnnouncementReaction>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct AnnouncementStatus {
+    pub id: String,
+    pub url: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct AnnouncementReaction {
+    pub name: String,
+    pub count: u32,
+    pub me: Option<bool>,
+    pub url: Option<String>,
+    pub static_url: Option<String>,
+}
@@ -0,0 +1,14 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct EncryptedMessage {
+    pub id: String,
+    pub account_id: String,
+    pub device_id: String,
+    pub r#type: u32,
+    pub body: String,
+    pub digest: String,
+    pub message_franking: String,
+    pub created_at: DateTime<Utc>,
+}
@@ -18,4 +18,5 @@ pub enum FilterContext {
     Notifications,
     Public,
     Thread,
+    Account,
 }
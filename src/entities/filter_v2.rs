@@ -0,0 +1,44 @@
+use super::filter::FilterContext;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct FilterV2 {
+    pub id: String,
+    pub title: String,
+    pub context: Vec<FilterContext>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub filter_action: FilterAction,
+    pub keywords: Option<Vec<FilterKeyword>>,
+    pub statuses: Option<Vec<FilterStatus>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FilterAction {
+    Warn,
+    Hide,
+    Blur,
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct FilterKeyword {
+    pub id: String,
+    pub keyword: String,
+    pub whole_word: bool,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct FilterStatus {
+    pub id: String,
+    pub status_id: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct FilterResult {
+    pub filter: FilterV2,
+    pub keyword_matches: Option<Vec<String>>,
+    pub status_matches: Option<Vec<String>>,
+}
@@ -0,0 +1,131 @@
+use super::instance::{InstanceRule, Polls, Statuses};
+use super::{Account, Instance};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct InstanceV2 {
+    pub domain: String,
+    pub title: String,
+    pub version: String,
+    pub source_url: Option<String>,
+    pub description: String,
+    pub usage: Option<Usage>,
+    pub thumbnail: Option<Thumbnail>,
+    pub languages: Vec<String>,
+    pub configuration: Configuration,
+    pub registrations: Registrations,
+    pub contact: Contact,
+    pub rules: Vec<InstanceRule>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct Usage {
+    pub users: UsageUsers,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct UsageUsers {
+    pub active_month: u32,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct Thumbnail {
+    pub url: String,
+    pub blurhash: Option<String>,
+    pub versions: Option<ThumbnailVersions>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct ThumbnailVersions {
+    #[serde(rename = "@1x")]
+    pub at1x: Option<String>,
+    #[serde(rename = "@2x")]
+    pub at2x: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct Configuration {
+    pub urls: ConfigurationURLs,
+    pub statuses: Statuses,
+    pub media_attachments: Option<MediaAttachments>,
+    pub polls: Polls,
+    pub translation: Translation,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct ConfigurationURLs {
+    pub streaming: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct MediaAttachments {
+    pub supported_mime_types: Vec<String>,
+    pub image_size_limit: u32,
+    pub image_matrix_limit: u32,
+    pub video_size_limit: u32,
+    pub video_frame_rate_limit: u32,
+    pub video_matrix_limit: u32,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct Translation {
+    pub enabled: bool,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct Registrations {
+    pub enabled: bool,
+    pub approval_required: bool,
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct Contact {
+    pub email: String,
+    pub account: Option<Account>,
+}
+
+/// Synthesize v2 from v1 for servers which do not have v2 endpoint.
+/// Values which v1 does not have, like usage and media attachment limits, are empty.
+impl From<Instance> for InstanceV2 {
+    fn from(item: Instance) -> Self {
+        InstanceV2 {
+            domain: item
+                .uri
+                .trim_start_matches("https://")
+                .trim_start_matches("http://")
+                .trim_end_matches('/')
+                .to_string(),
+            title: item.title,
+            version: item.version,
+            source_url: None,
+            description: item.description,
+            usage: None,
+            thumbnail: item.thumbnail.map(|url| Thumbnail {
+                url,
+                blurhash: None,
+                versions: None,
+            }),
+            languages: item.languages,
+            configuration: Configuration {
+                urls: ConfigurationURLs {
+                    streaming: item.urls.streaming_api,
+                },
+                statuses: item.configuration.statuses,
+                media_attachments: None,
+                polls: item.configuration.polls,
+                translation: Translation { enabled: false },
+            },
+            registrations: Registrations {
+                enabled: item.registrations,
+                approval_required: item.approval_required,
+                message: None,
+            },
+            contact: Contact {
+                email: item.email,
+                account: item.contact_account,
+            },
+            rules: item.rules.unwrap_or_default(),
+        }
+    }
+}
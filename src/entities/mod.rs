@@ -2,19 +2,24 @@
 
 pub mod account;
 pub mod activity;
+pub mod announcement;
 pub mod application;
 pub mod async_attachment;
 pub mod attachment;
 pub mod card;
+pub mod chat;
 pub mod context;
 pub mod conversation;
 pub mod emoji;
+pub mod encrypted_message;
 pub mod featured_tag;
 pub mod field;
 pub mod filter;
+pub mod filter_v2;
 pub mod history;
 pub mod identity_proof;
 pub mod instance;
+pub mod instance_v2;
 pub mod list;
 pub mod marker;
 pub mod mention;
@@ -34,24 +39,30 @@ pub mod status;
 pub mod status_params;
 pub mod tag;
 pub mod token;
+pub mod trend_link;
 pub mod urls;
 
 pub use account::Account;
 pub use activity::Activity;
+pub use announcement::Announcement;
 pub use application::Application;
 pub use async_attachment::AsyncAttachment;
 pub use async_attachment::UploadMedia;
 pub use attachment::Attachment;
 pub use card::Card;
+pub use chat::{Chat, ChatMessage};
 pub use context::Context;
 pub use conversation::Conversation;
 pub use emoji::Emoji;
+pub use encrypted_message::EncryptedMessage;
 pub use featured_tag::FeaturedTag;
 pub use field::Field;
 pub use filter::Filter;
+pub use filter_v2::{FilterKeyword, FilterResult, FilterStatus, FilterV2};
 pub use history::History;
 pub use identity_proof::IdentityProof;
 pub use instance::Instance;
+pub use instance_v2::InstanceV2;
 pub use list::List;
 pub use marker::Marker;
 pub use mention::Mention;
@@ -71,4 +82,5 @@ pub use status::{Status, StatusVisibility};
 pub use status_params::StatusParams;
 pub use tag::Tag;
 pub use token::Token;
+pub use trend_link::TrendLink;
 pub use urls::URLs;
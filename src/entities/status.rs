@@ -1,6 +1,8 @@
 use serde::{Deserialize, Serialize};
 
-use super::{Account, Application, Attachment, Card, Emoji, Mention, Poll, Reaction, Tag};
+use super::{
+    Account, Application, Attachment, Card, Emoji, FilterResult, Mention, Poll, Reaction, Tag,
+};
 use crate::error::{Error, Kind};
 use chrono::{DateTime, Utc};
 use core::fmt;
@@ -17,6 +19,8 @@ pub struct Status {
     pub reblog: Option<Box<Status>>,
     pub content: String,
     pub plain_content: Option<String>,
+    /// Source of the content written in MFM, which is given by Misskey and Akkoma.
+    pub mfm_content: Option<String>,
     pub created_at: DateTime<Utc>,
     pub emojis: Vec<Emoji>,
     pub replies_count: u32,
@@ -39,6 +43,7 @@ pub struct Status {
     pub emoji_reactions: Option<Vec<Reaction>>,
     pub quote: bool,
     pub bookmarked: Option<bool>,
+    pub filtered: Option<Vec<FilterResult>>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
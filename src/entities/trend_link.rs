@@ -0,0 +1,10 @@
+use serde::{Deserialize, Serialize};
+
+use super::{Card, History};
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TrendLink {
+    #[serde(flatten)]
+    pub card: Card,
+    pub history: Vec<History>,
+}
@@ -1,4 +1,8 @@
 //! Own errors
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
 use std::fmt;
 
 /// Possible megalodon errors.
@@ -37,6 +41,84 @@ pub struct OwnError {
     pub status: Option<u16>,
     pub message: String,
     pub kind: Kind,
+    /// Parsed error body which the server responds.
+    pub body: Option<ApiErrorBody>,
+}
+
+/// Error body which Mastodon and Pleroma respond, like `{"error": "...", "error_description": "...", "details": {...}}`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ApiErrorBody {
+    /// Error message.
+    pub error: String,
+    /// Description of the error, which is mainly returned from OAuth endpoints.
+    pub error_description: Option<String>,
+    /// Validation errors for each field.
+    pub details: HashMap<String, Vec<ErrorDetail>>,
+}
+
+/// Validation error of a field.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct ErrorDetail {
+    /// Error code, like `ERR_TAKEN`.
+    pub error: String,
+    /// Human readable description of the error.
+    pub description: String,
+}
+
+#[derive(Deserialize)]
+struct RawErrorBody {
+    error: Value,
+    error_description: Option<String>,
+    details: Option<HashMap<String, Vec<ErrorDetail>>>,
+}
+
+impl ApiErrorBody {
+    /// Parse an error body. Returns `None` if the body is not an error json.
+    /// Pleroma returns validation errors in `error` as an object, so they are converted to `details`.
+    /// Misskey returns an object which has `message` and `code` in `error`, so they are used as the error and the description.
+    pub fn parse(text: &str) -> Option<Self> {
+        let raw = serde_json::from_str::<RawErrorBody>(text).ok()?;
+        let mut details = raw.details.unwrap_or_default();
+        let mut error_description = raw.error_description;
+        let error = match raw.error {
+            Value::String(error) => error,
+            Value::Object(fields)
+                if fields.get("message").is_some_and(Value::is_string)
+                    && fields.get("code").is_some_and(Value::is_string) =>
+            {
+                error_description = fields["code"].as_str().map(str::to_string);
+                fields["message"].as_str().unwrap_or_default().to_string()
+            }
+            Value::Object(fields) => {
+                for (field, messages) in fields.iter() {
+                    let messages = match messages {
+                        Value::Array(messages) => messages.clone(),
+                        message => vec![message.clone()],
+                    };
+                    details
+                        .entry(field.clone())
+                        .or_default()
+                        .extend(messages.iter().map(|message| {
+                            let message = match message {
+                                Value::String(message) => message.clone(),
+                                message => message.to_string(),
+                            };
+                            ErrorDetail {
+                                error: message.clone(),
+                                description: message,
+                            }
+                        }));
+                }
+                Value::Object(fields).to_string()
+            }
+            error => error.to_string(),
+        };
+        Some(Self {
+            error,
+            error_description,
+            details,
+        })
+    }
 }
 
 /// Error kind of [`OwnError`].
@@ -55,6 +137,48 @@ pub enum Kind {
     /// The request is not completed error.
     #[error("partial content error")]
     HTTPPartialContentError,
+    /// The request is rejected because of the rate limit.
+    /// `reset_at` is the date when the client can send requests again, if the server tells it.
+    #[error("rate limited")]
+    RateLimited {
+        /// Date when the rate limit will reset.
+        reset_at: Option<DateTime<Utc>>,
+    },
+    /// The request responds 401, because the access token is missing or invalid.
+    #[error("unauthorized error")]
+    UnauthorizedError,
+    /// The request responds 403, because the access token does not have permission.
+    #[error("forbidden error")]
+    ForbiddenError,
+    /// The request responds 404.
+    #[error("not found error")]
+    NotFoundError,
+    /// The request responds 410, because the resource has been deleted.
+    #[error("gone error")]
+    GoneError,
+    /// The request responds 422, because some parameters are invalid.
+    #[error("unprocessable entity error")]
+    UnprocessableEntityError,
+    /// The request responds 5xx.
+    #[error("server error")]
+    ServerError,
+}
+
+impl Kind {
+    /// Get the error kind for the http status code.
+    pub fn from_status(status: u16) -> Self {
+        match status {
+            206 => Kind::HTTPPartialContentError,
+            401 => Kind::UnauthorizedError,
+            403 => Kind::ForbiddenError,
+            404 => Kind::NotFoundError,
+            410 => Kind::GoneError,
+            422 => Kind::UnprocessableEntityError,
+            429 => Kind::RateLimited { reset_at: None },
+            500..=599 => Kind::ServerError,
+            _ => Kind::HTTPStatusError,
+        }
+    }
 }
 
 impl Error {
@@ -65,10 +189,36 @@ impl Error {
             kind,
             url,
             status,
+            body: None,
+        })
+    }
+
+    /// Create a new [`OwnError`] struct from an error response.
+    /// When the body is an error json, the message is taken from `error` in the body.
+    pub fn new_http(text: String, kind: Kind, url: Option<String>, status: Option<u16>) -> Error {
+        let body = ApiErrorBody::parse(&text);
+        let message = match &body {
+            Some(body) => match &body.error_description {
+                Some(description) => format!("{}: {}", body.error, description),
+                None => body.error.clone(),
+            },
+            None => text,
+        };
+        Error::OwnError(OwnError {
+            message,
+            kind,
+            url,
+            status,
+            body,
         })
     }
 }
 
+/// Whether the error is 404 response.
+pub(crate) fn is_not_found(err: &Error) -> bool {
+    matches!(err, Error::OwnError(own) if matches!(own.kind, Kind::NotFoundError))
+}
+
 impl fmt::Debug for OwnError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let mut builder = f.debug_struct("megalodon::OwnError");
@@ -82,7 +232,60 @@ impl fmt::Debug for OwnError {
         if let Some(ref status) = self.status {
             builder.field("status", status);
         }
+        if let Some(ref body) = self.body {
+            builder.field("body", body);
+        }
 
         builder.finish()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_mastodon_error_body() {
+        let text = r#"{"error":"Validation failed: Username has already been taken","details":{"username":[{"error":"ERR_TAKEN","description":"has already been taken"}]}}"#;
+
+        let body = ApiErrorBody::parse(text).unwrap();
+        assert_eq!(
+            body.error,
+            "Validation failed: Username has already been taken"
+        );
+        assert_eq!(
+            body.details.get("username"),
+            Some(&vec![ErrorDetail {
+                error: "ERR_TAKEN".to_string(),
+                description: "has already been taken".to_string(),
+            }])
+        );
+    }
+
+    #[test]
+    fn test_parse_pleroma_error_body() {
+        let text = r#"{"error":{"username":["has already been taken"]}}"#;
+
+        let body = ApiErrorBody::parse(text).unwrap();
+        assert_eq!(body.details.get("username").unwrap().len(), 1);
+        assert_eq!(
+            body.details.get("username").unwrap()[0].description,
+            "has already been taken"
+        );
+    }
+
+    #[test]
+    fn test_parse_misskey_error_body() {
+        let text = r#"{"error":{"message":"No such note.","code":"NO_SUCH_NOTE","id":"24fcbfc6-2e37-42b6-8388-c29b3861a08d","kind":"client"}}"#;
+
+        let body = ApiErrorBody::parse(text).unwrap();
+        assert_eq!(body.error, "No such note.");
+        assert_eq!(body.error_description, Some("NO_SUCH_NOTE".to_string()));
+        assert!(body.details.is_empty());
+    }
+
+    #[test]
+    fn test_parse_non_json_error_body() {
+        assert_eq!(ApiErrorBody::parse("<html>Bad Gateway</html>"), None);
+    }
+}
@@ -0,0 +1,370 @@
+//! Client-side filtering modules, which apply filters to statuses in the same way as Mastodon
+//!
+//! ```rust
+//! # use megalodon::error::Error;
+//! # use megalodon::entities::filter::FilterContext;
+//! # use megalodon::filtering::FilterEngine;
+//! #
+//! # async fn run() -> Result<(), Error> {
+//! let client = megalodon::generator(
+//!   megalodon::SNS::Mastodon,
+//!   String::from("https://fedibird.com"),
+//!   Some(String::from("your access token")),
+//!   None,
+//! );
+//! let filters = client.get_filters().await?.json();
+//! let engine = FilterEngine::new(&filters);
+//! let statuses = client.get_home_timeline(None).await?.json();
+//! let statuses = engine.apply(statuses, &FilterContext::Home);
+//! # Ok(())
+//! # }
+//! ```
+use crate::entities::filter::FilterContext;
+use crate::entities::filter_v2::FilterAction;
+use crate::entities::{Filter, FilterKeyword, FilterResult, FilterV2, Notification, Status};
+use crate::streaming::Message;
+use chrono::{DateTime, Utc};
+
+/// Decision of filters for a status.
+#[derive(Debug, Clone)]
+pub enum FilterDecision {
+    /// No filter matches the status.
+    Show,
+    /// Some filters match the status, and it should be shown with a warning.
+    Warn(Vec<FilterResult>),
+    /// The status should be removed.
+    Hide,
+}
+
+#[derive(Debug, Clone)]
+struct Keyword {
+    /// Keyword as it is in the filter, which is returned in `keyword_matches`.
+    original: String,
+    /// Lowercase keyword to search.
+    keyword: String,
+    whole_word: bool,
+}
+
+#[derive(Debug, Clone)]
+struct Rule {
+    filter: FilterV2,
+    keywords: Vec<Keyword>,
+    status_ids: Vec<String>,
+}
+
+/// Engine which compiles filters and evaluates statuses with them.
+///
+/// Statuses are matched against the spoiler text, the content without HTML tags and the poll options.
+/// A reblog is matched with the reblogged status.
+/// Expired filters and filters for other contexts are ignored.
+/// Irreversible v1 filters and v2 filters with `hide` action remove statuses, and other filters, including `blur`, add [`FilterResult`] to `Status.filtered`.
+#[derive(Debug, Clone, Default)]
+pub struct FilterEngine {
+    rules: Vec<Rule>,
+}
+
+impl FilterEngine {
+    /// Compile v1 filters, which are returned from [`crate::Megalodon::get_filters`].
+    pub fn new(filters: &[Filter]) -> Self {
+        let rules = filters
+            .iter()
+            .map(|filter| Rule {
+                filter: FilterV2 {
+                    id: filter.id.clone(),
+                    title: filter.phrase.clone(),
+                    context: filter.context.clone(),
+                    expires_at: filter.expires_at,
+                    filter_action: if filter.irreversible {
+                        FilterAction::Hide
+                    } else {
+                        FilterAction::Warn
+                    },
+                    keywords: Some(vec![FilterKeyword {
+                        id: filter.id.clone(),
+                        keyword: filter.phrase.clone(),
+                        whole_word: filter.whole_word,
+                    }]),
+                    statuses: None,
+                },
+                keywords: vec![Keyword {
+                    original: filter.phrase.clone(),
+                    keyword: filter.phrase.to_lowercase(),
+                    whole_word: filter.whole_word,
+                }],
+                status_ids: Vec::new(),
+            })
+            .collect();
+        Self { rules }
+    }
+
+    /// Compile v2 filters, which are returned from [`crate::Megalodon::get_filters_v2`].
+    pub fn from_v2(filters: &[FilterV2]) -> Self {
+        let rules = filters
+            .iter()
+            .map(|filter| Rule {
+                filter: filter.clone(),
+                keywords: filter
+                    .keywords
+                    .iter()
+                    .flatten()
+                    .map(|keyword| Keyword {
+                        original: keyword.keyword.clone(),
+                        keyword: keyword.keyword.to_lowercase(),
+                        whole_word: keyword.whole_word,
+                    })
+                    .collect(),
+                status_ids: filter
+                    .statuses
+                    .iter()
+                    .flatten()
+                    .map(|status| status.status_id.clone())
+                    .collect(),
+            })
+            .collect();
+        Self { rules }
+    }
+
+    /// Evaluate the status in the context.
+    pub fn evaluate(&self, status: &Status, context: &FilterContext) -> FilterDecision {
+        self.evaluate_at(status, context, Utc::now())
+    }
+
+    /// Evaluate the status in the context at the given time, which is compared with expiry of filters.
+    pub fn evaluate_at(
+        &self,
+        status: &Status,
+        context: &FilterContext,
+        now: DateTime<Utc>,
+    ) -> FilterDecision {
+        let target = status.reblog.as_deref().unwrap_or(status);
+        let text = searchable_text(target);
+        let mut results = Vec::new();
+        for rule in self.rules.iter() {
+            if !rule.filter.context.iter().any(|c| same_context(c, context)) {
+                continue;
+            }
+            if rule
+                .filter
+                .expires_at
+                .is_some_and(|expires_at| expires_at <= now)
+            {
+                continue;
+            }
+            let keyword_matches: Vec<String> = rule
+                .keywords
+                .iter()
+                .filter(|keyword| contains_keyword(&text, &keyword.keyword, keyword.whole_word))
+                .map(|keyword| keyword.original.clone())
+                .collect();
+            let status_matches: Vec<String> = rule
+                .status_ids
+                .iter()
+                .filter(|id| **id == target.id)
+                .cloned()
+                .collect();
+            if keyword_matches.is_empty() && status_matches.is_empty() {
+                continue;
+            }
+            if rule.filter.filter_action == FilterAction::Hide {
+                return FilterDecision::Hide;
+            }
+            results.push(FilterResult {
+                filter: rule.filter.clone(),
+                keyword_matches: Some(keyword_matches).filter(|v| !v.is_empty()),
+                status_matches: Some(status_matches).filter(|v| !v.is_empty()),
+            });
+        }
+        if results.is_empty() {
+            FilterDecision::Show
+        } else {
+            FilterDecision::Warn(results)
+        }
+    }
+
+    /// Filter a status. Returns `None` if it should be removed, otherwise the status with [`FilterResult`] in `filtered`.
+    pub fn filter_status(&self, mut status: Status, context: &FilterContext) -> Option<Status> {
+        match self.evaluate(&status, context) {
+            FilterDecision::Show => Some(status),
+            FilterDecision::Warn(results) => {
+                status.filtered = Some(results);
+                Some(status)
+            }
+            FilterDecision::Hide => None,
+        }
+    }
+
+    /// Filter statuses of a timeline.
+    pub fn apply(&self, statuses: Vec<Status>, context: &FilterContext) -> Vec<Status> {
+        statuses
+            .into_iter()
+            .filter_map(|status| self.filter_status(status, context))
+            .collect()
+    }
+
+    /// Filter notifications in notifications context. Notifications whose status should be removed are removed.
+    pub fn apply_notifications(&self, notifications: Vec<Notification>) -> Vec<Notification> {
+        notifications
+            .into_iter()
+            .filter_map(|notification| self.filter_notification(notification))
+            .collect()
+    }
+
+    /// Filter a notification in notifications context.
+    pub fn filter_notification(&self, mut notification: Notification) -> Option<Notification> {
+        if let Some(status) = notification.status.take() {
+            notification.status = Some(self.filter_status(status, &FilterContext::Notifications)?);
+        }
+        Some(notification)
+    }
+
+    /// Filter a streaming message. Statuses in `Update` and `StatusUpdate` are filtered in the context,
+    /// and notifications are filtered in notifications context. Other messages are returned as they are.
+    pub fn filter_message(&self, message: Message, context: &FilterContext) -> Option<Message> {
+        match message {
+            Message::Update(status) => self.filter_status(status, context).map(Message::Update),
+            Message::StatusUpdate(status) => self
+                .filter_status(status, context)
+                .map(Message::StatusUpdate),
+            Message::Notification(notification) => self
+                .filter_notification(notification)
+                .map(Message::Notification),
+            message => Some(message),
+        }
+    }
+}
+
+fn same_context(a: &FilterContext, b: &FilterContext) -> bool {
+    std::mem::discriminant(a) == std::mem::discriminant(b)
+}
+
+/// Build lowercase text to search, which contains the spoiler text, the content and the poll options.
+fn searchable_text(status: &Status) -> String {
+    let mut text = status.spoiler_text.clone();
+    text.push_str("\n\n");
+    text.push_str(&strip_html(&status.content));
+    if let Some(poll) = &status.poll {
+        for option in poll.options.iter() {
+            text.push('\n');
+            text.push_str(&option.title);
+        }
+    }
+    text.to_lowercase()
+}
+
+/// Remove HTML tags and decode basic entities. Line breaks and paragraphs are replaced with new lines.
+fn strip_html(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut rest = html;
+    while let Some(start) = rest.find('<') {
+        text.push_str(&rest[..start]);
+        let Some(end) = rest[start..].find('>') else {
+            rest = &rest[start..];
+            break;
+        };
+        let tag = rest[start + 1..start + end].to_lowercase();
+        if tag.starts_with("br") || tag.starts_with("/p") {
+            text.push('\n');
+        }
+        rest = &rest[start + end + 1..];
+    }
+    text.push_str(rest);
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&apos;", "'")
+        .replace("&nbsp;", " ")
+        .replace("&amp;", "&")
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Whether the lowercase text contains the lowercase keyword.
+/// When `whole_word` is true, word boundaries are required at the edges of the keyword which are word characters, like `\b` in Mastodon.
+fn contains_keyword(text: &str, keyword: &str, whole_word: bool) -> bool {
+    if keyword.is_empty() {
+        return false;
+    }
+    if !whole_word {
+        return text.contains(keyword);
+    }
+    let first_is_word = keyword.chars().next().is_some_and(is_word_char);
+    let last_is_word = keyword.chars().last().is_some_and(is_word_char);
+    text.match_indices(keyword).any(|(start, _)| {
+        let before = text[..start].chars().last();
+        let after = text[start + keyword.len()..].chars().next();
+        (!first_is_word || !before.is_some_and(is_word_char))
+            && (!last_is_word || !after.is_some_and(is_word_char))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_contains_keyword() {
+        assert!(contains_keyword("i love rust!", "rust", true));
+        assert!(!contains_keyword("i love rustacean", "rust", true));
+        assert!(contains_keyword("i love rustacean", "rust", false));
+        assert!(contains_keyword("tag #rust", "#rust", true));
+        assert!(!contains_keyword("snake_case", "case", true));
+        assert!(contains_keyword("日本語のテキスト", "テキスト", false));
+        assert!(!contains_keyword("anything", "", false));
+    }
+
+    #[test]
+    fn test_strip_html() {
+        assert_eq!(
+            strip_html("<p>Hello <a href=\"https://example.com\">world</a> &amp; you</p><p>bye<br/>now</p>"),
+            "Hello world & you\nbye\nnow\n"
+        );
+    }
+
+    #[test]
+    fn test_evaluate() {
+        let status: Status = serde_json::from_str(
+            r#"{"id":"1","uri":"https://example.com/1","url":null,"account":{"id":"1","username":"a","acct":"a","display_name":"a","locked":false,"created_at":"2023-01-01T00:00:00Z","followers_count":0,"following_count":0,"statuses_count":0,"note":"","url":"https://example.com/@a","avatar":"","avatar_static":"","header":"","header_static":"","emojis":[]},"in_reply_to_id":null,"in_reply_to_account_id":null,"reblog":null,"content":"<p>Spoilers for the Movie</p>","plain_content":null,"created_at":"2023-01-01T00:00:00Z","emojis":[],"replies_count":0,"reblogs_count":0,"favourites_count":0,"reblogged":null,"favourited":null,"muted":null,"sensitive":false,"spoiler_text":"","visibility":"public","media_attachments":[],"mentions":[],"tags":[],"card":null,"poll":null,"application":null,"language":null,"pinned":null,"emoji_reactions":null,"quote":false,"bookmarked":null,"filtered":null}"#,
+        )
+        .unwrap();
+        let filter = |phrase: &str, irreversible: bool, expires_at: Option<&str>| Filter {
+            id: "1".to_string(),
+            phrase: phrase.to_string(),
+            context: vec![FilterContext::Home],
+            expires_at: expires_at.map(|e| e.parse().unwrap()),
+            irreversible,
+            whole_word: true,
+        };
+        let now = "2023-06-01T00:00:00Z".parse().unwrap();
+
+        let engine = FilterEngine::new(&[filter("movie", false, None)]);
+        assert!(matches!(
+            engine.evaluate_at(&status, &FilterContext::Home, now),
+            FilterDecision::Warn(_)
+        ));
+        assert!(matches!(
+            engine.evaluate_at(&status, &FilterContext::Public, now),
+            FilterDecision::Show
+        ));
+
+        let engine = FilterEngine::new(&[filter("movie", true, None)]);
+        assert!(matches!(
+            engine.evaluate_at(&status, &FilterContext::Home, now),
+            FilterDecision::Hide
+        ));
+
+        let engine = FilterEngine::new(&[filter("movie", true, Some("2023-02-01T00:00:00Z"))]);
+        assert!(matches!(
+            engine.evaluate_at(&status, &FilterContext::Home, now),
+            FilterDecision::Show
+        ));
+
+        let engine = FilterEngine::new(&[filter("mov", false, None)]);
+        assert!(matches!(
+            engine.evaluate_at(&status, &FilterContext::Home, now),
+            FilterDecision::Show
+        ));
+    }
+}
@@ -0,0 +1,75 @@
+use crate::entities as MegalodonEntities;
+use crate::mastodon::entities::instance::{InstanceConfig, InstanceRule};
+use crate::mastodon::entities::{Account, Stats, URLs};
+use serde::Deserialize;
+
+/// GoToSocial instance, which does not have contact account and rules until they are configured.
+#[derive(Debug, Deserialize, Clone)]
+pub struct Instance {
+    pub uri: String,
+    pub title: String,
+    pub description: String,
+    pub email: String,
+    pub version: String,
+    pub thumbnail: Option<String>,
+    pub urls: URLs,
+    pub stats: Stats,
+    pub languages: Vec<String>,
+    pub registrations: bool,
+    pub approval_required: bool,
+    pub invites_enabled: bool,
+    pub configuration: InstanceConfig,
+    pub contact_account: Option<Account>,
+    #[serde(default)]
+    pub rules: Vec<InstanceRule>,
+}
+
+impl From<Instance> for MegalodonEntities::Instance {
+    fn from(item: Instance) -> Self {
+        MegalodonEntities::Instance {
+            uri: item.uri,
+            title: item.title,
+            description: item.description,
+            email: item.email,
+            version: item.version,
+            thumbnail: item.thumbnail,
+            urls: item.urls.into(),
+            stats: item.stats.into(),
+            languages: item.languages,
+            registrations: item.registrations,
+            approval_required: item.approval_required,
+            invites_enabled: Some(item.invites_enabled),
+            contact_account: item.contact_account.map(|a| a.into()),
+            configuration: item.configuration.into(),
+            rules: Some(item.rules.into_iter().map(|r| r.into()).collect()),
+        }
+    }
+}
+
+/// Synthesize v2 from v1 for GoToSocial before 0.12, keeping media attachment limits.
+impl From<Instance> for MegalodonEntities::InstanceV2 {
+    fn from(item: Instance) -> Self {
+        let media_attachments = item.configuration.media_attachments.clone();
+        let v1: MegalodonEntities::Instance = item.into();
+        let mut v2: MegalodonEntities::InstanceV2 = v1.into();
+        v2.configuration.media_attachments = Some(media_attachments.into());
+        v2
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_instance_without_contact_account() {
+        let text = r#"{"uri":"gts.example.com","account_domain":"example.com","title":"GoToSocial","description":"","short_description":"","email":"","version":"0.13.0 git-ccd5b34","languages":[],"registrations":false,"approval_required":true,"invites_enabled":false,"configuration":{"statuses":{"max_characters":5000,"max_media_attachments":6,"characters_reserved_per_url":25,"supported_mime_types":["text/plain","text/markdown"]},"media_attachments":{"supported_mime_types":["image/jpeg"],"image_size_limit":10485760,"image_matrix_limit":16777216,"video_size_limit":41943040,"video_frame_rate_limit":60,"video_matrix_limit":16777216},"polls":{"max_options":6,"max_characters_per_option":50,"min_expiration":300,"max_expiration":2629746},"accounts":{"allow_custom_css":true,"max_featured_tags":10,"max_profile_fields":6},"emojis":{"emoji_size_limit":51200}},"urls":{"streaming_api":"wss://gts.example.com"},"stats":{"domain_count":2,"status_count":16,"user_count":1},"thumbnail":"https://gts.example.com/assets/logo.png","max_toot_chars":5000}"#;
+
+        let instance: MegalodonEntities::Instance =
+            serde_json::from_str::<Instance>(text).unwrap().into();
+        assert_eq!(instance.version, "0.13.0 git-ccd5b34");
+        assert!(instance.contact_account.is_none());
+        assert_eq!(instance.rules.unwrap().len(), 0);
+        assert_eq!(instance.configuration.statuses.max_characters, 5000);
+    }
+}
@@ -0,0 +1,3 @@
+pub mod instance;
+
+pub use instance::Instance;
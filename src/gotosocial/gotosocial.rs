@@ -0,0 +1,1137 @@
+use super::entities;
+use crate::capabilities::Capabilities;
+use crate::mastodon::api_client::APIClient;
+use crate::mastodon::Mastodon;
+use crate::rate_limit::RetryPolicy;
+use crate::streaming::{MultiplexedStreaming, StreamingOptions};
+use crate::token_manager::TokenManager;
+use crate::{
+    entities as MegalodonEntities, error::Error, megalodon, oauth as MegalodonOAuth,
+    response::Response,
+};
+use crate::{error, Streaming, SNS};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+
+/// GoToSocial API Client which satisfies megalodon trait.
+/// GoToSocial has Mastodon compatible API, so requests are sent by [`Mastodon`] except endpoints which GoToSocial does not have.
+#[derive(Debug, Clone)]
+pub struct GoToSocial {
+    mastodon: Mastodon,
+    client: APIClient,
+}
+
+impl GoToSocial {
+    /// Create a new [`GoToSocial`].
+    pub fn new(
+        base_url: String,
+        access_token: Option<String>,
+        user_agent: Option<String>,
+    ) -> GoToSocial {
+        let mastodon = Mastodon::new(base_url.clone(), access_token.clone(), user_agent.clone());
+        let client = APIClient::new(base_url, access_token, user_agent);
+        GoToSocial { mastodon, client }
+    }
+
+    /// Create a new [`GoToSocial`] which sends all requests with the given HTTP client.
+    /// The user agent is used for streaming connections, because the HTTP client has its own one.
+    pub fn with_client(
+        base_url: String,
+        access_token: Option<String>,
+        user_agent: Option<String>,
+        http_client: reqwest::Client,
+    ) -> GoToSocial {
+        let mastodon = Mastodon::with_client(
+            base_url.clone(),
+            access_token.clone(),
+            user_agent,
+            http_client.clone(),
+        );
+        let client = APIClient::with_client(base_url, access_token, http_client);
+        GoToSocial { mastodon, client }
+    }
+
+    /// Set a policy to retry failed requests. Requests are not retried when it is `None`.
+    pub fn set_retry_policy(&mut self, retry_policy: Option<RetryPolicy>) {
+        self.mastodon.set_retry_policy(retry_policy.clone());
+        self.client.set_retry_policy(retry_policy);
+    }
+
+    /// Set a manager which refreshes the access token. When it is set, the access token of the manager is used instead.
+    pub fn set_token_manager(&mut self, token_manager: Option<TokenManager>) {
+        let managed = token_manager.map(|manager| self.mastodon.managed_token(manager));
+        self.mastodon.set_managed_token(managed.clone());
+        self.client.set_token_manager(managed);
+    }
+
+    /// Set options of streaming connections, which are used for streaming objects created after this call.
+    pub fn set_streaming_options(&mut self, streaming_options: StreamingOptions) {
+        self.mastodon.set_streaming_options(streaming_options);
+    }
+
+    fn not_supported(&self) -> Error {
+        Error::new_own(
+            "GoToSocial does not support".to_string(),
+            error::Kind::NoImplementedError,
+            None,
+            None,
+        )
+    }
+}
+
+#[async_trait]
+impl megalodon::Megalodon for GoToSocial {
+    async fn register_app(
+        &self,
+        client_name: String,
+        options: &megalodon::AppInputOptions,
+    ) -> Result<MegalodonOAuth::AppData, Error> {
+        self.mastodon.register_app(client_name, options).await
+    }
+
+    async fn create_app(
+        &self,
+        client_name: String,
+        options: &megalodon::AppInputOptions,
+    ) -> Result<MegalodonOAuth::AppData, Error> {
+        self.mastodon.create_app(client_name, options).await
+    }
+
+    async fn fetch_access_token(
+        &self,
+        client_id: String,
+        client_secret: String,
+        code: String,
+        redirect_uri: String,
+        code_verifier: Option<String>,
+    ) -> Result<MegalodonOAuth::TokenData, Error> {
+        self.mastodon
+            .fetch_access_token(client_id, client_secret, code, redirect_uri, code_verifier)
+            .await
+    }
+
+    async fn refresh_access_token(
+        &self,
+        client_id: String,
+        client_secret: String,
+        refresh_token: String,
+    ) -> Result<MegalodonOAuth::TokenData, Error> {
+        self.mastodon
+            .refresh_access_token(client_id, client_secret, refresh_token)
+            .await
+    }
+
+    async fn fetch_app_token(
+        &self,
+        client_id: String,
+        client_secret: String,
+        scopes: Vec<String>,
+    ) -> Result<MegalodonOAuth::TokenData, Error> {
+        self.mastodon
+            .fetch_app_token(client_id, client_secret, scopes)
+            .await
+    }
+
+    async fn revoke_access_token(
+        &self,
+        client_id: String,
+        client_secret: String,
+        access_token: String,
+    ) -> Result<Response<()>, Error> {
+        self.mastodon
+            .revoke_access_token(client_id, client_secret, access_token)
+            .await
+    }
+
+    async fn verify_app_credentials(
+        &self,
+    ) -> Result<Response<MegalodonEntities::Application>, Error> {
+        self.mastodon.verify_app_credentials().await
+    }
+
+    async fn register_account(
+        &self,
+        username: String,
+        email: String,
+        password: String,
+        agreement: String,
+        locale: String,
+        reason: Option<String>,
+    ) -> Result<Response<MegalodonEntities::Token>, Error> {
+        self.mastodon
+            .register_account(username, email, password, agreement, locale, reason)
+            .await
+    }
+
+    async fn verify_account_credentials(
+        &self,
+    ) -> Result<Response<MegalodonEntities::Account>, Error> {
+        self.mastodon.verify_account_credentials().await
+    }
+
+    async fn update_credentials(
+        &self,
+        options: Option<&megalodon::UpdateCredentialsInputOptions>,
+    ) -> Result<Response<MegalodonEntities::Account>, Error> {
+        self.mastodon.update_credentials(options).await
+    }
+
+    async fn get_account(&self, id: String) -> Result<Response<MegalodonEntities::Account>, Error> {
+        self.mastodon.get_account(id).await
+    }
+
+    async fn get_account_statuses(
+        &self,
+        id: String,
+        options: Option<&megalodon::GetAccountStatusesInputOptions>,
+    ) -> Result<Response<Vec<MegalodonEntities::Status>>, Error> {
+        self.mastodon.get_account_statuses(id, options).await
+    }
+
+    async fn subscribe_account(
+        &self,
+        id: String,
+    ) -> Result<Response<MegalodonEntities::Relationship>, Error> {
+        self.mastodon.subscribe_account(id).await
+    }
+
+    async fn unsubscribe_account(
+        &self,
+        id: String,
+    ) -> Result<Response<MegalodonEntities::Relationship>, Error> {
+        self.mastodon.unsubscribe_account(id).await
+    }
+
+    async fn get_account_followers(
+        &self,
+        id: String,
+        options: Option<&megalodon::AccountFollowersInputOptions>,
+    ) -> Result<Response<Vec<MegalodonEntities::Account>>, Error> {
+        self.mastodon.get_account_followers(id, options).await
+    }
+
+    async fn get_account_following(
+        &self,
+        id: String,
+        options: Option<&megalodon::AccountFollowersInputOptions>,
+    ) -> Result<Response<Vec<MegalodonEntities::Account>>, Error> {
+        self.mastodon.get_account_following(id, options).await
+    }
+
+    async fn get_account_lists(
+        &self,
+        id: String,
+    ) -> Result<Response<Vec<MegalodonEntities::List>>, Error> {
+        self.mastodon.get_account_lists(id).await
+    }
+
+    async fn get_identity_proofs(
+        &self,
+        _id: String,
+    ) -> Result<Response<Vec<MegalodonEntities::IdentityProof>>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn follow_account(
+        &self,
+        id: String,
+        options: Option<&megalodon::FollowAccountInputOptions>,
+    ) -> Result<Response<MegalodonEntities::Relationship>, Error> {
+        self.mastodon.follow_account(id, options).await
+    }
+
+    async fn unfollow_account(
+        &self,
+        id: String,
+    ) -> Result<Response<MegalodonEntities::Relationship>, Error> {
+        self.mastodon.unfollow_account(id).await
+    }
+
+    async fn block_account(
+        &self,
+        id: String,
+    ) -> Result<Response<MegalodonEntities::Relationship>, Error> {
+        self.mastodon.block_account(id).await
+    }
+
+    async fn unblock_account(
+        &self,
+        id: String,
+    ) -> Result<Response<MegalodonEntities::Relationship>, Error> {
+        self.mastodon.unblock_account(id).await
+    }
+
+    async fn mute_account(
+        &self,
+        id: String,
+        notifications: bool,
+    ) -> Result<Response<MegalodonEntities::Relationship>, Error> {
+        self.mastodon.mute_account(id, notifications).await
+    }
+
+    async fn unmute_account(
+        &self,
+        id: String,
+    ) -> Result<Response<MegalodonEntities::Relationship>, Error> {
+        self.mastodon.unmute_account(id).await
+    }
+
+    async fn pin_account(
+        &self,
+        _id: String,
+    ) -> Result<Response<MegalodonEntities::Relationship>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn unpin_account(
+        &self,
+        _id: String,
+    ) -> Result<Response<MegalodonEntities::Relationship>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn get_relationships(
+        &self,
+        ids: Vec<String>,
+    ) -> Result<Response<Vec<MegalodonEntities::Relationship>>, Error> {
+        self.mastodon.get_relationships(ids).await
+    }
+
+    async fn search_account(
+        &self,
+        q: String,
+        options: Option<&megalodon::SearchAccountInputOptions>,
+    ) -> Result<Response<Vec<MegalodonEntities::Account>>, Error> {
+        self.mastodon.search_account(q, options).await
+    }
+
+    async fn get_bookmarks(
+        &self,
+        options: Option<&megalodon::GetBookmarksInputOptions>,
+    ) -> Result<Response<Vec<MegalodonEntities::Status>>, Error> {
+        self.mastodon.get_bookmarks(options).await
+    }
+
+    async fn get_favourites(
+        &self,
+        options: Option<&megalodon::GetFavouritesInputOptions>,
+    ) -> Result<Response<Vec<MegalodonEntities::Status>>, Error> {
+        self.mastodon.get_favourites(options).await
+    }
+
+    async fn get_mutes(
+        &self,
+        options: Option<&megalodon::GetMutesInputOptions>,
+    ) -> Result<Response<Vec<MegalodonEntities::Account>>, Error> {
+        self.mastodon.get_mutes(options).await
+    }
+
+    async fn get_blocks(
+        &self,
+        options: Option<&megalodon::GetBlocksInputOptions>,
+    ) -> Result<Response<Vec<MegalodonEntities::Account>>, Error> {
+        self.mastodon.get_blocks(options).await
+    }
+
+    async fn get_domain_blocks(
+        &self,
+        options: Option<&megalodon::GetDomainBlocksInputOptions>,
+    ) -> Result<Response<Vec<String>>, Error> {
+        self.mastodon.get_domain_blocks(options).await
+    }
+
+    async fn block_domain(&self, domain: String) -> Result<Response<()>, Error> {
+        self.mastodon.block_domain(domain).await
+    }
+
+    async fn unblock_domain(&self, domain: String) -> Result<Response<()>, Error> {
+        self.mastodon.unblock_domain(domain).await
+    }
+
+    async fn get_filters(&self) -> Result<Response<Vec<MegalodonEntities::Filter>>, Error> {
+        self.mastodon.get_filters().await
+    }
+
+    async fn get_filter(&self, id: String) -> Result<Response<MegalodonEntities::Filter>, Error> {
+        self.mastodon.get_filter(id).await
+    }
+
+    async fn create_filter(
+        &self,
+        phrase: String,
+        context: Vec<MegalodonEntities::filter::FilterContext>,
+        options: Option<&megalodon::FilterInputOptions>,
+    ) -> Result<Response<MegalodonEntities::Filter>, Error> {
+        self.mastodon.create_filter(phrase, context, options).await
+    }
+
+    async fn update_filter(
+        &self,
+        id: String,
+        phrase: String,
+        context: Vec<MegalodonEntities::filter::FilterContext>,
+        options: Option<&megalodon::FilterInputOptions>,
+    ) -> Result<Response<MegalodonEntities::Filter>, Error> {
+        self.mastodon
+            .update_filter(id, phrase, context, options)
+            .await
+    }
+
+    async fn delete_filter(&self, id: String) -> Result<Response<()>, Error> {
+        self.mastodon.delete_filter(id).await
+    }
+
+    async fn get_filters_v2(&self) -> Result<Response<Vec<MegalodonEntities::FilterV2>>, Error> {
+        self.mastodon.get_filters_v2().await
+    }
+
+    async fn get_filter_v2(
+        &self,
+        id: String,
+    ) -> Result<Response<MegalodonEntities::FilterV2>, Error> {
+        self.mastodon.get_filter_v2(id).await
+    }
+
+    async fn create_filter_v2(
+        &self,
+        title: String,
+        context: Vec<MegalodonEntities::filter::FilterContext>,
+        options: Option<&megalodon::FilterV2InputOptions>,
+    ) -> Result<Response<MegalodonEntities::FilterV2>, Error> {
+        self.mastodon
+            .create_filter_v2(title, context, options)
+            .await
+    }
+
+    async fn update_filter_v2(
+        &self,
+        id: String,
+        options: Option<&megalodon::UpdateFilterV2InputOptions>,
+    ) -> Result<Response<MegalodonEntities::FilterV2>, Error> {
+        self.mastodon.update_filter_v2(id, options).await
+    }
+
+    async fn delete_filter_v2(&self, id: String) -> Result<Response<()>, Error> {
+        self.mastodon.delete_filter_v2(id).await
+    }
+
+    async fn get_filter_keywords(
+        &self,
+        filter_id: String,
+    ) -> Result<Response<Vec<MegalodonEntities::FilterKeyword>>, Error> {
+        self.mastodon.get_filter_keywords(filter_id).await
+    }
+
+    async fn add_filter_keyword(
+        &self,
+        filter_id: String,
+        keyword: String,
+        whole_word: Option<bool>,
+    ) -> Result<Response<MegalodonEntities::FilterKeyword>, Error> {
+        self.mastodon
+            .add_filter_keyword(filter_id, keyword, whole_word)
+            .await
+    }
+
+    async fn get_filter_keyword(
+        &self,
+        id: String,
+    ) -> Result<Response<MegalodonEntities::FilterKeyword>, Error> {
+        self.mastodon.get_filter_keyword(id).await
+    }
+
+    async fn update_filter_keyword(
+        &self,
+        id: String,
+        keyword: String,
+        whole_word: Option<bool>,
+    ) -> Result<Response<MegalodonEntities::FilterKeyword>, Error> {
+        self.mastodon
+            .update_filter_keyword(id, keyword, whole_word)
+            .await
+    }
+
+    async fn remove_filter_keyword(&self, id: String) -> Result<Response<()>, Error> {
+        self.mastodon.remove_filter_keyword(id).await
+    }
+
+    async fn get_filter_statuses(
+        &self,
+        filter_id: String,
+    ) -> Result<Response<Vec<MegalodonEntities::FilterStatus>>, Error> {
+        self.mastodon.get_filter_statuses(filter_id).await
+    }
+
+    async fn add_filter_status(
+        &self,
+        filter_id: String,
+        status_id: String,
+    ) -> Result<Response<MegalodonEntities::FilterStatus>, Error> {
+        self.mastodon.add_filter_status(filter_id, status_id).await
+    }
+
+    async fn get_filter_status(
+        &self,
+        id: String,
+    ) -> Result<Response<MegalodonEntities::FilterStatus>, Error> {
+        self.mastodon.get_filter_status(id).await
+    }
+
+    async fn remove_filter_status(&self, id: String) -> Result<Response<()>, Error> {
+        self.mastodon.remove_filter_status(id).await
+    }
+
+    async fn report(
+        &self,
+        account_id: String,
+        options: Option<&megalodon::ReportInputOptions>,
+    ) -> Result<Response<MegalodonEntities::Report>, Error> {
+        self.mastodon.report(account_id, options).await
+    }
+
+    async fn get_follow_requests(
+        &self,
+        limit: Option<u32>,
+    ) -> Result<Response<Vec<MegalodonEntities::Account>>, Error> {
+        self.mastodon.get_follow_requests(limit).await
+    }
+
+    async fn accept_follow_request(
+        &self,
+        id: String,
+    ) -> Result<Response<MegalodonEntities::Relationship>, Error> {
+        self.mastodon.accept_follow_request(id).await
+    }
+
+    async fn reject_follow_request(
+        &self,
+        id: String,
+    ) -> Result<Response<MegalodonEntities::Relationship>, Error> {
+        self.mastodon.reject_follow_request(id).await
+    }
+
+    async fn get_endorsements(
+        &self,
+        _options: Option<&megalodon::GetEndorsementsInputOptions>,
+    ) -> Result<Response<Vec<MegalodonEntities::Account>>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn get_featured_tags(
+        &self,
+    ) -> Result<Response<Vec<MegalodonEntities::FeaturedTag>>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn create_featured_tag(
+        &self,
+        _name: String,
+    ) -> Result<Response<MegalodonEntities::FeaturedTag>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn delete_featured_tag(&self, _id: String) -> Result<Response<()>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn get_suggested_tags(&self) -> Result<Response<Vec<MegalodonEntities::Tag>>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn get_preferences(&self) -> Result<Response<MegalodonEntities::Preferences>, Error> {
+        self.mastodon.get_preferences().await
+    }
+
+    async fn get_suggestions(
+        &self,
+        _limit: Option<u32>,
+    ) -> Result<Response<Vec<MegalodonEntities::Account>>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn get_tag(&self, id: String) -> Result<Response<MegalodonEntities::Tag>, Error> {
+        self.mastodon.get_tag(id).await
+    }
+
+    async fn follow_tag(&self, id: String) -> Result<Response<MegalodonEntities::Tag>, Error> {
+        self.mastodon.follow_tag(id).await
+    }
+
+    async fn unfollow_tag(&self, id: String) -> Result<Response<MegalodonEntities::Tag>, Error> {
+        self.mastodon.unfollow_tag(id).await
+    }
+
+    async fn post_status(
+        &self,
+        status: String,
+        options: Option<&megalodon::PostStatusInputOptions>,
+    ) -> Result<Response<MegalodonEntities::Status>, Error> {
+        self.mastodon.post_status(status, options).await
+    }
+
+    async fn get_status(&self, id: String) -> Result<Response<MegalodonEntities::Status>, Error> {
+        self.mastodon.get_status(id).await
+    }
+
+    async fn edit_status(
+        &self,
+        id: String,
+        options: &megalodon::EditStatusInputOptions,
+    ) -> Result<Response<MegalodonEntities::Status>, Error> {
+        self.mastodon.edit_status(id, options).await
+    }
+
+    async fn delete_status(&self, id: String) -> Result<Response<()>, Error> {
+        self.mastodon.delete_status(id).await
+    }
+
+    async fn get_status_context(
+        &self,
+        id: String,
+        options: Option<&megalodon::GetStatusContextInputOptions>,
+    ) -> Result<Response<MegalodonEntities::Context>, Error> {
+        self.mastodon.get_status_context(id, options).await
+    }
+
+    async fn get_status_reblogged_by(
+        &self,
+        id: String,
+    ) -> Result<Response<Vec<MegalodonEntities::Account>>, Error> {
+        self.mastodon.get_status_reblogged_by(id).await
+    }
+
+    async fn get_status_favourited_by(
+        &self,
+        id: String,
+    ) -> Result<Response<Vec<MegalodonEntities::Account>>, Error> {
+        self.mastodon.get_status_favourited_by(id).await
+    }
+
+    async fn favourite_status(
+        &self,
+        id: String,
+    ) -> Result<Response<MegalodonEntities::Status>, Error> {
+        self.mastodon.favourite_status(id).await
+    }
+
+    async fn unfavourite_status(
+        &self,
+        id: String,
+    ) -> Result<Response<MegalodonEntities::Status>, Error> {
+        self.mastodon.unfavourite_status(id).await
+    }
+
+    async fn reblog_status(
+        &self,
+        id: String,
+    ) -> Result<Response<MegalodonEntities::Status>, Error> {
+        self.mastodon.reblog_status(id).await
+    }
+
+    async fn unreblog_status(
+        &self,
+        id: String,
+    ) -> Result<Response<MegalodonEntities::Status>, Error> {
+        self.mastodon.unreblog_status(id).await
+    }
+
+    async fn bookmark_status(
+        &self,
+        id: String,
+    ) -> Result<Response<MegalodonEntities::Status>, Error> {
+        self.mastodon.bookmark_status(id).await
+    }
+
+    async fn unbookmark_status(
+        &self,
+        id: String,
+    ) -> Result<Response<MegalodonEntities::Status>, Error> {
+        self.mastodon.unbookmark_status(id).await
+    }
+
+    async fn mute_status(&self, id: String) -> Result<Response<MegalodonEntities::Status>, Error> {
+        self.mastodon.mute_status(id).await
+    }
+
+    async fn unmute_status(
+        &self,
+        id: String,
+    ) -> Result<Response<MegalodonEntities::Status>, Error> {
+        self.mastodon.unmute_status(id).await
+    }
+
+    async fn pin_status(&self, id: String) -> Result<Response<MegalodonEntities::Status>, Error> {
+        self.mastodon.pin_status(id).await
+    }
+
+    async fn unpin_status(&self, id: String) -> Result<Response<MegalodonEntities::Status>, Error> {
+        self.mastodon.unpin_status(id).await
+    }
+
+    async fn upload_media(
+        &self,
+        file_path: String,
+        options: Option<&megalodon::UploadMediaInputOptions>,
+    ) -> Result<Response<MegalodonEntities::UploadMedia>, Error> {
+        self.mastodon.upload_media(file_path, options).await
+    }
+
+    async fn get_media(
+        &self,
+        id: String,
+    ) -> Result<Response<MegalodonEntities::Attachment>, Error> {
+        self.mastodon.get_media(id).await
+    }
+
+    async fn update_media(
+        &self,
+        id: String,
+        options: Option<&megalodon::UpdateMediaInputOptions>,
+    ) -> Result<Response<MegalodonEntities::Attachment>, Error> {
+        self.mastodon.update_media(id, options).await
+    }
+
+    async fn get_poll(&self, id: String) -> Result<Response<MegalodonEntities::Poll>, Error> {
+        self.mastodon.get_poll(id).await
+    }
+
+    async fn vote_poll(
+        &self,
+        id: String,
+        choices: Vec<u32>,
+    ) -> Result<Response<MegalodonEntities::Poll>, Error> {
+        self.mastodon.vote_poll(id, choices).await
+    }
+
+    async fn get_scheduled_statuses(
+        &self,
+        _options: Option<&megalodon::GetScheduledStatusesInputOptions>,
+    ) -> Result<Response<Vec<MegalodonEntities::ScheduledStatus>>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn get_scheduled_status(
+        &self,
+        _id: String,
+    ) -> Result<Response<MegalodonEntities::ScheduledStatus>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn schedule_status(
+        &self,
+        _id: String,
+        _scheduled_at: Option<DateTime<Utc>>,
+    ) -> Result<Response<MegalodonEntities::ScheduledStatus>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn cancel_scheduled_status(&self, _id: String) -> Result<Response<()>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn get_public_timeline(
+        &self,
+        options: Option<&megalodon::GetPublicTimelineInputOptions>,
+    ) -> Result<Response<Vec<MegalodonEntities::Status>>, Error> {
+        self.mastodon.get_public_timeline(options).await
+    }
+
+    async fn get_local_timeline(
+        &self,
+        options: Option<&megalodon::GetLocalTimelineInputOptions>,
+    ) -> Result<Response<Vec<MegalodonEntities::Status>>, Error> {
+        self.mastodon.get_local_timeline(options).await
+    }
+
+    async fn get_bubble_timeline(
+        &self,
+        _options: Option<&megalodon::GetLocalTimelineInputOptions>,
+    ) -> Result<Response<Vec<MegalodonEntities::Status>>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn get_tag_timeline(
+        &self,
+        hashtag: String,
+        options: Option<&megalodon::GetTagTimelineInputOptions>,
+    ) -> Result<Response<Vec<MegalodonEntities::Status>>, Error> {
+        self.mastodon.get_tag_timeline(hashtag, options).await
+    }
+
+    async fn get_home_timeline(
+        &self,
+        options: Option<&megalodon::GetHomeTimelineInputOptions>,
+    ) -> Result<Response<Vec<MegalodonEntities::Status>>, Error> {
+        self.mastodon.get_home_timeline(options).await
+    }
+
+    async fn get_list_timeline(
+        &self,
+        list_id: String,
+        options: Option<&megalodon::GetListTimelineInputOptions>,
+    ) -> Result<Response<Vec<MegalodonEntities::Status>>, Error> {
+        self.mastodon.get_list_timeline(list_id, options).await
+    }
+
+    async fn get_conversation_timeline(
+        &self,
+        _options: Option<&megalodon::GetConversationTimelineInputOptions>,
+    ) -> Result<Response<Vec<MegalodonEntities::Status>>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn delete_conversation(&self, _id: String) -> Result<Response<()>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn read_conversation(
+        &self,
+        _id: String,
+    ) -> Result<Response<MegalodonEntities::Conversation>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn get_lists(&self) -> Result<Response<Vec<MegalodonEntities::List>>, Error> {
+        self.mastodon.get_lists().await
+    }
+
+    async fn get_list(&self, id: String) -> Result<Response<MegalodonEntities::List>, Error> {
+        self.mastodon.get_list(id).await
+    }
+
+    async fn create_list(&self, title: String) -> Result<Response<MegalodonEntities::List>, Error> {
+        self.mastodon.create_list(title).await
+    }
+
+    async fn update_list(
+        &self,
+        id: String,
+        title: String,
+    ) -> Result<Response<MegalodonEntities::List>, Error> {
+        self.mastodon.update_list(id, title).await
+    }
+
+    async fn delete_list(&self, id: String) -> Result<Response<()>, Error> {
+        self.mastodon.delete_list(id).await
+    }
+
+    async fn get_accounts_in_list(
+        &self,
+        id: String,
+        options: Option<&megalodon::GetAccountsInListInputOptions>,
+    ) -> Result<Response<Vec<MegalodonEntities::Account>>, Error> {
+        self.mastodon.get_accounts_in_list(id, options).await
+    }
+
+    async fn add_accounts_to_list(
+        &self,
+        id: String,
+        account_ids: Vec<String>,
+    ) -> Result<Response<MegalodonEntities::List>, Error> {
+        self.mastodon.add_accounts_to_list(id, account_ids).await
+    }
+
+    async fn delete_accounts_from_list(
+        &self,
+        id: String,
+        account_ids: Vec<String>,
+    ) -> Result<Response<()>, Error> {
+        self.mastodon
+            .delete_accounts_from_list(id, account_ids)
+            .await
+    }
+
+    async fn get_markers(
+        &self,
+        timeline: Vec<String>,
+    ) -> Result<Response<MegalodonEntities::Marker>, Error> {
+        self.mastodon.get_markers(timeline).await
+    }
+
+    async fn save_markers(
+        &self,
+        options: Option<&megalodon::SaveMarkersInputOptions>,
+    ) -> Result<Response<MegalodonEntities::Marker>, Error> {
+        self.mastodon.save_markers(options).await
+    }
+
+    async fn get_notifications(
+        &self,
+        options: Option<&megalodon::GetNotificationsInputOptions>,
+    ) -> Result<Response<Vec<MegalodonEntities::Notification>>, Error> {
+        self.mastodon.get_notifications(options).await
+    }
+
+    async fn get_notification(
+        &self,
+        id: String,
+    ) -> Result<Response<MegalodonEntities::Notification>, Error> {
+        self.mastodon.get_notification(id).await
+    }
+
+    async fn dismiss_notifications(&self) -> Result<Response<()>, Error> {
+        self.mastodon.dismiss_notifications().await
+    }
+
+    async fn dismiss_notification(&self, id: String) -> Result<Response<()>, Error> {
+        self.mastodon.dismiss_notification(id).await
+    }
+
+    async fn subscribe_push_notification(
+        &self,
+        _subscription: &megalodon::SubscribePushNotificationInputSubscription,
+        _data: Option<&megalodon::SubscribePushNotificationInputData>,
+    ) -> Result<Response<MegalodonEntities::PushSubscription>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn get_push_subscription(
+        &self,
+    ) -> Result<Response<MegalodonEntities::PushSubscription>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn update_push_subscription(
+        &self,
+        _data: Option<&megalodon::SubscribePushNotificationInputData>,
+    ) -> Result<Response<MegalodonEntities::PushSubscription>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn delete_push_subscription(&self) -> Result<Response<()>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn search(
+        &self,
+        q: String,
+        r#type: &megalodon::SearchType,
+        options: Option<&megalodon::SearchInputOptions>,
+    ) -> Result<Response<MegalodonEntities::Results>, Error> {
+        self.mastodon.search(q, r#type, options).await
+    }
+
+    async fn get_instance(&self) -> Result<Response<MegalodonEntities::Instance>, Error> {
+        let res = self
+            .client
+            .get::<entities::Instance>("/api/v1/instance", None)
+            .await?;
+
+        Ok(Response::<MegalodonEntities::Instance>::new(
+            res.json.into(),
+            res.status,
+            res.status_text,
+            res.header,
+        ))
+    }
+
+    async fn get_instance_v2(&self) -> Result<Response<MegalodonEntities::InstanceV2>, Error> {
+        match self
+            .client
+            .get::<crate::mastodon::entities::InstanceV2>("/api/v2/instance", None)
+            .await
+        {
+            Ok(res) => Ok(Response::<MegalodonEntities::InstanceV2>::new(
+                res.json.into(),
+                res.status,
+                res.status_text,
+                res.header,
+            )),
+            // GoToSocial before 0.12 does not have v2 endpoint.
+            Err(err) if error::is_not_found(&err) => {
+                let res = self
+                    .client
+                    .get::<entities::Instance>("/api/v1/instance", None)
+                    .await?;
+                Ok(Response::<MegalodonEntities::InstanceV2>::new(
+                    res.json.into(),
+                    res.status,
+                    res.status_text,
+                    res.header,
+                ))
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    async fn capabilities(&self) -> Result<Capabilities, Error> {
+        let res = self.get_instance().await?;
+        Ok(Capabilities::new(&SNS::GoToSocial, &res.json.version, &[]))
+    }
+
+    async fn get_instance_peers(&self) -> Result<Response<Vec<String>>, Error> {
+        self.mastodon.get_instance_peers().await
+    }
+
+    async fn get_instance_activity(
+        &self,
+    ) -> Result<Response<Vec<MegalodonEntities::Activity>>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn get_instance_trends(
+        &self,
+        _limit: Option<u32>,
+    ) -> Result<Response<Vec<MegalodonEntities::Tag>>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn get_trend_tags(
+        &self,
+        _options: Option<&megalodon::GetTrendsInputOptions>,
+    ) -> Result<Response<Vec<MegalodonEntities::Tag>>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn get_trend_statuses(
+        &self,
+        _options: Option<&megalodon::GetTrendsInputOptions>,
+    ) -> Result<Response<Vec<MegalodonEntities::Status>>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn get_trend_links(
+        &self,
+        _options: Option<&megalodon::GetTrendsInputOptions>,
+    ) -> Result<Response<Vec<MegalodonEntities::TrendLink>>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn get_instance_directory(
+        &self,
+        _options: Option<&megalodon::GetInstanceDirectoryInputOptions>,
+    ) -> Result<Response<Vec<MegalodonEntities::Account>>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn get_instance_custom_emojis(
+        &self,
+    ) -> Result<Response<Vec<MegalodonEntities::Emoji>>, Error> {
+        self.mastodon.get_instance_custom_emojis().await
+    }
+
+    async fn get_announcements(
+        &self,
+    ) -> Result<Response<Vec<MegalodonEntities::Announcement>>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn dismiss_announcement(&self, _id: String) -> Result<Response<()>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn add_announcement_reaction(
+        &self,
+        _id: String,
+        _name: String,
+    ) -> Result<Response<()>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn remove_announcement_reaction(
+        &self,
+        _id: String,
+        _name: String,
+    ) -> Result<Response<()>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn create_emoji_reaction(
+        &self,
+        id: String,
+        emoji: String,
+    ) -> Result<Response<MegalodonEntities::Status>, Error> {
+        self.mastodon.create_emoji_reaction(id, emoji).await
+    }
+
+    async fn delete_emoji_reaction(
+        &self,
+        id: String,
+        emoji: String,
+    ) -> Result<Response<MegalodonEntities::Status>, Error> {
+        self.mastodon.delete_emoji_reaction(id, emoji).await
+    }
+
+    async fn get_emoji_reactions(
+        &self,
+        id: String,
+    ) -> Result<Response<Vec<MegalodonEntities::Reaction>>, Error> {
+        self.mastodon.get_emoji_reactions(id).await
+    }
+
+    async fn get_emoji_reaction(
+        &self,
+        id: String,
+        emoji: String,
+    ) -> Result<Response<MegalodonEntities::Reaction>, Error> {
+        self.mastodon.get_emoji_reaction(id, emoji).await
+    }
+
+    fn user_streaming(&self, streaming_url: String) -> Box<dyn Streaming + Send + Sync> {
+        self.mastodon.user_streaming(streaming_url)
+    }
+
+    fn public_streaming(&self, streaming_url: String) -> Box<dyn Streaming + Send + Sync> {
+        self.mastodon.public_streaming(streaming_url)
+    }
+
+    fn local_streaming(&self, streaming_url: String) -> Box<dyn Streaming + Send + Sync> {
+        self.mastodon.local_streaming(streaming_url)
+    }
+
+    fn direct_streaming(&self, streaming_url: String) -> Box<dyn Streaming + Send + Sync> {
+        self.mastodon.direct_streaming(streaming_url)
+    }
+
+    fn tag_streaming(
+        &self,
+        streaming_url: String,
+        tag: String,
+    ) -> Box<dyn Streaming + Send + Sync> {
+        self.mastodon.tag_streaming(streaming_url, tag)
+    }
+
+    fn list_streaming(
+        &self,
+        streaming_url: String,
+        list_id: String,
+    ) -> Box<dyn Streaming + Send + Sync> {
+        self.mastodon.list_streaming(streaming_url, list_id)
+    }
+
+    fn multiplexed_streaming(
+        &self,
+        streaming_url: String,
+    ) -> Box<dyn MultiplexedStreaming + Send + Sync> {
+        self.mastodon.multiplexed_streaming(streaming_url)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::megalodon::Megalodon;
+    use crate::test_server::StubServer;
+
+    const INSTANCE: &str = r#"{"uri":"gts.example.com","account_domain":"example.com","title":"GoToSocial","description":"","short_description":"","email":"admin@example.com","version":"0.11.1 git-d8c4d4b","languages":[],"registrations":false,"approval_required":true,"invites_enabled":false,"configuration":{"statuses":{"max_characters":5000,"max_media_attachments":6,"characters_reserved_per_url":25,"supported_mime_types":["text/plain","text/markdown"]},"media_attachments":{"supported_mime_types":["image/jpeg"],"image_size_limit":10485760,"image_matrix_limit":16777216,"video_size_limit":41943040,"video_frame_rate_limit":60,"video_matrix_limit":16777216},"polls":{"max_options":6,"max_characters_per_option":50,"min_expiration":300,"max_expiration":2629746},"accounts":{"allow_custom_css":true,"max_featured_tags":10,"max_profile_fields":6},"emojis":{"emoji_size_limit":51200}},"urls":{"streaming_api":"wss://gts.example.com"},"stats":{"domain_count":2,"status_count":16,"user_count":1},"thumbnail":"https://gts.example.com/assets/logo.png","max_toot_chars":5000}"#;
+
+    #[tokio::test]
+    async fn test_get_instance_v2_falls_back_to_v1() {
+        let server = StubServer::start().await;
+        server
+            .route("GET", "/api/v2/instance", 404, r#"{"error":"Not Found"}"#)
+            .route("GET", "/api/v1/instance", 200, INSTANCE);
+        let client = GoToSocial::new(server.base_url.clone(), None, None);
+
+        let res = client.get_instance_v2().await.unwrap();
+        assert_eq!(res.json.domain, "gts.example.com");
+        assert_eq!(res.json.version, "0.11.1 git-d8c4d4b");
+        assert_eq!(res.json.contact.email, "admin@example.com");
+        let media_attachments = res.json.configuration.media_attachments.unwrap();
+        assert_eq!(media_attachments.image_size_limit, 10485760);
+        assert_eq!(media_attachments.video_frame_rate_limit, 60);
+
+        let paths: Vec<String> = server.requests().into_iter().map(|r| r.path).collect();
+        assert_eq!(paths, vec!["/api/v2/instance", "/api/v1/instance"]);
+    }
+}
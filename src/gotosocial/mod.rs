@@ -0,0 +1,8 @@
+//! GoToSocial related modules
+
+mod entities;
+/// GoToSocial API client.
+#[allow(clippy::module_inception)]
+pub mod gotosocial;
+
+pub use gotosocial::GoToSocial;
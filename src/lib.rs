@@ -1,7 +1,7 @@
 #![deny(missing_debug_implementations)]
 #![cfg_attr(docsrs, feature(doc_cfg))]
 //! # Megalodon
-//! The `megalodon` is a client library for Mastodon and Pleroma. It provides REST API and streaming method which uses WebSocket. By using this library, you can take Mastodon and Pleroma with the same interface.
+//! The `megalodon` is a client library for Mastodon, Pleroma, Misskey, GoToSocial and Akkoma. It provides REST API and streaming method which uses WebSocket. By using this library, you can take Mastodon and Pleroma with the same interface.
 //!
 //! ## Making Mastodon request
 //! For a request without authentication.
@@ -23,6 +23,28 @@
 //! # }
 //! ```
 //!
+//! ## Configuring HTTP client
+//! [`ClientBuilder`] shares one HTTP client across all requests, and configures timeouts, a proxy and so on.
+//!
+//! ```rust
+//! # use megalodon;
+//! # use megalodon::error::Error;
+//! # use std::time::Duration;
+//! #
+//! # async fn run() -> Result<(), Error> {
+//! let client = megalodon::ClientBuilder::new(
+//!   megalodon::SNS::Mastodon,
+//!   String::from("https://fedibird.com"),
+//! )
+//! .access_token(String::from("your access token"))
+//! .timeout(Duration::from_secs(30))
+//! .build()?;
+//! let res = client.verify_account_credentials().await?;
+//! println!("{:#?}", res.json());
+//! # Ok(())
+//! # }
+//! ```
+//!
 //! ## Making Mastodon request with authentication
 //! For a request with authentication.
 //!
@@ -43,18 +65,33 @@
 //! # }
 //! ```
 
+use reqwest::header::HeaderMap;
 use serde::{Deserialize, Serialize};
-use std::{fmt, str::FromStr};
+use std::{fmt, str::FromStr, time::Duration};
 
+pub mod akkoma;
+pub mod capabilities;
+pub mod credential_store;
 pub mod default;
 pub mod entities;
 pub mod error;
+pub mod filtering;
+pub mod gotosocial;
+pub mod loopback;
 pub mod mastodon;
 pub mod megalodon;
+pub mod misskey;
+pub mod nodeinfo;
 pub mod oauth;
+pub mod pagination;
 pub mod pleroma;
+pub mod rate_limit;
 pub mod response;
 pub mod streaming;
+pub mod token_manager;
+
+#[cfg(test)]
+mod test_server;
 
 pub use self::megalodon::Megalodon;
 pub use streaming::Streaming;
@@ -63,46 +100,178 @@ pub use streaming::Streaming;
 struct Instance {
     title: String,
     uri: String,
+    account_domain: Option<String>,
     urls: entities::URLs,
     version: String,
     pleroma: Option<pleroma::entities::instance::PleromaConfig>,
 }
 
-/// Detect which SNS the provided URL is. To detect SNS, the URL has to open `/api/v1/instance` or `/api/meta` endpoint.
-pub async fn detector(url: &str) -> Result<SNS, error::Error> {
+#[derive(Deserialize, Debug)]
+struct Meta {
+    version: String,
+}
+
+/// Server which is detected by [`detector`].
+#[derive(Debug, Clone)]
+pub struct DetectedServer {
+    /// Which SNS the server is compatible with.
+    pub sns: SNS,
+    /// Name of the server software, like `mastodon` or `firefish`.
+    pub software: String,
+    /// Version of the server software.
+    pub version: String,
+    /// Features which the server reports, like `pleroma_emoji_reactions`.
+    pub features: Vec<String>,
+}
+
+impl DetectedServer {
+    /// Get capabilities of the server without sending any requests.
+    pub fn capabilities(&self) -> capabilities::Capabilities {
+        capabilities::Capabilities::new(&self.sns, &self.version, &self.features)
+    }
+}
+
+/// Detect which SNS the provided URL is.
+/// It reads NodeInfo at first, and probes `/api/v1/instance` and `/api/meta` endpoints when NodeInfo does not tell the SNS.
+pub async fn detector(url: &str) -> Result<DetectedServer, error::Error> {
     let client = reqwest::Client::builder().user_agent("megalodon").build()?;
-    let res = client
-        .get(format!("{}{}", url, "/api/v1/instance"))
-        .send()
-        .await;
-
-    match res {
-        Ok(res) => {
-            let obj = res.json::<Instance>().await;
-            match obj {
-                Ok(json) => {
-                    if let Some(_pleroma) = json.pleroma {
-                        Ok(SNS::Pleroma)
-                    } else {
-                        Ok(SNS::Mastodon)
-                    }
-                }
-                Err(err) => Err(err.into()),
+    let nodeinfo = match nodeinfo::fetch(&client, url).await {
+        Ok(nodeinfo) => {
+            if let Some(sns) = sns_from_software(&nodeinfo.software.name) {
+                return Ok(DetectedServer {
+                    sns,
+                    features: nodeinfo.features(),
+                    software: nodeinfo.software.name,
+                    version: nodeinfo.software.version,
+                });
             }
+            log::info!("Unknown software: {}", nodeinfo.software.name);
+            Some(nodeinfo)
         }
-        Err(_) => {
-            let client = reqwest::Client::new();
-            let res = client.post(format!("{}{}", url, "/api/meta")).send().await;
-            match res {
-                Ok(_) => Ok(SNS::Misskey),
-                Err(err) => Err(err.into()),
-            }
+        Err(err) => {
+            log::info!("Failed to get NodeInfo: {}", err);
+            None
         }
+    };
+
+    let mut server = match detect_from_instance(&client, url).await {
+        Ok(server) => server,
+        Err(err) => detect_from_meta(&client, url).await.map_err(|e| {
+            log::info!("Failed to get meta: {}", e);
+            err
+        })?,
+    };
+    // NodeInfo knows the actual software, even if it is not supported.
+    if let Some(nodeinfo) = nodeinfo {
+        server.features.extend(nodeinfo.features());
+        server.software = nodeinfo.software.name;
+        server.version = nodeinfo.software.version;
     }
+    Ok(server)
+}
+
+fn sns_from_software(name: &str) -> Option<SNS> {
+    match name.to_lowercase().as_str() {
+        "mastodon" | "hometown" | "fedibird" => Some(SNS::Mastodon),
+        "pleroma" => Some(SNS::Pleroma),
+        "akkoma" => Some(SNS::Akkoma),
+        "gotosocial" => Some(SNS::GoToSocial),
+        "misskey" | "calckey" | "firefish" | "foundkey" | "sharkey" | "iceshrimp"
+        | "cherrypick" => Some(SNS::Misskey),
+        _ => None,
+    }
+}
+
+async fn detect_from_instance(
+    client: &reqwest::Client,
+    url: &str,
+) -> Result<DetectedServer, error::Error> {
+    let url = format!("{}{}", url, "/api/v1/instance");
+    let res = client.get(&url).send().await?;
+    let status = res.status();
+    if !status.is_success() {
+        return Err(error::Error::new_http(
+            res.text().await.unwrap_or_default(),
+            error::Kind::from_status(status.as_u16()),
+            Some(url),
+            Some(status.as_u16()),
+        ));
+    }
+    let json = res.json::<Instance>().await?;
+
+    if let Some(pleroma) = json.pleroma {
+        // Pleroma and Akkoma report a version like `2.7.2 (compatible; Akkoma 3.9.3)`.
+        let compatible = json
+            .version
+            .split_once("(compatible; ")
+            .and_then(|(_, compatible)| compatible.trim_end_matches(')').split_once(' '));
+        let (software, version) = match compatible {
+            Some((software, version)) => (software.to_lowercase(), version.to_string()),
+            None => ("pleroma".to_string(), json.version.clone()),
+        };
+        Ok(DetectedServer {
+            sns: if software == "akkoma" {
+                SNS::Akkoma
+            } else {
+                SNS::Pleroma
+            },
+            software,
+            version,
+            features: pleroma.metadata.features,
+        })
+    } else if json.account_domain.is_some() || json.version.contains(" git-") {
+        Ok(DetectedServer {
+            sns: SNS::GoToSocial,
+            software: "gotosocial".to_string(),
+            version: json
+                .version
+                .split_whitespace()
+                .next()
+                .unwrap_or_default()
+                .to_string(),
+            features: Vec::new(),
+        })
+    } else {
+        Ok(DetectedServer {
+            sns: SNS::Mastodon,
+            software: "mastodon".to_string(),
+            version: json.version,
+            features: Vec::new(),
+        })
+    }
+}
+
+async fn detect_from_meta(
+    client: &reqwest::Client,
+    url: &str,
+) -> Result<DetectedServer, error::Error> {
+    let url = format!("{}{}", url, "/api/meta");
+    let res = client
+        .post(&url)
+        .json(&serde_json::json!({}))
+        .send()
+        .await?;
+    let status = res.status();
+    if !status.is_success() {
+        return Err(error::Error::new_http(
+            res.text().await.unwrap_or_default(),
+            error::Kind::from_status(status.as_u16()),
+            Some(url),
+            Some(status.as_u16()),
+        ));
+    }
+    let json = res.json::<Meta>().await?;
+    Ok(DetectedServer {
+        sns: SNS::Misskey,
+        software: "misskey".to_string(),
+        version: json.version,
+        features: Vec::new(),
+    })
 }
 
 /// Which SNS.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum SNS {
     /// SNS is Mastodon.
     Mastodon,
@@ -110,6 +279,10 @@ pub enum SNS {
     Pleroma,
     /// SNS is Misskey.
     Misskey,
+    /// SNS is GoToSocial.
+    GoToSocial,
+    /// SNS is Akkoma.
+    Akkoma,
 }
 
 impl fmt::Display for SNS {
@@ -118,6 +291,8 @@ impl fmt::Display for SNS {
             SNS::Mastodon => write!(f, "mastodon"),
             SNS::Pleroma => write!(f, "pleroma"),
             SNS::Misskey => write!(f, "misskey"),
+            SNS::GoToSocial => write!(f, "gotosocial"),
+            SNS::Akkoma => write!(f, "akkoma"),
         }
     }
 }
@@ -130,6 +305,8 @@ impl FromStr for SNS {
             "mastodon" => Ok(SNS::Mastodon),
             "pleroma" => Ok(SNS::Pleroma),
             "misskey" => Ok(SNS::Misskey),
+            "gotosocial" => Ok(SNS::GoToSocial),
+            "akkoma" => Ok(SNS::Akkoma),
             &_ => Err(format!("Unknown sns: {}", s)),
         }
     }
@@ -147,9 +324,386 @@ pub fn generator(
             let pleroma = pleroma::Pleroma::new(base_url, access_token, user_agent);
             Box::new(pleroma)
         }
-        _ => {
+        SNS::Misskey => {
+            let misskey = misskey::Misskey::new(base_url, access_token, user_agent);
+            Box::new(misskey)
+        }
+        SNS::GoToSocial => {
+            let gotosocial = gotosocial::GoToSocial::new(base_url, access_token, user_agent);
+            Box::new(gotosocial)
+        }
+        SNS::Akkoma => {
+            let akkoma = akkoma::Akkoma::new(base_url, access_token, user_agent);
+            Box::new(akkoma)
+        }
+        SNS::Mastodon => {
             let mastodon = mastodon::Mastodon::new(base_url, access_token, user_agent);
             Box::new(mastodon)
         }
     }
 }
+
+/// Builder of an API client which satisfies megalodon trait.
+/// The built client shares one HTTP client, so connections are pooled across all requests.
+#[derive(Debug)]
+pub struct ClientBuilder {
+    sns: SNS,
+    base_url: String,
+    access_token: Option<String>,
+    user_agent: Option<String>,
+    timeout: Option<Duration>,
+    connect_timeout: Option<Duration>,
+    proxy: Option<reqwest::Proxy>,
+    default_headers: HeaderMap,
+    root_certificates: Vec<reqwest::Certificate>,
+    http_client: Option<reqwest::Client>,
+    retry_policy: Option<rate_limit::RetryPolicy>,
+    token_manager: Option<token_manager::TokenManager>,
+    streaming_options: streaming::StreamingOptions,
+}
+
+impl ClientBuilder {
+    /// Create a new [`ClientBuilder`].
+    pub fn new(sns: SNS, base_url: String) -> Self {
+        Self {
+            sns,
+            base_url,
+            access_token: None,
+            user_agent: None,
+            timeout: None,
+            connect_timeout: None,
+            proxy: None,
+            default_headers: HeaderMap::new(),
+            root_certificates: Vec::new(),
+            http_client: None,
+            retry_policy: None,
+            token_manager: None,
+            streaming_options: Default::default(),
+        }
+    }
+
+    /// Create a new [`ClientBuilder`] from the credential in the store.
+    /// The access token is managed by [`token_manager::TokenManager`], and refreshed tokens are saved to the store.
+    pub async fn from_credential_store(
+        store: std::sync::Arc<dyn credential_store::CredentialStore>,
+        key: &credential_store::CredentialKey,
+    ) -> Result<Self, error::Error> {
+        let Some(credential) = store.load(key).await? else {
+            return Err(error::Error::new_own(
+                format!("Credential for {} is not stored", key.account),
+                error::Kind::NotFoundError,
+                Some(key.instance_url.clone()),
+                None,
+            ));
+        };
+        // Refreshed tokens are saved by a single task, so they are stored in the order they are refreshed.
+        let (saver, mut refreshed) =
+            tokio::sync::mpsc::unbounded_channel::<credential_store::Credential>();
+        let persisted_key = key.clone();
+        tokio::spawn(async move {
+            while let Some(credential) = refreshed.recv().await {
+                if let Err(err) = store.save(&persisted_key, &credential).await {
+                    log::error!("Failed to save the refreshed token: {}", err);
+                }
+            }
+        });
+        let persisted = credential.clone();
+        let manager = token_manager::TokenManager::new(
+            credential.app.client_id,
+            credential.app.client_secret,
+            credential.token,
+        )
+        .on_refresh(move |token| {
+            let credential = credential_store::Credential {
+                token: token.clone(),
+                ..persisted.clone()
+            };
+            if saver.send(credential).is_err() {
+                log::error!("Failed to save the refreshed token: the saving task has stopped");
+            }
+        });
+
+        Ok(Self::new(credential.sns, key.instance_url.clone()).token_manager(manager))
+    }
+
+    /// Set an access token.
+    pub fn access_token(mut self, access_token: String) -> Self {
+        self.access_token = Some(access_token);
+        self
+    }
+
+    /// Set a User-Agent. Default is [`default::DEFAULT_UA`].
+    pub fn user_agent(mut self, user_agent: String) -> Self {
+        self.user_agent = Some(user_agent);
+        self
+    }
+
+    /// Set a timeout for each request, from connecting until the response body has finished.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Set a timeout for only the connect phase.
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Send requests through the proxy.
+    pub fn proxy(mut self, proxy: reqwest::Proxy) -> Self {
+        self.proxy = Some(proxy);
+        self
+    }
+
+    /// Set headers which are sent with every request.
+    pub fn default_headers(mut self, headers: HeaderMap) -> Self {
+        self.default_headers = headers;
+        self
+    }
+
+    /// Add a custom root certificate to trust.
+    pub fn add_root_certificate(mut self, certificate: reqwest::Certificate) -> Self {
+        self.root_certificates.push(certificate);
+        self
+    }
+
+    /// Use the given HTTP client instead of building a new one.
+    /// When it is set, user agent, timeouts, proxy, default headers and root certificates are not applied to REST API requests.
+    pub fn http_client(mut self, client: reqwest::Client) -> Self {
+        self.http_client = Some(client);
+        self
+    }
+
+    /// Retry failed requests according to the policy. Requests are not retried by default.
+    pub fn retry_policy(mut self, retry_policy: rate_limit::RetryPolicy) -> Self {
+        self.retry_policy = Some(retry_policy);
+        self
+    }
+
+    /// Refresh the access token automatically with the manager. The access token of the manager is used instead of [`ClientBuilder::access_token`].
+    /// Misskey does not have refresh tokens, so only the current access token of the manager is used.
+    pub fn token_manager(mut self, token_manager: token_manager::TokenManager) -> Self {
+        self.token_manager = Some(token_manager);
+        self
+    }
+
+    /// Set options of streaming connections, like reconnection backoff and read timeout.
+    pub fn streaming_options(mut self, streaming_options: streaming::StreamingOptions) -> Self {
+        self.streaming_options = streaming_options;
+        self
+    }
+
+    /// Build an API client which satisfies megalodon trait.
+    #[allow(clippy::result_large_err)]
+    pub fn build(self) -> Result<Box<dyn Megalodon + Send + Sync>, error::Error> {
+        let http_client = match self.http_client {
+            Some(client) => client,
+            None => {
+                let ua = self
+                    .user_agent
+                    .clone()
+                    .unwrap_or(default::DEFAULT_UA.to_string());
+                let mut builder = reqwest::Client::builder()
+                    .user_agent(ua)
+                    .default_headers(self.default_headers);
+                if let Some(timeout) = self.timeout {
+                    builder = builder.timeout(timeout);
+                }
+                if let Some(timeout) = self.connect_timeout {
+                    builder = builder.connect_timeout(timeout);
+                }
+                if let Some(proxy) = self.proxy {
+                    builder = builder.proxy(proxy);
+                }
+                for certificate in self.root_certificates {
+                    builder = builder.add_root_certificate(certificate);
+                }
+                builder.build()?
+            }
+        };
+
+        match self.sns {
+            SNS::Pleroma => {
+                let mut pleroma = pleroma::Pleroma::with_client(
+                    self.base_url,
+                    self.access_token,
+                    self.user_agent,
+                    http_client,
+                );
+                pleroma.set_retry_policy(self.retry_policy);
+                pleroma.set_token_manager(self.token_manager);
+                pleroma.set_streaming_options(self.streaming_options);
+                Ok(Box::new(pleroma))
+            }
+            SNS::Misskey => {
+                let access_token = match &self.token_manager {
+                    Some(manager) => Some(manager.token_data().access_token),
+                    None => self.access_token,
+                };
+                let mut misskey = misskey::Misskey::with_client(
+                    self.base_url,
+                    access_token,
+                    self.user_agent,
+                    http_client,
+                );
+                misskey.set_retry_policy(self.retry_policy);
+                misskey.set_streaming_options(self.streaming_options);
+                Ok(Box::new(misskey))
+            }
+            SNS::GoToSocial => {
+                let mut gotosocial = gotosocial::GoToSocial::with_client(
+                    self.base_url,
+                    self.access_token,
+                    self.user_agent,
+                    http_client,
+                );
+                gotosocial.set_retry_policy(self.retry_policy);
+                gotosocial.set_token_manager(self.token_manager);
+                gotosocial.set_streaming_options(self.streaming_options);
+                Ok(Box::new(gotosocial))
+            }
+            SNS::Akkoma => {
+                let mut akkoma = akkoma::Akkoma::with_client(
+                    self.base_url,
+                    self.access_token,
+                    self.user_agent,
+                    http_client,
+                );
+                akkoma.set_retry_policy(self.retry_policy);
+                akkoma.set_token_manager(self.token_manager);
+                akkoma.set_streaming_options(self.streaming_options);
+                Ok(Box::new(akkoma))
+            }
+            SNS::Mastodon => {
+                let mut mastodon = mastodon::Mastodon::with_client(
+                    self.base_url,
+                    self.access_token,
+                    self.user_agent,
+                    http_client,
+                );
+                mastodon.set_retry_policy(self.retry_policy);
+                mastodon.set_token_manager(self.token_manager);
+                mastodon.set_streaming_options(self.streaming_options);
+                Ok(Box::new(mastodon))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_server::StubServer;
+
+    const MASTODON_INSTANCE: &str = r#"{"title":"Mastodon","uri":"mastodon.example","urls":{"streaming_api":"wss://mastodon.example"},"version":"4.2.0"}"#;
+
+    fn route_nodeinfo(server: &StubServer, name: &str, version: &str) {
+        server
+            .route(
+                "GET",
+                "/.well-known/nodeinfo",
+                200,
+                &format!(
+                    r#"{{"links":[{{"rel":"http://nodeinfo.diaspora.software/ns/schema/2.0","href":"{}/nodeinfo/2.0"}}]}}"#,
+                    server.base_url
+                ),
+            )
+            .route(
+                "GET",
+                "/nodeinfo/2.0",
+                200,
+                &format!(
+                    r#"{{"version":"2.0","software":{{"name":"{}","version":"{}"}},"metadata":{{"features":["pleroma_api"]}}}}"#,
+                    name, version
+                ),
+            );
+    }
+
+    #[tokio::test]
+    async fn test_detect_from_nodeinfo() {
+        for (name, sns) in [
+            ("mastodon", "mastodon"),
+            ("hometown", "mastodon"),
+            ("fedibird", "mastodon"),
+            ("pleroma", "pleroma"),
+            ("akkoma", "akkoma"),
+            ("gotosocial", "gotosocial"),
+            ("misskey", "misskey"),
+            ("firefish", "misskey"),
+            ("sharkey", "misskey"),
+        ] {
+            let server = StubServer::start().await;
+            route_nodeinfo(&server, name, "1.0.0");
+
+            let detected = detector(&server.base_url).await.unwrap();
+            assert_eq!(detected.sns.to_string(), sns, "{}", name);
+            assert_eq!(detected.software, name);
+            assert_eq!(detected.version, "1.0.0");
+            assert_eq!(detected.features, vec!["pleroma_api".to_string()]);
+            // The SNS is known from NodeInfo, so the other endpoints are not probed.
+            assert_eq!(server.requests().len(), 2);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_detect_unknown_software_with_probes() {
+        let server = StubServer::start().await;
+        route_nodeinfo(&server, "hollo", "0.3.0");
+        server.route("GET", "/api/v1/instance", 200, MASTODON_INSTANCE);
+
+        let detected = detector(&server.base_url).await.unwrap();
+        assert!(matches!(detected.sns, SNS::Mastodon));
+        assert_eq!(detected.software, "hollo");
+        assert_eq!(detected.version, "0.3.0");
+    }
+
+    #[tokio::test]
+    async fn test_detect_without_nodeinfo() {
+        let server = StubServer::start().await;
+        server.route("GET", "/api/v1/instance", 200, MASTODON_INSTANCE);
+
+        let detected = detector(&server.base_url).await.unwrap();
+        assert!(matches!(detected.sns, SNS::Mastodon));
+        assert_eq!(detected.software, "mastodon");
+        assert_eq!(detected.version, "4.2.0");
+
+        let server = StubServer::start().await;
+        server.route("POST", "/api/meta", 200, r#"{"version":"13.14.2"}"#);
+
+        let detected = detector(&server.base_url).await.unwrap();
+        assert!(matches!(detected.sns, SNS::Misskey));
+        assert_eq!(detected.version, "13.14.2");
+    }
+
+    #[tokio::test]
+    async fn test_detect_fails_when_meta_is_not_found() {
+        let server = StubServer::start().await;
+
+        let err = detector(&server.base_url).await.unwrap_err();
+        let error::Error::OwnError(err) = err else {
+            panic!("Unexpected error: {:?}", err);
+        };
+        assert!(matches!(err.kind, error::Kind::NotFoundError));
+        assert!(server
+            .requests()
+            .iter()
+            .any(|request| request.path == "/api/meta"));
+    }
+
+    #[tokio::test]
+    async fn test_detect_gotosocial_with_probes() {
+        for instance in [
+            r#"{"title":"GoToSocial","uri":"gts.example","account_domain":"example.com","urls":{"streaming_api":"wss://gts.example"},"version":"0.13.0"}"#,
+            r#"{"title":"GoToSocial","uri":"gts.example","urls":{"streaming_api":"wss://gts.example"},"version":"0.13.0 git-ccbbc7b"}"#,
+        ] {
+            let server = StubServer::start().await;
+            server.route("GET", "/api/v1/instance", 200, instance);
+
+            let detected = detector(&server.base_url).await.unwrap();
+            assert!(matches!(detected.sns, SNS::GoToSocial));
+            assert_eq!(detected.software, "gotosocial");
+            assert_eq!(detected.version, "0.13.0");
+        }
+    }
+}
@@ -0,0 +1,272 @@
+//! Loopback redirect modules, which complete OAuth authorization for CLI and desktop applications
+use crate::error::{Error, Kind};
+use crate::megalodon::{AppInputOptions, Megalodon};
+use crate::oauth::TokenData;
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+
+const CALLBACK_PATH: &str = "/callback";
+/// How long to wait for a request after a connection is accepted.
+const CONNECTION_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Authorize the application with a loopback redirect URI, like `http://127.0.0.1:<port>/callback`.
+///
+/// This starts a temporary HTTP listener on `127.0.0.1:port`, registers the application with the loopback redirect URI,
+/// and passes the authorize URL to `open_url`, which should open it in a browser or show it to the user.
+/// After the user authorizes the application, the server redirects the browser to the listener with `code` and `state`.
+/// `state` is verified, and then the code is exchanged for an access token.
+///
+/// When `port` is `0`, a free port is chosen by OS.
+/// `redirect_uris` in `options` is overwritten with the loopback redirect URI.
+/// This waits for the callback forever, so please wrap it with [`tokio::time::timeout`] if necessary.
+///
+/// ```no_run
+/// # use megalodon::error::Error;
+/// #
+/// # async fn run() -> Result<(), Error> {
+/// let client = megalodon::generator(
+///   megalodon::SNS::Mastodon,
+///   String::from("https://fedibird.com"),
+///   None,
+///   None,
+/// );
+/// let token = megalodon::loopback::authorize(
+///   client.as_ref(),
+///   String::from("megalodon"),
+///   &megalodon::megalodon::AppInputOptions::default(),
+///   0,
+///   |url| println!("Open {} in your browser", url),
+/// )
+/// .await?;
+/// println!("{}", token.access_token);
+/// # Ok(())
+/// # }
+/// ```
+pub async fn authorize(
+    client: &(dyn Megalodon + Send + Sync),
+    client_name: String,
+    options: &AppInputOptions,
+    port: u16,
+    open_url: impl FnOnce(&str),
+) -> Result<TokenData, Error> {
+    let listener = TcpListener::bind(("127.0.0.1", port)).await?;
+    let redirect_uri = format!(
+        "http://127.0.0.1:{}{}",
+        listener.local_addr()?.port(),
+        CALLBACK_PATH
+    );
+
+    let options = AppInputOptions {
+        redirect_uris: Some(redirect_uri.clone()),
+        ..options.clone()
+    };
+    let app = client.register_app(client_name, &options).await?;
+    let Some(url) = &app.url else {
+        return Err(Error::new_own(
+            "Authorize URL is not generated".to_string(),
+            Kind::ParseError,
+            None,
+            None,
+        ));
+    };
+    open_url(url);
+
+    // Browsers and other programs may connect to the listener before the callback, and some of them send nothing.
+    // So each connection is handled in its own task, and errors of the connection are only logged.
+    let (sender, mut callbacks) = tokio::sync::mpsc::channel::<HashMap<String, String>>(1);
+    let params = loop {
+        tokio::select! {
+            accepted = listener.accept() => {
+                let (stream, addr) = accepted?;
+                let sender = sender.clone();
+                tokio::spawn(async move {
+                    match tokio::time::timeout(CONNECTION_TIMEOUT, receive_callback(stream)).await {
+                        Ok(Ok(Some(params))) => {
+                            let _ = sender.send(params).await;
+                        }
+                        Ok(Ok(None)) => {}
+                        Ok(Err(err)) => {
+                            log::warn!("Failed to receive a request from {}: {}", addr, err)
+                        }
+                        Err(_) => log::warn!("Timed out receiving a request from {}", addr),
+                    }
+                });
+            }
+            Some(params) = callbacks.recv() => break params,
+        }
+    };
+
+    if let Some(error) = params.get("error") {
+        return Err(Error::new_own(
+            params
+                .get("error_description")
+                .cloned()
+                .unwrap_or_else(|| error.clone()),
+            Kind::UnauthorizedError,
+            Some(redirect_uri),
+            None,
+        ));
+    }
+    // Misskey does not have `state`, and it returns `session` instead of `code`.
+    if app.state.is_some() && !app.verify_state(params.get("state").map_or("", String::as_str)) {
+        return Err(Error::new_own(
+            "state does not match".to_string(),
+            Kind::UnauthorizedError,
+            Some(redirect_uri),
+            None,
+        ));
+    }
+    let Some(code) = params.get("code").or_else(|| params.get("session")) else {
+        return Err(Error::new_own(
+            "code is not found in the callback".to_string(),
+            Kind::UnauthorizedError,
+            Some(redirect_uri),
+            None,
+        ));
+    };
+
+    client
+        .fetch_access_token(
+            app.client_id,
+            app.client_secret,
+            code.clone(),
+            redirect_uri,
+            app.code_verifier,
+        )
+        .await
+}
+
+/// Read a request and respond to it. Returns query parameters if it is a request to the callback path.
+async fn receive_callback(mut stream: TcpStream) -> Result<Option<HashMap<String, String>>, Error> {
+    let mut request_line = String::new();
+    {
+        let mut reader = BufReader::new(&mut stream);
+        reader.read_line(&mut request_line).await?;
+        // Discard headers.
+        let mut line = String::new();
+        while reader.read_line(&mut line).await? > 0 && line != "\r\n" && line != "\n" {
+            line.clear();
+        }
+    }
+
+    let params = parse_request_line(&request_line);
+    let response = match &params {
+        Some(_) => "HTTP/1.1 200 OK\r\nContent-Type: text/html; charset=utf-8\r\nConnection: close\r\n\r\n<html><body>Authorization is completed. You can close this window.</body></html>",
+        None => "HTTP/1.1 404 Not Found\r\nConnection: close\r\n\r\n",
+    };
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await?;
+    Ok(params)
+}
+
+/// Parse a request line, like `GET /callback?code=xxx&state=yyy HTTP/1.1`.
+fn parse_request_line(line: &str) -> Option<HashMap<String, String>> {
+    let mut parts = line.split_whitespace();
+    if parts.next()? != "GET" {
+        return None;
+    }
+    let url = url::Url::parse(&format!("http://127.0.0.1{}", parts.next()?)).ok()?;
+    if url.path() != CALLBACK_PATH {
+        return None;
+    }
+    Some(url.query_pairs().into_owned().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::StubServer;
+
+    #[test]
+    fn test_parse_request_line() {
+        let params =
+            parse_request_line("GET /callback?code=abc%2Fd&state=xyz HTTP/1.1\r\n").unwrap();
+        assert_eq!(params.get("code").unwrap(), "abc/d");
+        assert_eq!(params.get("state").unwrap(), "xyz");
+
+        assert!(parse_request_line("GET /favicon.ico HTTP/1.1\r\n").is_none());
+        assert!(parse_request_line("POST /callback?code=abc HTTP/1.1\r\n").is_none());
+        assert!(parse_request_line("").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_authorize_skips_stray_connections() {
+        let port = std::net::TcpListener::bind(("127.0.0.1", 0))
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let redirect_uri = format!("http://127.0.0.1:{}/callback", port);
+        let server = StubServer::start().await;
+        server
+            .route(
+                "POST",
+                "/api/v1/apps",
+                200,
+                &format!(
+                    r#"{{"id":"1","name":"megalodon","website":null,"redirect_uri":"{}","client_id":"client_id","client_secret":"client_secret"}}"#,
+                    redirect_uri
+                ),
+            )
+            .route(
+                "POST",
+                "/oauth/token",
+                200,
+                r#"{"access_token":"token","token_type":"Bearer","scope":"read","created_at":1000}"#,
+            );
+        let client = crate::mastodon::Mastodon::new(server.base_url.clone(), None, None);
+
+        let token = authorize(
+            &client,
+            "megalodon".to_string(),
+            &AppInputOptions::default(),
+            port,
+            |url| {
+                let state = url::Url::parse(url)
+                    .unwrap()
+                    .query_pairs()
+                    .find(|(key, _)| key == "state")
+                    .unwrap()
+                    .1
+                    .into_owned();
+                tokio::spawn(async move {
+                    // A connection which sends nothing, like a preconnect of browsers.
+                    let idle = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+                    // A connection which is closed without any request.
+                    drop(TcpStream::connect(("127.0.0.1", port)).await.unwrap());
+
+                    let mut favicon = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+                    favicon
+                        .write_all(b"GET /favicon.ico HTTP/1.1\r\n\r\n")
+                        .await
+                        .unwrap();
+
+                    let mut callback = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+                    callback
+                        .write_all(
+                            format!("GET /callback?code=abc&state={} HTTP/1.1\r\n\r\n", state)
+                                .as_bytes(),
+                        )
+                        .await
+                        .unwrap();
+                    let mut response = String::new();
+                    tokio::io::AsyncReadExt::read_to_string(&mut callback, &mut response)
+                        .await
+                        .unwrap();
+                    assert!(response.starts_with("HTTP/1.1 200 OK"));
+                    drop(idle);
+                });
+            },
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(token.access_token, "token");
+        let requests = server.requests();
+        let fetch = requests.iter().find(|r| r.path == "/oauth/token").unwrap();
+        assert_eq!(fetch.json()["code"], "abc");
+        assert_eq!(fetch.json()["redirect_uri"], redirect_uri.as_str());
+    }
+}
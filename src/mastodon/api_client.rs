@@ -1,8 +1,10 @@
-use crate::default::DEFAULT_UA;
+use crate::default::{self, DEFAULT_UA};
 use crate::error::{Error as MegalodonError, Kind};
+use crate::rate_limit::{self, RetryPolicy};
 use crate::response::Response;
+use crate::token_manager::{self, ManagedToken};
 use reqwest::header::HeaderMap;
-use reqwest::Url;
+use reqwest::{RequestBuilder, Url};
 use serde::de::DeserializeOwned;
 use serde_json::Value;
 use std::collections::HashMap;
@@ -12,22 +14,45 @@ use std::fmt::Debug;
 pub struct APIClient {
     access_token: Option<String>,
     base_url: String,
-    user_agent: String,
+    client: reqwest::Client,
+    retry_policy: Option<RetryPolicy>,
+    token_manager: Option<ManagedToken>,
 }
 
 impl APIClient {
     pub fn new(base_url: String, access_token: Option<String>, user_agent: Option<String>) -> Self {
-        let ua: String;
-        match user_agent {
-            Some(agent) => ua = agent,
-            None => ua = DEFAULT_UA.to_string(),
-        }
+        let ua = user_agent.unwrap_or(DEFAULT_UA.to_string());
+        let client = default::http_client(&ua);
+
+        Self::with_client(base_url, access_token, client)
+    }
 
+    /// Create a new [`APIClient`] which shares the given HTTP client across all requests.
+    pub fn with_client(
+        base_url: String,
+        access_token: Option<String>,
+        client: reqwest::Client,
+    ) -> Self {
         Self {
             access_token,
             base_url,
-            user_agent: ua,
+            client,
+            retry_policy: None,
+            token_manager: None,
+        }
+    }
+
+    /// Set a policy to retry failed requests. Requests are not retried when it is `None`.
+    pub fn set_retry_policy(&mut self, retry_policy: Option<RetryPolicy>) {
+        self.retry_policy = retry_policy;
+    }
+
+    /// Set a manager which refreshes the access token. When it is set, the access token of the manager is used instead.
+    pub fn set_token_manager(&mut self, token_manager: Option<ManagedToken>) {
+        if token_manager.is_some() {
+            self.access_token = None;
         }
+        self.token_manager = token_manager;
     }
 
     pub async fn get<T>(
@@ -40,11 +65,7 @@ impl APIClient {
     {
         let url_str = format!("{}{}", self.base_url, path);
         let url = Url::parse(&*url_str)?;
-        let client = reqwest::Client::builder()
-            .user_agent(&self.user_agent)
-            .build()?;
-
-        let mut req = client.get(url);
+        let mut req = self.client.get(url);
         if let Some(token) = &self.access_token {
             req = req.bearer_auth(token);
         }
@@ -52,37 +73,7 @@ impl APIClient {
             req = req.headers(headers);
         }
 
-        let res = req.send().await?;
-        let status = res.status();
-        match status {
-            reqwest::StatusCode::OK
-            | reqwest::StatusCode::CREATED
-            | reqwest::StatusCode::ACCEPTED
-            | reqwest::StatusCode::NO_CONTENT => {
-                let res = Response::<T>::from_reqwest(res).await?;
-                Ok(res)
-            }
-            reqwest::StatusCode::PARTIAL_CONTENT => Err(MegalodonError::new_own(
-                String::from("The requested resource is still being processed"),
-                Kind::HTTPPartialContentError,
-                Some(url_str),
-                Some(status.as_u16()),
-            )),
-            _ => match res.text().await {
-                Ok(text) => Err(MegalodonError::new_own(
-                    text,
-                    Kind::HTTPStatusError,
-                    Some(url_str),
-                    Some(status.as_u16()),
-                )),
-                Err(_err) => Err(MegalodonError::new_own(
-                    "Unknown error".to_string(),
-                    Kind::HTTPStatusError,
-                    Some(url_str),
-                    Some(status.as_u16()),
-                )),
-            },
-        }
+        self.send::<T>(req, url_str, true).await
     }
 
     pub async fn post<T>(
@@ -96,11 +87,7 @@ impl APIClient {
     {
         let url_str = format!("{}{}", self.base_url, path);
         let url = Url::parse(&*url_str)?;
-        let client = reqwest::Client::builder()
-            .user_agent(&self.user_agent)
-            .build()?;
-
-        let mut req = client.post(url);
+        let mut req = self.client.post(url);
         if let Some(token) = &self.access_token {
             req = req.bearer_auth(token);
         }
@@ -108,31 +95,7 @@ impl APIClient {
             req = req.headers(headers);
         }
 
-        let res = req.json(params).send().await?;
-        let status = res.status();
-        match status {
-            reqwest::StatusCode::OK
-            | reqwest::StatusCode::CREATED
-            | reqwest::StatusCode::ACCEPTED
-            | reqwest::StatusCode::NO_CONTENT => {
-                let res = Response::<T>::from_reqwest(res).await?;
-                Ok(res)
-            }
-            _ => match res.text().await {
-                Ok(text) => Err(MegalodonError::new_own(
-                    text,
-                    Kind::HTTPStatusError,
-                    Some(url_str),
-                    Some(status.as_u16()),
-                )),
-                Err(_err) => Err(MegalodonError::new_own(
-                    "Unknown error".to_string(),
-                    Kind::HTTPStatusError,
-                    Some(url_str),
-                    Some(status.as_u16()),
-                )),
-            },
-        }
+        self.send::<T>(req.json(params), url_str, false).await
     }
 
     pub async fn post_multipart<T>(
@@ -146,11 +109,7 @@ impl APIClient {
     {
         let url_str = format!("{}{}", self.base_url, path);
         let url = Url::parse(&*url_str)?;
-        let client = reqwest::Client::builder()
-            .user_agent(&self.user_agent)
-            .build()?;
-
-        let mut req = client.post(url);
+        let mut req = self.client.post(url);
         if let Some(token) = &self.access_token {
             req = req.bearer_auth(token);
         }
@@ -158,31 +117,7 @@ impl APIClient {
             req = req.headers(headers);
         }
 
-        let res = req.multipart(params).send().await?;
-        let status = res.status();
-        match status {
-            reqwest::StatusCode::OK
-            | reqwest::StatusCode::CREATED
-            | reqwest::StatusCode::ACCEPTED
-            | reqwest::StatusCode::NO_CONTENT => {
-                let res = Response::<T>::from_reqwest(res).await?;
-                Ok(res)
-            }
-            _ => match res.text().await {
-                Ok(text) => Err(MegalodonError::new_own(
-                    text,
-                    Kind::HTTPStatusError,
-                    Some(url_str),
-                    Some(status.as_u16()),
-                )),
-                Err(_err) => Err(MegalodonError::new_own(
-                    "Unknown error".to_string(),
-                    Kind::HTTPStatusError,
-                    Some(url_str),
-                    Some(status.as_u16()),
-                )),
-            },
-        }
+        self.send::<T>(req.multipart(params), url_str, false).await
     }
 
     pub async fn put<T>(
@@ -196,11 +131,7 @@ impl APIClient {
     {
         let url_str = format!("{}{}", self.base_url, path);
         let url = Url::parse(&*url_str)?;
-        let client = reqwest::Client::builder()
-            .user_agent(&self.user_agent)
-            .build()?;
-
-        let mut req = client.put(url);
+        let mut req = self.client.put(url);
         if let Some(token) = &self.access_token {
             req = req.bearer_auth(token);
         }
@@ -208,31 +139,7 @@ impl APIClient {
             req = req.headers(headers);
         }
 
-        let res = req.json(params).send().await?;
-        let status = res.status();
-        match status {
-            reqwest::StatusCode::OK
-            | reqwest::StatusCode::CREATED
-            | reqwest::StatusCode::ACCEPTED
-            | reqwest::StatusCode::NO_CONTENT => {
-                let res = Response::<T>::from_reqwest(res).await?;
-                Ok(res)
-            }
-            _ => match res.text().await {
-                Ok(text) => Err(MegalodonError::new_own(
-                    text,
-                    Kind::HTTPStatusError,
-                    Some(url_str),
-                    Some(status.as_u16()),
-                )),
-                Err(_err) => Err(MegalodonError::new_own(
-                    "Unknown error".to_string(),
-                    Kind::HTTPStatusError,
-                    Some(url_str),
-                    Some(status.as_u16()),
-                )),
-            },
-        }
+        self.send::<T>(req.json(params), url_str, true).await
     }
 
     pub async fn put_multipart<T>(
@@ -246,11 +153,7 @@ impl APIClient {
     {
         let url_str = format!("{}{}", self.base_url, path);
         let url = Url::parse(&*url_str)?;
-        let client = reqwest::Client::builder()
-            .user_agent(&self.user_agent)
-            .build()?;
-
-        let mut req = client.put(url);
+        let mut req = self.client.put(url);
         if let Some(token) = &self.access_token {
             req = req.bearer_auth(token);
         }
@@ -258,31 +161,7 @@ impl APIClient {
             req = req.headers(headers);
         }
 
-        let res = req.multipart(params).send().await?;
-        let status = res.status();
-        match status {
-            reqwest::StatusCode::OK
-            | reqwest::StatusCode::CREATED
-            | reqwest::StatusCode::ACCEPTED
-            | reqwest::StatusCode::NO_CONTENT => {
-                let res = Response::<T>::from_reqwest(res).await?;
-                Ok(res)
-            }
-            _ => match res.text().await {
-                Ok(text) => Err(MegalodonError::new_own(
-                    text,
-                    Kind::HTTPStatusError,
-                    Some(url_str),
-                    Some(status.as_u16()),
-                )),
-                Err(_err) => Err(MegalodonError::new_own(
-                    "Unknown error".to_string(),
-                    Kind::HTTPStatusError,
-                    Some(url_str),
-                    Some(status.as_u16()),
-                )),
-            },
-        }
+        self.send::<T>(req.multipart(params), url_str, true).await
     }
 
     pub async fn patch<T>(
@@ -296,11 +175,7 @@ impl APIClient {
     {
         let url_str = format!("{}{}", self.base_url, path);
         let url = Url::parse(&*url_str)?;
-        let client = reqwest::Client::builder()
-            .user_agent(&self.user_agent)
-            .build()?;
-
-        let mut req = client.patch(url);
+        let mut req = self.client.patch(url);
         if let Some(token) = &self.access_token {
             req = req.bearer_auth(token);
         }
@@ -308,31 +183,7 @@ impl APIClient {
             req = req.headers(headers);
         }
 
-        let res = req.json(params).send().await?;
-        let status = res.status();
-        match status {
-            reqwest::StatusCode::OK
-            | reqwest::StatusCode::CREATED
-            | reqwest::StatusCode::ACCEPTED
-            | reqwest::StatusCode::NO_CONTENT => {
-                let res = Response::<T>::from_reqwest(res).await?;
-                Ok(res)
-            }
-            _ => match res.text().await {
-                Ok(text) => Err(MegalodonError::new_own(
-                    text,
-                    Kind::HTTPStatusError,
-                    Some(url_str),
-                    Some(status.as_u16()),
-                )),
-                Err(_err) => Err(MegalodonError::new_own(
-                    "Unknown error".to_string(),
-                    Kind::HTTPStatusError,
-                    Some(url_str),
-                    Some(status.as_u16()),
-                )),
-            },
-        }
+        self.send::<T>(req.json(params), url_str, false).await
     }
 
     pub async fn delete<T>(
@@ -346,11 +197,7 @@ impl APIClient {
     {
         let url_str = format!("{}{}", self.base_url, path);
         let url = Url::parse(&*url_str)?;
-        let client = reqwest::Client::builder()
-            .user_agent(&self.user_agent)
-            .build()?;
-
-        let mut req = client.delete(url);
+        let mut req = self.client.delete(url);
         if let Some(token) = &self.access_token {
             req = req.bearer_auth(token);
         }
@@ -358,7 +205,82 @@ impl APIClient {
             req = req.headers(headers);
         }
 
-        let res = req.json(params).send().await?;
+        self.send::<T>(req.json(params), url_str, true).await
+    }
+
+    /// Send the request, and retry it according to the retry policy.
+    /// When the token manager is set, the request is retried once with the refreshed access token if it responds 401.
+    /// If refreshing fails, the 401 error is returned as it is.
+    /// Multipart requests can not be cloned, so they are never retried.
+    async fn send<T>(
+        &self,
+        req: RequestBuilder,
+        url_str: String,
+        idempotent: bool,
+    ) -> Result<Response<T>, MegalodonError>
+    where
+        T: DeserializeOwned + Debug,
+    {
+        let mut req = req;
+        let mut attempt: u32 = 0;
+        let mut refreshed = false;
+        loop {
+            let next = req.try_clone();
+            let access_token = match &self.token_manager {
+                Some(manager) => Some(manager.access_token().await),
+                None => None,
+            };
+            let sending = match &access_token {
+                Some(token) => req.bearer_auth(token),
+                None => req,
+            };
+            let err = match self.execute::<T>(sending, &url_str).await {
+                Ok(res) => return Ok(res),
+                Err(err) => err,
+            };
+            let Some(next) = next else {
+                return Err(err);
+            };
+            if let (Some(manager), Some(token)) = (&self.token_manager, &access_token) {
+                if !refreshed && token_manager::is_unauthorized(&err) {
+                    match manager.refresh_rejected(token).await {
+                        Ok(true) => {
+                            log::info!("Retrying {} with the refreshed access token", url_str);
+                            refreshed = true;
+                            req = next;
+                            continue;
+                        }
+                        Ok(false) => {}
+                        // Return the original 401 rather than the error of refreshing.
+                        Err(refresh_err) => {
+                            log::warn!("Failed to refresh the access token: {}", refresh_err);
+                            return Err(err);
+                        }
+                    }
+                }
+            }
+            let Some(policy) = &self.retry_policy else {
+                return Err(err);
+            };
+            let Some(delay) = policy.retry_delay(attempt, &err, idempotent) else {
+                return Err(err);
+            };
+            log::warn!("Retrying {} in {:?} because of {}", url_str, delay, err);
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+            req = next;
+        }
+    }
+
+    async fn execute<T>(
+        &self,
+        req: RequestBuilder,
+        url_str: &str,
+    ) -> Result<Response<T>, MegalodonError>
+    where
+        T: DeserializeOwned + Debug,
+    {
+        let res = req.send().await?;
         let status = res.status();
         match status {
             reqwest::StatusCode::OK
@@ -368,20 +290,31 @@ impl APIClient {
                 let res = Response::<T>::from_reqwest(res).await?;
                 Ok(res)
             }
-            _ => match res.text().await {
-                Ok(text) => Err(MegalodonError::new_own(
-                    text,
-                    Kind::HTTPStatusError,
-                    Some(url_str),
-                    Some(status.as_u16()),
-                )),
-                Err(_err) => Err(MegalodonError::new_own(
-                    "Unknown error".to_string(),
-                    Kind::HTTPStatusError,
-                    Some(url_str),
+            reqwest::StatusCode::PARTIAL_CONTENT => Err(MegalodonError::new_own(
+                String::from("The requested resource is still being processed"),
+                Kind::HTTPPartialContentError,
+                Some(url_str.to_string()),
+                Some(status.as_u16()),
+            )),
+            reqwest::StatusCode::TOO_MANY_REQUESTS => {
+                let reset_at = rate_limit::reset_at(res.headers());
+                Err(MegalodonError::new_http(
+                    res.text()
+                        .await
+                        .unwrap_or_else(|_| "Too many requests".to_string()),
+                    Kind::RateLimited { reset_at },
+                    Some(url_str.to_string()),
                     Some(status.as_u16()),
-                )),
-            },
+                ))
+            }
+            _ => Err(MegalodonError::new_http(
+                res.text()
+                    .await
+                    .unwrap_or_else(|_| "Unknown error".to_string()),
+                Kind::from_status(status.as_u16()),
+                Some(url_str.to_string()),
+                Some(status.as_u16()),
+            )),
         }
     }
 }
@@ -0,0 +1,30 @@
+use crate::entities as MegalodonEntities;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct EncryptedMessage {
+    id: String,
+    account_id: String,
+    device_id: String,
+    r#type: u32,
+    body: String,
+    digest: String,
+    message_franking: String,
+    created_at: DateTime<Utc>,
+}
+
+impl From<EncryptedMessage> for MegalodonEntities::EncryptedMessage {
+    fn from(item: EncryptedMessage) -> Self {
+        MegalodonEntities::EncryptedMessage {
+            id: item.id,
+            account_id: item.account_id,
+            device_id: item.device_id,
+            r#type: item.r#type,
+            body: item.body,
+            digest: item.digest,
+            message_franking: item.message_franking,
+            created_at: item.created_at,
+        }
+    }
+}
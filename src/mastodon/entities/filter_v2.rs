@@ -0,0 +1,128 @@
+use super::filter::FilterContext;
+use crate::entities as MegalodonEntities;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct FilterV2 {
+    id: String,
+    title: String,
+    context: Vec<FilterContext>,
+    expires_at: Option<DateTime<Utc>>,
+    filter_action: FilterAction,
+    keywords: Option<Vec<FilterKeyword>>,
+    statuses: Option<Vec<FilterStatus>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FilterAction {
+    Warn,
+    Hide,
+    Blur,
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct FilterKeyword {
+    id: String,
+    keyword: String,
+    whole_word: bool,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct FilterStatus {
+    id: String,
+    status_id: String,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct FilterResult {
+    filter: FilterV2,
+    keyword_matches: Option<Vec<String>>,
+    status_matches: Option<Vec<String>>,
+}
+
+impl From<FilterAction> for MegalodonEntities::filter_v2::FilterAction {
+    fn from(item: FilterAction) -> Self {
+        match item {
+            FilterAction::Warn => MegalodonEntities::filter_v2::FilterAction::Warn,
+            FilterAction::Hide => MegalodonEntities::filter_v2::FilterAction::Hide,
+            FilterAction::Blur => MegalodonEntities::filter_v2::FilterAction::Blur,
+            FilterAction::Unknown => MegalodonEntities::filter_v2::FilterAction::Unknown,
+        }
+    }
+}
+
+impl From<FilterV2> for MegalodonEntities::FilterV2 {
+    fn from(item: FilterV2) -> Self {
+        MegalodonEntities::FilterV2 {
+            id: item.id,
+            title: item.title,
+            context: item.context.into_iter().map(|i| i.into()).collect(),
+            expires_at: item.expires_at,
+            filter_action: item.filter_action.into(),
+            keywords: item
+                .keywords
+                .map(|v| v.into_iter().map(|i| i.into()).collect()),
+            statuses: item
+                .statuses
+                .map(|v| v.into_iter().map(|i| i.into()).collect()),
+        }
+    }
+}
+
+impl From<FilterKeyword> for MegalodonEntities::FilterKeyword {
+    fn from(item: FilterKeyword) -> Self {
+        MegalodonEntities::FilterKeyword {
+            id: item.id,
+            keyword: item.keyword,
+            whole_word: item.whole_word,
+        }
+    }
+}
+
+impl From<FilterStatus> for MegalodonEntities::FilterStatus {
+    fn from(item: FilterStatus) -> Self {
+        MegalodonEntities::FilterStatus {
+            id: item.id,
+            status_id: item.status_id,
+        }
+    }
+}
+
+impl From<FilterResult> for MegalodonEntities::FilterResult {
+    fn from(item: FilterResult) -> Self {
+        MegalodonEntities::FilterResult {
+            filter: item.filter.into(),
+            keyword_matches: item.keyword_matches,
+            status_matches: item.status_matches,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_filter_result() {
+        let text = r#"{"filter":{"id":"3","title":"Hide completely","context":["home","account"],"expires_at":"2022-09-20T17:27:39.296Z","filter_action":"hide","keywords":[{"id":"1197","keyword":"bad word","whole_word":false}],"statuses":[]},"keyword_matches":["bad word"],"status_matches":null}"#;
+
+        let result: MegalodonEntities::FilterResult =
+            serde_json::from_str::<FilterResult>(text).unwrap().into();
+        assert_eq!(result.filter.title, "Hide completely");
+        assert_eq!(
+            result.filter.filter_action,
+            MegalodonEntities::filter_v2::FilterAction::Hide
+        );
+        assert!(matches!(
+            result.filter.context[1],
+            MegalodonEntities::filter::FilterContext::Account
+        ));
+        assert_eq!(result.filter.keywords.unwrap()[0].keyword, "bad word");
+        assert_eq!(result.keyword_matches, Some(vec!["bad word".to_string()]));
+        assert!(result.status_matches.is_none());
+    }
+}
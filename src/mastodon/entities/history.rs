@@ -3,17 +3,35 @@ use serde::Deserialize;
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct History {
-    day: u64,
-    uses: usize,
-    accounts: usize,
+    day: Count,
+    uses: Count,
+    accounts: Count,
+}
+
+/// Counts in history.
+/// Mastodon returns them as strings, and some compatible servers return numbers.
+#[derive(Debug, Deserialize, Clone)]
+#[serde(untagged)]
+enum Count {
+    Number(u64),
+    String(String),
+}
+
+impl Count {
+    fn value(self) -> u64 {
+        match self {
+            Count::Number(n) => n,
+            Count::String(s) => s.parse().unwrap_or(0),
+        }
+    }
 }
 
 impl Into<MegalodonEntities::History> for History {
     fn into(self) -> MegalodonEntities::History {
         MegalodonEntities::History {
-            day: self.day,
-            uses: self.uses,
-            accounts: self.accounts,
+            day: self.day.value(),
+            uses: self.uses.value() as usize,
+            accounts: self.accounts.value() as usize,
         }
     }
 }
@@ -82,6 +82,17 @@ impl Into<MegalodonEntities::Instance> for Instance {
     }
 }
 
+/// Synthesize v2 from v1 for servers before Mastodon 4.0, keeping media attachment limits.
+impl From<Instance> for MegalodonEntities::InstanceV2 {
+    fn from(item: Instance) -> Self {
+        let media_attachments = item.configuration.media_attachments.clone();
+        let v1: MegalodonEntities::Instance = item.into();
+        let mut v2: MegalodonEntities::InstanceV2 = v1.into();
+        v2.configuration.media_attachments = Some(media_attachments.into());
+        v2
+    }
+}
+
 impl Into<MegalodonEntities::instance::InstanceConfig> for InstanceConfig {
     fn into(self) -> MegalodonEntities::instance::InstanceConfig {
         MegalodonEntities::instance::InstanceConfig {
@@ -100,6 +111,19 @@ impl Into<MegalodonEntities::instance::Statuses> for Statuses {
     }
 }
 
+impl From<MediaAttachments> for MegalodonEntities::instance_v2::MediaAttachments {
+    fn from(item: MediaAttachments) -> Self {
+        MegalodonEntities::instance_v2::MediaAttachments {
+            supported_mime_types: item.supported_mime_types,
+            image_size_limit: item.image_size_limit,
+            image_matrix_limit: item.image_matrix_limit,
+            video_size_limit: item.video_size_limit,
+            video_frame_rate_limit: item.video_frame_rate_limit,
+            video_matrix_limit: item.video_matrix_limit,
+        }
+    }
+}
+
 impl Into<MegalodonEntities::instance::Polls> for Polls {
     fn into(self) -> MegalodonEntities::instance::Polls {
         MegalodonEntities::instance::Polls {
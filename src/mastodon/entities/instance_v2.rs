@@ -0,0 +1,175 @@
+use super::instance::{InstanceRule, MediaAttachments, Polls, Statuses};
+use super::Account;
+use crate::entities as MegalodonEntities;
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct InstanceV2 {
+    pub domain: String,
+    pub title: String,
+    pub version: String,
+    pub source_url: Option<String>,
+    pub description: String,
+    pub usage: Option<Usage>,
+    pub thumbnail: Option<Thumbnail>,
+    pub languages: Vec<String>,
+    pub configuration: Configuration,
+    pub registrations: Registrations,
+    pub contact: Contact,
+    #[serde(default)]
+    pub rules: Vec<InstanceRule>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct Usage {
+    pub users: UsageUsers,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct UsageUsers {
+    pub active_month: u32,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct Thumbnail {
+    pub url: String,
+    pub blurhash: Option<String>,
+    pub versions: Option<ThumbnailVersions>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct ThumbnailVersions {
+    #[serde(rename = "@1x")]
+    pub at1x: Option<String>,
+    #[serde(rename = "@2x")]
+    pub at2x: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct Configuration {
+    pub urls: ConfigurationURLs,
+    pub statuses: Statuses,
+    pub media_attachments: MediaAttachments,
+    pub polls: Polls,
+    /// GoToSocial does not have translation.
+    #[serde(default)]
+    pub translation: Translation,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct ConfigurationURLs {
+    pub streaming: String,
+}
+
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct Translation {
+    pub enabled: bool,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct Registrations {
+    pub enabled: bool,
+    pub approval_required: bool,
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct Contact {
+    pub email: String,
+    pub account: Option<Account>,
+}
+
+impl From<InstanceV2> for MegalodonEntities::InstanceV2 {
+    fn from(item: InstanceV2) -> Self {
+        MegalodonEntities::InstanceV2 {
+            domain: item.domain,
+            title: item.title,
+            version: item.version,
+            source_url: item.source_url,
+            description: item.description,
+            usage: item.usage.map(|u| MegalodonEntities::instance_v2::Usage {
+                users: MegalodonEntities::instance_v2::UsageUsers {
+                    active_month: u.users.active_month,
+                },
+            }),
+            thumbnail: item.thumbnail.map(|t| t.into()),
+            languages: item.languages,
+            configuration: item.configuration.into(),
+            registrations: MegalodonEntities::instance_v2::Registrations {
+                enabled: item.registrations.enabled,
+                approval_required: item.registrations.approval_required,
+                message: item.registrations.message,
+            },
+            contact: MegalodonEntities::instance_v2::Contact {
+                email: item.contact.email,
+                account: item.contact.account.map(|a| a.into()),
+            },
+            rules: item.rules.into_iter().map(|r| r.into()).collect(),
+        }
+    }
+}
+
+impl From<Thumbnail> for MegalodonEntities::instance_v2::Thumbnail {
+    fn from(item: Thumbnail) -> Self {
+        MegalodonEntities::instance_v2::Thumbnail {
+            url: item.url,
+            blurhash: item.blurhash,
+            versions: item
+                .versions
+                .map(|v| MegalodonEntities::instance_v2::ThumbnailVersions {
+                    at1x: v.at1x,
+                    at2x: v.at2x,
+                }),
+        }
+    }
+}
+
+impl From<Configuration> for MegalodonEntities::instance_v2::Configuration {
+    fn from(item: Configuration) -> Self {
+        MegalodonEntities::instance_v2::Configuration {
+            urls: MegalodonEntities::instance_v2::ConfigurationURLs {
+                streaming: item.urls.streaming,
+            },
+            statuses: item.statuses.into(),
+            media_attachments: Some(item.media_attachments.into()),
+            polls: item.polls.into(),
+            translation: MegalodonEntities::instance_v2::Translation {
+                enabled: item.translation.enabled,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_instance_v2() {
+        let text = r#"{"domain":"mastodon.social","title":"Mastodon","version":"4.2.0","source_url":"https://github.com/mastodon/mastodon","description":"The original server operated by the Mastodon gGmbH non-profit","usage":{"users":{"active_month":246893}},"thumbnail":{"url":"https://files.mastodon.social/site_uploads/files/000/000/001/@1x/57c12f441d083cde.png","blurhash":"UeKUpFxuo~R%0nW;WCnhF6RjaJt757oJodS$","versions":{"@1x":"https://files.mastodon.social/site_uploads/files/000/000/001/@1x/57c12f441d083cde.png","@2x":"https://files.mastodon.social/site_uploads/files/000/000/001/@2x/57c12f441d083cde.png"}},"languages":["en"],"configuration":{"urls":{"streaming":"wss://mastodon.social"},"vapid":{"public_key":"BCkMmVdKDnKYwzVCDC99Iuc9GvId-x7-kKtuHnLgfF98ENiZp_aj-UNthbCdI70DqN1zUVis-x0Wrot2sBagkMc="},"accounts":{"max_featured_tags":10},"statuses":{"max_characters":500,"max_media_attachments":4,"characters_reserved_per_url":23},"media_attachments":{"supported_mime_types":["image/jpeg","image/png","video/mp4"],"image_size_limit":16777216,"image_matrix_limit":33177600,"video_size_limit":103809024,"video_frame_rate_limit":120,"video_matrix_limit":8294400},"polls":{"max_options":4,"max_characters_per_option":50,"min_expiration":300,"max_expiration":2629746},"translation":{"enabled":true}},"registrations":{"enabled":false,"approval_required":false,"message":"Registrations are closed"},"contact":{"email":"staff@mastodon.social","account":null},"rules":[{"id":"1","text":"Sexually explicit or violent media must be marked as sensitive when posting"}]}"#;
+
+        let instance: MegalodonEntities::InstanceV2 =
+            serde_json::from_str::<InstanceV2>(text).unwrap().into();
+        assert_eq!(instance.domain, "mastodon.social");
+        assert_eq!(instance.usage.unwrap().users.active_month, 246893);
+        assert_eq!(
+            instance.thumbnail.unwrap().versions.unwrap().at2x.unwrap(),
+            "https://files.mastodon.social/site_uploads/files/000/000/001/@2x/57c12f441d083cde.png"
+        );
+        assert_eq!(
+            instance.configuration.urls.streaming,
+            "wss://mastodon.social"
+        );
+        let media_attachments = instance.configuration.media_attachments.unwrap();
+        assert_eq!(media_attachments.supported_mime_types.len(), 3);
+        assert_eq!(media_attachments.image_size_limit, 16777216);
+        assert!(instance.configuration.translation.enabled);
+        assert_eq!(
+            instance.registrations.message.unwrap(),
+            "Registrations are closed"
+        );
+        assert_eq!(instance.contact.email, "staff@mastodon.social");
+        assert!(instance.contact.account.is_none());
+        assert_eq!(instance.rules.len(), 1);
+    }
+}
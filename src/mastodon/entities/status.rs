@@ -1,7 +1,7 @@
 use core::fmt;
 use std::str::FromStr;
 
-use super::{Account, Application, Attachment, Card, Emoji, Mention, Poll, Tag};
+use super::{Account, Application, Attachment, Card, Emoji, FilterResult, Mention, Poll, Tag};
 use crate::entities as MegalodonEntities;
 use crate::error::{Error, Kind};
 use chrono::{DateTime, Utc};
@@ -38,6 +38,7 @@ pub struct Status {
     pinned: Option<bool>,
     quote: Option<Box<Status>>,
     bookmarked: Option<bool>,
+    filtered: Option<Vec<FilterResult>>,
 }
 
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
@@ -108,6 +109,7 @@ impl Into<MegalodonEntities::Status> for Status {
             reblog: reblog_status,
             content: self.content,
             plain_content: None,
+            mfm_content: None,
             created_at: self.created_at,
             emojis: self.emojis.into_iter().map(|i| i.into()).collect(),
             replies_count: self.replies_count,
@@ -134,6 +136,31 @@ impl Into<MegalodonEntities::Status> for Status {
             emoji_reactions: None,
             quote: quoted,
             bookmarked: self.bookmarked,
+            filtered: self
+                .filtered
+                .map(|v| v.into_iter().map(|i| i.into()).collect()),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_status_filtered_with_blur() {
+        let text = r#"{"id":"103270115826048975","uri":"https://mastodon.social/users/Gargron/statuses/103270115826048975","url":"https://mastodon.social/@Gargron/103270115826048975","account":{"id":"1","username":"Gargron","acct":"Gargron","display_name":"Eugen","locked":false,"bot":false,"created_at":"2016-03-16T14:34:26.392Z","note":"<p>Developer of Mastodon</p>","url":"https://mastodon.social/@Gargron","avatar":"https://files.mastodon.social/accounts/avatars/000/000/001/original/d96d39a0abb45b92.jpg","avatar_static":"https://files.mastodon.social/accounts/avatars/000/000/001/original/d96d39a0abb45b92.jpg","header":"https://files.mastodon.social/accounts/headers/000/000/001/original/c91b871f294ea63e.png","header_static":"https://files.mastodon.social/accounts/headers/000/000/001/original/c91b871f294ea63e.png","followers_count":322930,"following_count":459,"statuses_count":61323,"emojis":[],"fields":[]},"in_reply_to_id":null,"in_reply_to_account_id":null,"reblog":null,"content":"<p>Sensitive photo</p>","created_at":"2019-12-08T03:48:33.901Z","emojis":[],"replies_count":5,"reblogs_count":6,"favourites_count":11,"reblogged":false,"favourited":false,"muted":false,"sensitive":false,"spoiler_text":"","visibility":"public","media_attachments":[],"mentions":[],"tags":[],"card":null,"poll":null,"application":{"name":"Web","website":null},"language":"en","pinned":false,"bookmarked":false,"filtered":[{"filter":{"id":"5","title":"Photos","context":["home","public"],"expires_at":null,"filter_action":"blur","keywords":[{"id":"10","keyword":"photo","whole_word":true}],"statuses":[]},"keyword_matches":["photo"],"status_matches":null},{"filter":{"id":"6","title":"Future action","context":["home"],"expires_at":null,"filter_action":"collapse"},"keyword_matches":["photo"],"status_matches":null}]}"#;
+
+        let status: MegalodonEntities::Status =
+            serde_json::from_str::<Status>(text).unwrap().into();
+        let filtered = status.filtered.unwrap();
+        assert_eq!(
+            filtered[0].filter.filter_action,
+            MegalodonEntities::filter_v2::FilterAction::Blur
+        );
+        assert_eq!(
+            filtered[1].filter.filter_action,
+            MegalodonEntities::filter_v2::FilterAction::Unknown
+        );
+    }
+}
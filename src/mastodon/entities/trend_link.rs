@@ -0,0 +1,40 @@
+use super::{Card, History};
+use crate::entities as MegalodonEntities;
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct TrendLink {
+    #[serde(flatten)]
+    card: Card,
+    history: Vec<History>,
+}
+
+impl From<TrendLink> for MegalodonEntities::TrendLink {
+    fn from(item: TrendLink) -> Self {
+        MegalodonEntities::TrendLink {
+            card: item.card.into(),
+            history: item.history.into_iter().map(|h| h.into()).collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_trend_link() {
+        let text = r#"{"url":"https://www.nbcnews.com/specials/plan-your-vote-2022-elections/index.html","title":"Plan Your Vote: 2022 Elections","description":"Everything you need to know about the voting rules where you live.","type":"link","author_name":"","author_url":"","provider_name":"NBC News","provider_url":"","html":"","width":400,"height":420,"image":"https://files.mastodon.social/cache/preview_cards/images/045/027/478/original/0783d5e91a14fd49.jpeg","embed_url":"","blurhash":"UcQmF#ay~qofj[WBj[j[~qof9Fayofofayay","history":[{"day":"1661817600","accounts":"7","uses":"7"},{"day":"1661731200","accounts":"23","uses":"23"}]}"#;
+
+        let link: MegalodonEntities::TrendLink =
+            serde_json::from_str::<TrendLink>(text).unwrap().into();
+        assert_eq!(link.card.title, "Plan Your Vote: 2022 Elections");
+        assert!(matches!(
+            link.card.r#type,
+            MegalodonEntities::card::CardType::Link
+        ));
+        assert_eq!(link.history.len(), 2);
+        assert_eq!(link.history[0].day, 1661817600);
+        assert_eq!(link.history[1].uses, 23);
+    }
+}
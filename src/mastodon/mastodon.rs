@@ -2,23 +2,37 @@ use super::api_client::APIClient;
 use super::entities;
 use super::oauth;
 use super::web_socket::WebSocket;
+use crate::capabilities::Capabilities;
+use crate::rate_limit::RetryPolicy;
+use crate::streaming::{MultiplexedStreaming, StreamingOptions};
+use crate::token_manager::{ManagedToken, TokenManager};
 use crate::{
     default, entities as MegalodonEntities, error::Error, megalodon, oauth as MegalodonOAuth,
     response::Response,
 };
-use crate::{error, Streaming};
+use crate::{error, Streaming, SNS};
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 use oauth2::basic::BasicClient;
 use oauth2::{
-    AuthUrl, ClientId, ClientSecret, CsrfToken, RedirectUrl, ResponseType, Scope, TokenUrl,
+    AuthUrl, ClientId, ClientSecret, CsrfToken, PkceCodeChallenge, RedirectUrl, ResponseType,
+    Scope, TokenUrl,
 };
 use serde_json::Value;
 use sha1::{Digest, Sha1};
 use std::collections::HashMap;
+use std::sync::Arc;
 use tokio::fs::File;
 use tokio_util::codec::{BytesCodec, FramedRead};
 
+/// Authorize URL with `state` and PKCE code verifier.
+#[derive(Debug)]
+struct AuthUrlWithVerifier {
+    url: String,
+    state: String,
+    code_verifier: String,
+}
+
 /// Mastodon API Client which satisfies megalodon trait.
 #[derive(Debug, Clone)]
 pub struct Mastodon {
@@ -26,6 +40,7 @@ pub struct Mastodon {
     base_url: String,
     access_token: Option<String>,
     user_agent: Option<String>,
+    streaming_options: StreamingOptions,
 }
 
 impl Mastodon {
@@ -41,37 +56,120 @@ impl Mastodon {
             base_url,
             access_token,
             user_agent,
+            streaming_options: StreamingOptions::default(),
         }
     }
 
+    /// Create a new [`Mastodon`] which sends all requests with the given HTTP client.
+    /// The user agent is used for streaming connections, because the HTTP client has its own one.
+    pub fn with_client(
+        base_url: String,
+        access_token: Option<String>,
+        user_agent: Option<String>,
+        http_client: reqwest::Client,
+    ) -> Mastodon {
+        let client = APIClient::with_client(base_url.clone(), access_token.clone(), http_client);
+        Mastodon {
+            client,
+            base_url,
+            access_token,
+            user_agent,
+            streaming_options: StreamingOptions::default(),
+        }
+    }
+
+    /// Set a policy to retry failed requests. Requests are not retried when it is `None`.
+    pub fn set_retry_policy(&mut self, retry_policy: Option<RetryPolicy>) {
+        self.client.set_retry_policy(retry_policy);
+    }
+
+    /// Set a manager which refreshes the access token. When it is set, the access token of the manager is used instead.
+    /// Streaming connections use the access token at the time of this call.
+    pub fn set_token_manager(&mut self, token_manager: Option<TokenManager>) {
+        let managed = token_manager.map(|manager| self.managed_token(manager));
+        self.set_managed_token(managed);
+    }
+
+    /// Set the token manager which is already bound with a refresher, so a wrapping client can share it.
+    pub(crate) fn set_managed_token(&mut self, managed: Option<ManagedToken>) {
+        if let Some(managed) = &managed {
+            self.access_token = Some(managed.token_data().access_token);
+        }
+        self.client.set_token_manager(managed);
+    }
+
+    /// Bind the token manager with a copy of this client without any token manager, which refreshes the access token with [`megalodon::Megalodon::refresh_access_token`].
+    pub(crate) fn managed_token(&self, manager: TokenManager) -> ManagedToken {
+        let mut refresher = self.clone();
+        refresher.client.set_token_manager(None);
+        ManagedToken::new(manager, Arc::new(refresher))
+    }
+
+    /// Set options of streaming connections, which are used for streaming objects created after this call.
+    pub fn set_streaming_options(&mut self, streaming_options: StreamingOptions) {
+        self.streaming_options = streaming_options;
+    }
+
     async fn generate_auth_url(
         &self,
         client_id: String,
         client_secret: String,
         scope: Vec<&str>,
         redirect_uri: String,
-    ) -> Result<String, Error> {
+    ) -> Result<AuthUrlWithVerifier, Error> {
         let client = BasicClient::new(
             ClientId::new(client_id),
             Some(ClientSecret::new(client_secret)),
             AuthUrl::new(format!("{}{}", self.base_url, "/oauth/authorize").to_string())?,
             Some(TokenUrl::new(
-                format!("{}{}", self.base_url, "/oaut/token").to_string(),
+                format!("{}{}", self.base_url, "/oauth/token").to_string(),
             )?),
         )
         .set_redirect_uri(RedirectUrl::new(redirect_uri)?);
 
         let scopes: Vec<Scope> = scope.iter().map(|s| Scope::new(s.to_string())).collect();
 
-        let (auth_url, _) = client
+        let (pkce_challenge, pkce_verifier) = PkceCodeChallenge::new_random_sha256();
+
+        let (auth_url, csrf_token) = client
             .authorize_url(CsrfToken::new_random)
             .add_scopes(scopes)
             .set_response_type(&ResponseType::new("code".to_string()))
+            .set_pkce_challenge(pkce_challenge)
             .url();
-        Ok(auth_url.to_string())
+        Ok(AuthUrlWithVerifier {
+            url: auth_url.to_string(),
+            state: csrf_token.secret().to_string(),
+            code_verifier: pkce_verifier.secret().to_string(),
+        })
     }
 }
 
+/// Build `keywords_attributes` parameter of filters v2 API.
+fn keywords_attributes_value(attributes: &[megalodon::FilterKeywordAttributes]) -> Value {
+    Value::Array(
+        attributes
+            .iter()
+            .map(|attribute| {
+                let mut value = serde_json::Map::new();
+                if let Some(id) = &attribute.id {
+                    value.insert("id".to_string(), Value::String(id.clone()));
+                }
+                if let Some(keyword) = &attribute.keyword {
+                    value.insert("keyword".to_string(), Value::String(keyword.clone()));
+                }
+                if let Some(whole_word) = attribute.whole_word {
+                    value.insert("whole_word".to_string(), Value::Bool(whole_word));
+                }
+                if attribute.destroy {
+                    value.insert("_destroy".to_string(), Value::Bool(true));
+                }
+                Value::Object(value)
+            })
+            .collect(),
+    )
+}
+
 #[async_trait]
 impl megalodon::Megalodon for Mastodon {
     async fn register_app(
@@ -85,7 +183,7 @@ impl megalodon::Megalodon for Mastodon {
         }
 
         let mut app = self.create_app(client_name, options).await?;
-        let url = self
+        let auth = self
             .generate_auth_url(
                 app.client_id.clone(),
                 app.client_secret.clone(),
@@ -93,7 +191,9 @@ impl megalodon::Megalodon for Mastodon {
                 app.redirect_uri.clone(),
             )
             .await?;
-        app.url = Some(url);
+        app.url = Some(auth.url);
+        app.state = Some(auth.state);
+        app.code_verifier = Some(auth.code_verifier);
         Ok(app)
     }
 
@@ -135,12 +235,16 @@ impl megalodon::Megalodon for Mastodon {
         client_secret: String,
         code: String,
         redirect_uri: String,
+        code_verifier: Option<String>,
     ) -> Result<MegalodonOAuth::TokenData, Error> {
         let mut params = HashMap::<&str, Value>::new();
         params.insert("client_id", serde_json::Value::String(client_id));
         params.insert("client_secret", serde_json::Value::String(client_secret));
         params.insert("code", serde_json::Value::String(code));
         params.insert("redirect_uri", serde_json::Value::String(redirect_uri));
+        if let Some(code_verifier) = code_verifier {
+            params.insert("code_verifier", serde_json::Value::String(code_verifier));
+        }
         params.insert(
             "grant_type",
             serde_json::Value::String("authorization_code".to_string()),
@@ -165,7 +269,7 @@ impl megalodon::Megalodon for Mastodon {
         params.insert("refresh_token", serde_json::Value::String(refresh_token));
         params.insert(
             "grant_type",
-            serde_json::Value::String("authorization_code".to_string()),
+            serde_json::Value::String("refresh_token".to_string()),
         );
 
         let res = self
@@ -175,6 +279,30 @@ impl megalodon::Megalodon for Mastodon {
         Ok(MegalodonOAuth::TokenData::from(res.json.into()))
     }
 
+    async fn fetch_app_token(
+        &self,
+        client_id: String,
+        client_secret: String,
+        scopes: Vec<String>,
+    ) -> Result<MegalodonOAuth::TokenData, Error> {
+        let mut params = HashMap::<&str, Value>::new();
+        params.insert("client_id", serde_json::Value::String(client_id));
+        params.insert("client_secret", serde_json::Value::String(client_secret));
+        if !scopes.is_empty() {
+            params.insert("scope", serde_json::Value::String(scopes.join(" ")));
+        }
+        params.insert(
+            "grant_type",
+            serde_json::Value::String("client_credentials".to_string()),
+        );
+
+        let res = self
+            .client
+            .post::<oauth::TokenDataFromServer>("/oauth/token", &params, None)
+            .await?;
+        Ok(res.json.into())
+    }
+
     async fn revoke_access_token(
         &self,
         client_id: String,
@@ -1096,6 +1224,329 @@ impl megalodon::Megalodon for Mastodon {
         Ok(res)
     }
 
+    async fn get_filters_v2(&self) -> Result<Response<Vec<MegalodonEntities::FilterV2>>, Error> {
+        let res = self
+            .client
+            .get::<Vec<entities::FilterV2>>("/api/v2/filters", None)
+            .await?;
+
+        Ok(Response::<Vec<MegalodonEntities::FilterV2>>::new(
+            res.json.into_iter().map(|j| j.into()).collect(),
+            res.status,
+            res.status_text,
+            res.header,
+        ))
+    }
+
+    async fn get_filter_v2(
+        &self,
+        id: String,
+    ) -> Result<Response<MegalodonEntities::FilterV2>, Error> {
+        let res = self
+            .client
+            .get::<entities::FilterV2>(format!("/api/v2/filters/{}", id).as_str(), None)
+            .await?;
+
+        Ok(Response::<MegalodonEntities::FilterV2>::new(
+            res.json.into(),
+            res.status,
+            res.status_text,
+            res.header,
+        ))
+    }
+
+    async fn create_filter_v2(
+        &self,
+        title: String,
+        context: Vec<MegalodonEntities::filter::FilterContext>,
+        options: Option<&megalodon::FilterV2InputOptions>,
+    ) -> Result<Response<MegalodonEntities::FilterV2>, Error> {
+        let mut params = HashMap::<&str, Value>::from([
+            ("title", serde_json::Value::String(title)),
+            (
+                "context",
+                serde_json::to_value(&context).ok().unwrap_or_default(),
+            ),
+        ]);
+        if let Some(options) = options {
+            if let Some(filter_action) = &options.filter_action {
+                params.insert(
+                    "filter_action",
+                    serde_json::to_value(filter_action).ok().unwrap_or_default(),
+                );
+            }
+            if let Some(expires_in) = options.expires_in {
+                params.insert(
+                    "expires_in",
+                    serde_json::Value::String(expires_in.to_string()),
+                );
+            }
+            if let Some(keywords_attributes) = &options.keywords_attributes {
+                params.insert(
+                    "keywords_attributes",
+                    keywords_attributes_value(keywords_attributes),
+                );
+            }
+        }
+        let res = self
+            .client
+            .post::<entities::FilterV2>("/api/v2/filters", &params, None)
+            .await?;
+
+        Ok(Response::<MegalodonEntities::FilterV2>::new(
+            res.json.into(),
+            res.status,
+            res.status_text,
+            res.header,
+        ))
+    }
+
+    async fn update_filter_v2(
+        &self,
+        id: String,
+        options: Option<&megalodon::UpdateFilterV2InputOptions>,
+    ) -> Result<Response<MegalodonEntities::FilterV2>, Error> {
+        let mut params = HashMap::<&str, Value>::new();
+        if let Some(options) = options {
+            if let Some(title) = &options.title {
+                params.insert("title", serde_json::Value::String(title.clone()));
+            }
+            if let Some(context) = &options.context {
+                params.insert(
+                    "context",
+                    serde_json::to_value(context).ok().unwrap_or_default(),
+                );
+            }
+            if let Some(filter_action) = &options.filter_action {
+                params.insert(
+                    "filter_action",
+                    serde_json::to_value(filter_action).ok().unwrap_or_default(),
+                );
+            }
+            if let Some(expires_in) = options.expires_in {
+                params.insert(
+                    "expires_in",
+                    serde_json::Value::String(expires_in.to_string()),
+                );
+            }
+            if let Some(keywords_attributes) = &options.keywords_attributes {
+                params.insert(
+                    "keywords_attributes",
+                    keywords_attributes_value(keywords_attributes),
+                );
+            }
+        }
+        let res = self
+            .client
+            .put::<entities::FilterV2>(format!("/api/v2/filters/{}", id).as_str(), &params, None)
+            .await?;
+
+        Ok(Response::<MegalodonEntities::FilterV2>::new(
+            res.json.into(),
+            res.status,
+            res.status_text,
+            res.header,
+        ))
+    }
+
+    async fn delete_filter_v2(&self, id: String) -> Result<Response<()>, Error> {
+        let params = HashMap::<&str, Value>::new();
+        let res = self
+            .client
+            .delete::<()>(format!("/api/v2/filters/{}", id).as_str(), &params, None)
+            .await?;
+
+        Ok(res)
+    }
+
+    async fn get_filter_keywords(
+        &self,
+        filter_id: String,
+    ) -> Result<Response<Vec<MegalodonEntities::FilterKeyword>>, Error> {
+        let res = self
+            .client
+            .get::<Vec<entities::FilterKeyword>>(
+                format!("/api/v2/filters/{}/keywords", filter_id).as_str(),
+                None,
+            )
+            .await?;
+
+        Ok(Response::<Vec<MegalodonEntities::FilterKeyword>>::new(
+            res.json.into_iter().map(|j| j.into()).collect(),
+            res.status,
+            res.status_text,
+            res.header,
+        ))
+    }
+
+    async fn add_filter_keyword(
+        &self,
+        filter_id: String,
+        keyword: String,
+        whole_word: Option<bool>,
+    ) -> Result<Response<MegalodonEntities::FilterKeyword>, Error> {
+        let mut params =
+            HashMap::<&str, Value>::from([("keyword", serde_json::Value::String(keyword))]);
+        if let Some(whole_word) = whole_word {
+            params.insert("whole_word", serde_json::Value::Bool(whole_word));
+        }
+        let res = self
+            .client
+            .post::<entities::FilterKeyword>(
+                format!("/api/v2/filters/{}/keywords", filter_id).as_str(),
+                &params,
+                None,
+            )
+            .await?;
+
+        Ok(Response::<MegalodonEntities::FilterKeyword>::new(
+            res.json.into(),
+            res.status,
+            res.status_text,
+            res.header,
+        ))
+    }
+
+    async fn get_filter_keyword(
+        &self,
+        id: String,
+    ) -> Result<Response<MegalodonEntities::FilterKeyword>, Error> {
+        let res = self
+            .client
+            .get::<entities::FilterKeyword>(
+                format!("/api/v2/filters/keywords/{}", id).as_str(),
+                None,
+            )
+            .await?;
+
+        Ok(Response::<MegalodonEntities::FilterKeyword>::new(
+            res.json.into(),
+            res.status,
+            res.status_text,
+            res.header,
+        ))
+    }
+
+    async fn update_filter_keyword(
+        &self,
+        id: String,
+        keyword: String,
+        whole_word: Option<bool>,
+    ) -> Result<Response<MegalodonEntities::FilterKeyword>, Error> {
+        let mut params =
+            HashMap::<&str, Value>::from([("keyword", serde_json::Value::String(keyword))]);
+        if let Some(whole_word) = whole_word {
+            params.insert("whole_word", serde_json::Value::Bool(whole_word));
+        }
+        let res = self
+            .client
+            .put::<entities::FilterKeyword>(
+                format!("/api/v2/filters/keywords/{}", id).as_str(),
+                &params,
+                None,
+            )
+            .await?;
+
+        Ok(Response::<MegalodonEntities::FilterKeyword>::new(
+            res.json.into(),
+            res.status,
+            res.status_text,
+            res.header,
+        ))
+    }
+
+    async fn remove_filter_keyword(&self, id: String) -> Result<Response<()>, Error> {
+        let params = HashMap::<&str, Value>::new();
+        let res = self
+            .client
+            .delete::<()>(
+                format!("/api/v2/filters/keywords/{}", id).as_str(),
+                &params,
+                None,
+            )
+            .await?;
+
+        Ok(res)
+    }
+
+    async fn get_filter_statuses(
+        &self,
+        filter_id: String,
+    ) -> Result<Response<Vec<MegalodonEntities::FilterStatus>>, Error> {
+        let res = self
+            .client
+            .get::<Vec<entities::FilterStatus>>(
+                format!("/api/v2/filters/{}/statuses", filter_id).as_str(),
+                None,
+            )
+            .await?;
+
+        Ok(Response::<Vec<MegalodonEntities::FilterStatus>>::new(
+            res.json.into_iter().map(|j| j.into()).collect(),
+            res.status,
+            res.status_text,
+            res.header,
+        ))
+    }
+
+    async fn add_filter_status(
+        &self,
+        filter_id: String,
+        status_id: String,
+    ) -> Result<Response<MegalodonEntities::FilterStatus>, Error> {
+        let params =
+            HashMap::<&str, Value>::from([("status_id", serde_json::Value::String(status_id))]);
+        let res = self
+            .client
+            .post::<entities::FilterStatus>(
+                format!("/api/v2/filters/{}/statuses", filter_id).as_str(),
+                &params,
+                None,
+            )
+            .await?;
+
+        Ok(Response::<MegalodonEntities::FilterStatus>::new(
+            res.json.into(),
+            res.status,
+            res.status_text,
+            res.header,
+        ))
+    }
+
+    async fn get_filter_status(
+        &self,
+        id: String,
+    ) -> Result<Response<MegalodonEntities::FilterStatus>, Error> {
+        let res = self
+            .client
+            .get::<entities::FilterStatus>(
+                format!("/api/v2/filters/statuses/{}", id).as_str(),
+                None,
+            )
+            .await?;
+
+        Ok(Response::<MegalodonEntities::FilterStatus>::new(
+            res.json.into(),
+            res.status,
+            res.status_text,
+            res.header,
+        ))
+    }
+
+    async fn remove_filter_status(&self, id: String) -> Result<Response<()>, Error> {
+        let params = HashMap::<&str, Value>::new();
+        let res = self
+            .client
+            .delete::<()>(
+                format!("/api/v2/filters/statuses/{}", id).as_str(),
+                &params,
+                None,
+            )
+            .await?;
+
+        Ok(res)
+    }
+
     async fn report(
         &self,
         account_id: String,
@@ -2127,6 +2578,18 @@ impl megalodon::Megalodon for Mastodon {
         ))
     }
 
+    async fn get_bubble_timeline(
+        &self,
+        _options: Option<&megalodon::GetLocalTimelineInputOptions>,
+    ) -> Result<Response<Vec<MegalodonEntities::Status>>, Error> {
+        Err(Error::new_own(
+            "Mastodon does not support".to_string(),
+            error::Kind::NoImplementedError,
+            None,
+            None,
+        ))
+    }
+
     async fn get_tag_timeline(
         &self,
         hashtag: String,
@@ -2754,6 +3217,40 @@ impl megalodon::Megalodon for Mastodon {
         ))
     }
 
+    async fn get_instance_v2(&self) -> Result<Response<MegalodonEntities::InstanceV2>, Error> {
+        match self
+            .client
+            .get::<entities::InstanceV2>("/api/v2/instance", None)
+            .await
+        {
+            Ok(res) => Ok(Response::<MegalodonEntities::InstanceV2>::new(
+                res.json.into(),
+                res.status,
+                res.status_text,
+                res.header,
+            )),
+            // Mastodon before 4.0 does not have v2 endpoint.
+            Err(err) if error::is_not_found(&err) => {
+                let res = self
+                    .client
+                    .get::<entities::Instance>("/api/v1/instance", None)
+                    .await?;
+                Ok(Response::<MegalodonEntities::InstanceV2>::new(
+                    res.json.into(),
+                    res.status,
+                    res.status_text,
+                    res.header,
+                ))
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    async fn capabilities(&self) -> Result<Capabilities, Error> {
+        let res = self.get_instance().await?;
+        Ok(Capabilities::new(&SNS::Mastodon, &res.json.version, &[]))
+    }
+
     async fn get_instance_peers(&self) -> Result<Response<Vec<String>>, Error> {
         let res = self
             .client
@@ -2803,6 +3300,96 @@ impl megalodon::Megalodon for Mastodon {
         ))
     }
 
+    async fn get_trend_tags(
+        &self,
+        options: Option<&megalodon::GetTrendsInputOptions>,
+    ) -> Result<Response<Vec<MegalodonEntities::Tag>>, Error> {
+        let mut params = Vec::<String>::new();
+        if let Some(options) = options {
+            if let Some(limit) = options.limit {
+                params.push(format!("limit={}", limit));
+            }
+            if let Some(offset) = options.offset {
+                params.push(format!("offset={}", offset));
+            }
+        }
+        let mut path = "/api/v1/trends/tags".to_string();
+        if !params.is_empty() {
+            path = path + "?" + params.join("&").as_str();
+        }
+        let res = self
+            .client
+            .get::<Vec<entities::Tag>>(path.as_str(), None)
+            .await?;
+
+        Ok(Response::<Vec<MegalodonEntities::Tag>>::new(
+            res.json.into_iter().map(|j| j.into()).collect(),
+            res.status,
+            res.status_text,
+            res.header,
+        ))
+    }
+
+    async fn get_trend_statuses(
+        &self,
+        options: Option<&megalodon::GetTrendsInputOptions>,
+    ) -> Result<Response<Vec<MegalodonEntities::Status>>, Error> {
+        let mut params = Vec::<String>::new();
+        if let Some(options) = options {
+            if let Some(limit) = options.limit {
+                params.push(format!("limit={}", limit));
+            }
+            if let Some(offset) = options.offset {
+                params.push(format!("offset={}", offset));
+            }
+        }
+        let mut path = "/api/v1/trends/statuses".to_string();
+        if !params.is_empty() {
+            path = path + "?" + params.join("&").as_str();
+        }
+        let res = self
+            .client
+            .get::<Vec<entities::Status>>(path.as_str(), None)
+            .await?;
+
+        Ok(Response::<Vec<MegalodonEntities::Status>>::new(
+            res.json.into_iter().map(|j| j.into()).collect(),
+            res.status,
+            res.status_text,
+            res.header,
+        ))
+    }
+
+    async fn get_trend_links(
+        &self,
+        options: Option<&megalodon::GetTrendsInputOptions>,
+    ) -> Result<Response<Vec<MegalodonEntities::TrendLink>>, Error> {
+        let mut params = Vec::<String>::new();
+        if let Some(options) = options {
+            if let Some(limit) = options.limit {
+                params.push(format!("limit={}", limit));
+            }
+            if let Some(offset) = options.offset {
+                params.push(format!("offset={}", offset));
+            }
+        }
+        let mut path = "/api/v1/trends/links".to_string();
+        if !params.is_empty() {
+            path = path + "?" + params.join("&").as_str();
+        }
+        let res = self
+            .client
+            .get::<Vec<entities::TrendLink>>(path.as_str(), None)
+            .await?;
+
+        Ok(Response::<Vec<MegalodonEntities::TrendLink>>::new(
+            res.json.into_iter().map(|j| j.into()).collect(),
+            res.status,
+            res.status_text,
+            res.header,
+        ))
+    }
+
     async fn get_instance_directory(
         &self,
         options: Option<&megalodon::GetInstanceDirectoryInputOptions>,
@@ -2855,6 +3442,82 @@ impl megalodon::Megalodon for Mastodon {
         ))
     }
 
+    async fn get_announcements(
+        &self,
+    ) -> Result<Response<Vec<MegalodonEntities::Announcement>>, Error> {
+        let res = self
+            .client
+            .get::<Vec<entities::Announcement>>("/api/v1/announcements", None)
+            .await?;
+
+        Ok(Response::<Vec<MegalodonEntities::Announcement>>::new(
+            res.json.into_iter().map(|j| j.into()).collect(),
+            res.status,
+            res.status_text,
+            res.header,
+        ))
+    }
+
+    async fn dismiss_announcement(&self, id: String) -> Result<Response<()>, Error> {
+        let params = HashMap::<&str, Value>::new();
+        let res = self
+            .client
+            .post::<()>(
+                format!("/api/v1/announcements/{}/dismiss", id).as_str(),
+                &params,
+                None,
+            )
+            .await?;
+
+        Ok(res)
+    }
+
+    async fn add_announcement_reaction(
+        &self,
+        id: String,
+        name: String,
+    ) -> Result<Response<()>, Error> {
+        let params = HashMap::<&str, Value>::new();
+        let res = self
+            .client
+            .put::<()>(
+                format!(
+                    "/api/v1/announcements/{}/reactions/{}",
+                    id,
+                    urlencoding::encode(&name)
+                )
+                .as_str(),
+                &params,
+                None,
+            )
+            .await?;
+
+        Ok(res)
+    }
+
+    async fn remove_announcement_reaction(
+        &self,
+        id: String,
+        name: String,
+    ) -> Result<Response<()>, Error> {
+        let params = HashMap::<&str, Value>::new();
+        let res = self
+            .client
+            .delete::<()>(
+                format!(
+                    "/api/v1/announcements/{}/reactions/{}",
+                    id,
+                    urlencoding::encode(&name)
+                )
+                .as_str(),
+                &params,
+                None,
+            )
+            .await?;
+
+        Ok(res)
+    }
+
     async fn create_emoji_reaction(
         &self,
         _id: String,
@@ -2914,6 +3577,7 @@ impl megalodon::Megalodon for Mastodon {
             Some(params),
             self.access_token.clone(),
             self.user_agent.clone(),
+            self.streaming_options.clone(),
         );
 
         Box::new(c)
@@ -2927,6 +3591,7 @@ impl megalodon::Megalodon for Mastodon {
             Some(params),
             self.access_token.clone(),
             self.user_agent.clone(),
+            self.streaming_options.clone(),
         );
 
         Box::new(c)
@@ -2940,6 +3605,7 @@ impl megalodon::Megalodon for Mastodon {
             Some(params),
             self.access_token.clone(),
             self.user_agent.clone(),
+            self.streaming_options.clone(),
         );
 
         Box::new(c)
@@ -2953,6 +3619,7 @@ impl megalodon::Megalodon for Mastodon {
             Some(params),
             self.access_token.clone(),
             self.user_agent.clone(),
+            self.streaming_options.clone(),
         );
 
         Box::new(c)
@@ -2970,6 +3637,7 @@ impl megalodon::Megalodon for Mastodon {
             Some(params),
             self.access_token.clone(),
             self.user_agent.clone(),
+            self.streaming_options.clone(),
         );
 
         Box::new(c)
@@ -2987,8 +3655,171 @@ impl megalodon::Megalodon for Mastodon {
             Some(params),
             self.access_token.clone(),
             self.user_agent.clone(),
+            self.streaming_options.clone(),
+        );
+
+        Box::new(c)
+    }
+
+    fn multiplexed_streaming(
+        &self,
+        streaming_url: String,
+    ) -> Box<dyn MultiplexedStreaming + Send + Sync> {
+        let c = WebSocket::new(
+            streaming_url + "/api/v1/streaming",
+            String::new(),
+            None,
+            self.access_token.clone(),
+            self.user_agent.clone(),
+            self.streaming_options.clone(),
         );
 
         Box::new(c)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::megalodon::Megalodon;
+    use crate::test_server::StubServer;
+    use oauth2::PkceCodeVerifier;
+
+    const APP: &str = r#"{"id":"1","name":"megalodon","website":null,"redirect_uri":"urn:ietf:wg:oauth:2.0:oob","client_id":"client_id","client_secret":"client_secret"}"#;
+
+    const TOKEN: &str =
+        r#"{"access_token":"access","token_type":"Bearer","scope":"read write","created_at":1000}"#;
+
+    #[tokio::test]
+    async fn test_register_app_generates_pkce_auth_url() {
+        let server = StubServer::start().await;
+        server.route("POST", "/api/v1/apps", 200, APP);
+        let client = Mastodon::new(server.base_url.clone(), None, None);
+
+        let app = client
+            .register_app(
+                "megalodon".to_string(),
+                &megalodon::AppInputOptions::default(),
+            )
+            .await
+            .unwrap();
+        let url = url::Url::parse(&app.url.unwrap()).unwrap();
+        assert_eq!(url.path(), "/oauth/authorize");
+        let query: HashMap<String, String> = url.query_pairs().into_owned().collect();
+        assert_eq!(query["client_id"], "client_id");
+        assert_eq!(query["response_type"], "code");
+        assert_eq!(query["code_challenge_method"], "S256");
+        assert_eq!(query["state"], app.state.unwrap());
+
+        let verifier = PkceCodeVerifier::new(app.code_verifier.unwrap());
+        let challenge = PkceCodeChallenge::from_code_verifier_sha256(&verifier);
+        assert_eq!(query["code_challenge"], challenge.as_str());
+    }
+
+    #[tokio::test]
+    async fn test_fetch_access_token_posts_code_verifier() {
+        let server = StubServer::start().await;
+        server.route("POST", "/oauth/token", 200, TOKEN);
+        let client = Mastodon::new(server.base_url.clone(), None, None);
+
+        let token = client
+            .fetch_access_token(
+                "client_id".to_string(),
+                "client_secret".to_string(),
+                "code".to_string(),
+                "urn:ietf:wg:oauth:2.0:oob".to_string(),
+                Some("verifier".to_string()),
+            )
+            .await
+            .unwrap();
+        assert_eq!(token.access_token, "access");
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 1);
+        let params = requests[0].json();
+        assert_eq!(params["grant_type"], "authorization_code");
+        assert_eq!(params["code"], "code");
+        assert_eq!(params["code_verifier"], "verifier");
+    }
+
+    #[tokio::test]
+    async fn test_fetch_app_token_uses_client_credentials() {
+        let server = StubServer::start().await;
+        server.route("POST", "/oauth/token", 200, TOKEN);
+        let client = Mastodon::new(server.base_url.clone(), None, None);
+
+        let token = client
+            .fetch_app_token(
+                "client_id".to_string(),
+                "client_secret".to_string(),
+                vec!["read".to_string(), "write".to_string()],
+            )
+            .await
+            .unwrap();
+        assert_eq!(token.access_token, "access");
+        assert_eq!(token.scope, "read write");
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 1);
+        assert!(!requests[0].headers.contains_key("authorization"));
+        let params = requests[0].json();
+        assert_eq!(params["grant_type"], "client_credentials");
+        assert_eq!(params["client_id"], "client_id");
+        assert_eq!(params["client_secret"], "client_secret");
+        assert_eq!(params["scope"], "read write");
+        assert!(params.get("code").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_announcement_paths() {
+        let server = StubServer::start().await;
+        server
+            .route("POST", "/api/v1/announcements/8/dismiss", 200, "{}")
+            .route(
+                "PUT",
+                "/api/v1/announcements/8/reactions/%F0%9F%91%8D",
+                200,
+                "{}",
+            )
+            .route(
+                "DELETE",
+                "/api/v1/announcements/8/reactions/blob%2Fcat",
+                200,
+                "{}",
+            );
+        let client = Mastodon::new(server.base_url.clone(), Some("token".to_string()), None);
+
+        client.dismiss_announcement("8".to_string()).await.unwrap();
+        client
+            .add_announcement_reaction("8".to_string(), "👍".to_string())
+            .await
+            .unwrap();
+        client
+            .remove_announcement_reaction("8".to_string(), "blob/cat".to_string())
+            .await
+            .unwrap();
+
+        let requests: Vec<(String, String)> = server
+            .requests()
+            .into_iter()
+            .map(|r| (r.method, r.path))
+            .collect();
+        assert_eq!(
+            requests,
+            vec![
+                (
+                    "POST".to_string(),
+                    "/api/v1/announcements/8/dismiss".to_string()
+                ),
+                (
+                    "PUT".to_string(),
+                    "/api/v1/announcements/8/reactions/%F0%9F%91%8D".to_string()
+                ),
+                (
+                    "DELETE".to_string(),
+                    "/api/v1/announcements/8/reactions/blob%2Fcat".to_string()
+                ),
+            ]
+        );
+    }
+}
@@ -1,7 +1,7 @@
 //! Mastodon related modules
 
-mod api_client;
-mod entities;
+pub(crate) mod api_client;
+pub(crate) mod entities;
 /// Mastodon API client.
 pub mod mastodon;
 mod oauth;
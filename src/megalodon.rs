@@ -3,9 +3,11 @@
 use core::fmt;
 use std::str::FromStr;
 
+use crate::capabilities::Capabilities;
 use crate::error::{Error, Kind};
 use crate::oauth::{AppData, TokenData};
 use crate::response::Response;
+use crate::streaming::MultiplexedStreaming;
 use crate::{entities, Streaming};
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
@@ -33,12 +35,14 @@ pub trait Megalodon {
     // ======================================
     /// Fetch OAuth access token.
     /// Get an access token based client_id, client_secret and authorization_code.
+    /// `code_verifier` is the PKCE code verifier, which is returned in [`AppData::code_verifier`] by [`Megalodon::register_app`].
     async fn fetch_access_token(
         &self,
         client_id: String,
         client_secret: String,
         code: String,
         redirect_uri: String,
+        code_verifier: Option<String>,
     ) -> Result<TokenData, Error>;
 
     /// Refresh OAuth access token.
@@ -50,6 +54,16 @@ pub trait Megalodon {
         refresh_token: String,
     ) -> Result<TokenData, Error>;
 
+    /// Get an access token for the application itself with client credentials grant.
+    /// The token is not related to any user, and it is used for [`Megalodon::verify_app_credentials`], [`Megalodon::register_account`] and reading public data.
+    /// When `scopes` is empty, the default scope of the server is used.
+    async fn fetch_app_token(
+        &self,
+        client_id: String,
+        client_secret: String,
+        scopes: Vec<String>,
+    ) -> Result<TokenData, Error>;
+
     /// Revoke an access token.
     async fn revoke_access_token(
         &self,
@@ -62,6 +76,7 @@ pub trait Megalodon {
     // apps
     // ======================================
     /// Test to make sure that the application token works.
+    /// Both an app token from [`Megalodon::fetch_app_token`] and an user access token are accepted.
     async fn verify_app_credentials(&self) -> Result<Response<entities::Application>, Error>;
 
     // ======================================
@@ -258,6 +273,86 @@ pub trait Megalodon {
     /// Delete a filter.
     async fn delete_filter(&self, id: String) -> Result<Response<()>, Error>;
 
+    // ======================================
+    // filters v2
+    // ======================================
+    /// Get all filters with v2 API.
+    async fn get_filters_v2(&self) -> Result<Response<Vec<entities::FilterV2>>, Error>;
+
+    /// Get a specified filter with v2 API.
+    async fn get_filter_v2(&self, id: String) -> Result<Response<entities::FilterV2>, Error>;
+
+    /// Create a filter with v2 API.
+    async fn create_filter_v2(
+        &self,
+        title: String,
+        context: Vec<entities::filter::FilterContext>,
+        options: Option<&FilterV2InputOptions>,
+    ) -> Result<Response<entities::FilterV2>, Error>;
+
+    /// Update a filter with v2 API.
+    async fn update_filter_v2(
+        &self,
+        id: String,
+        options: Option<&UpdateFilterV2InputOptions>,
+    ) -> Result<Response<entities::FilterV2>, Error>;
+
+    /// Delete a filter with v2 API.
+    async fn delete_filter_v2(&self, id: String) -> Result<Response<()>, Error>;
+
+    /// Get all keywords of the filter.
+    async fn get_filter_keywords(
+        &self,
+        filter_id: String,
+    ) -> Result<Response<Vec<entities::FilterKeyword>>, Error>;
+
+    /// Add a keyword to the filter.
+    async fn add_filter_keyword(
+        &self,
+        filter_id: String,
+        keyword: String,
+        whole_word: Option<bool>,
+    ) -> Result<Response<entities::FilterKeyword>, Error>;
+
+    /// Get a specified keyword.
+    async fn get_filter_keyword(
+        &self,
+        id: String,
+    ) -> Result<Response<entities::FilterKeyword>, Error>;
+
+    /// Update a keyword.
+    async fn update_filter_keyword(
+        &self,
+        id: String,
+        keyword: String,
+        whole_word: Option<bool>,
+    ) -> Result<Response<entities::FilterKeyword>, Error>;
+
+    /// Remove a keyword from the filter.
+    async fn remove_filter_keyword(&self, id: String) -> Result<Response<()>, Error>;
+
+    /// Get all status filters of the filter.
+    async fn get_filter_statuses(
+        &self,
+        filter_id: String,
+    ) -> Result<Response<Vec<entities::FilterStatus>>, Error>;
+
+    /// Add a status to the filter.
+    async fn add_filter_status(
+        &self,
+        filter_id: String,
+        status_id: String,
+    ) -> Result<Response<entities::FilterStatus>, Error>;
+
+    /// Get a specified status filter.
+    async fn get_filter_status(
+        &self,
+        id: String,
+    ) -> Result<Response<entities::FilterStatus>, Error>;
+
+    /// Remove a status from the filter.
+    async fn remove_filter_status(&self, id: String) -> Result<Response<()>, Error>;
+
     // ======================================
     // accounts/reports
     // ======================================
@@ -488,6 +583,13 @@ pub trait Megalodon {
         options: Option<&GetLocalTimelineInputOptions>,
     ) -> Result<Response<Vec<entities::Status>>, Error>;
 
+    /// Get statuses of bubble timeline, which contains statuses from instances chosen by the admin.
+    /// Only Akkoma supports it.
+    async fn get_bubble_timeline(
+        &self,
+        options: Option<&GetLocalTimelineInputOptions>,
+    ) -> Result<Response<Vec<entities::Status>>, Error>;
+
     /// Get statuses of tag timeline.
     async fn get_tag_timeline(
         &self,
@@ -640,6 +742,13 @@ pub trait Megalodon {
     /// Get information about the server.
     async fn get_instance(&self) -> Result<Response<entities::Instance>, Error>;
 
+    /// Get information about the server with v2 endpoint.
+    /// On servers which do not have it, it is synthesized from v1 endpoint, so some values are empty.
+    async fn get_instance_v2(&self) -> Result<Response<entities::InstanceV2>, Error>;
+
+    /// Get features which the server supports, to check them before calling methods.
+    async fn capabilities(&self) -> Result<Capabilities, Error>;
+
     /// Get domains that this instance is aware of.
     async fn get_instance_peers(&self) -> Result<Response<Vec<String>>, Error>;
 
@@ -655,6 +764,24 @@ pub trait Megalodon {
         limit: Option<u32>,
     ) -> Result<Response<Vec<entities::Tag>>, Error>;
 
+    /// Tags that are being used more frequently within the past week, with offset pagination.
+    async fn get_trend_tags(
+        &self,
+        options: Option<&GetTrendsInputOptions>,
+    ) -> Result<Response<Vec<entities::Tag>>, Error>;
+
+    /// Statuses that have been interacted with more than others.
+    async fn get_trend_statuses(
+        &self,
+        options: Option<&GetTrendsInputOptions>,
+    ) -> Result<Response<Vec<entities::Status>>, Error>;
+
+    /// Links that have been shared more than others.
+    async fn get_trend_links(
+        &self,
+        options: Option<&GetTrendsInputOptions>,
+    ) -> Result<Response<Vec<entities::TrendLink>>, Error>;
+
     // ======================================
     // instance/directory
     // ======================================
@@ -670,6 +797,29 @@ pub trait Megalodon {
     /// Returns custom emojis that are available on the server.
     async fn get_instance_custom_emojis(&self) -> Result<Response<Vec<entities::Emoji>>, Error>;
 
+    // ======================================
+    // instance/announcements
+    // ======================================
+    /// Get all currently active announcements set by admins.
+    async fn get_announcements(&self) -> Result<Response<Vec<entities::Announcement>>, Error>;
+
+    /// Dismiss an announcement.
+    async fn dismiss_announcement(&self, id: String) -> Result<Response<()>, Error>;
+
+    /// Add an emoji reaction to the announcement.
+    async fn add_announcement_reaction(
+        &self,
+        id: String,
+        name: String,
+    ) -> Result<Response<()>, Error>;
+
+    /// Remove the emoji reaction from the announcement.
+    async fn remove_announcement_reaction(
+        &self,
+        id: String,
+        name: String,
+    ) -> Result<Response<()>, Error>;
+
     // ======================================
     // Emoji reactions
     // ======================================
@@ -725,6 +875,12 @@ pub trait Megalodon {
         streaming_url: String,
         list_id: String,
     ) -> Box<dyn Streaming + Send + Sync>;
+
+    /// Get streaming object which subscribes multiple streams over a single connection.
+    fn multiplexed_streaming(
+        &self,
+        streaming_url: String,
+    ) -> Box<dyn MultiplexedStreaming + Send + Sync>;
 }
 
 /// Input options for [`Megalodon::register_app`] and [`Megalodon::create_app`].
@@ -885,6 +1041,45 @@ pub struct FilterInputOptions {
     pub expires_in: Option<u64>,
 }
 
+/// Input options for [`Megalodon::create_filter_v2`].
+#[derive(Debug, Clone, Default)]
+pub struct FilterV2InputOptions {
+    /// The policy to be applied when the filter is matched. Default is warn.
+    pub filter_action: Option<entities::filter_v2::FilterAction>,
+    /// Number of seconds from now the filter should expire.
+    pub expires_in: Option<u64>,
+    /// Keywords to be added to the filter.
+    pub keywords_attributes: Option<Vec<FilterKeywordAttributes>>,
+}
+
+/// Input options for [`Megalodon::update_filter_v2`].
+#[derive(Debug, Clone, Default)]
+pub struct UpdateFilterV2InputOptions {
+    /// The name of the filter group.
+    pub title: Option<String>,
+    /// Where the filter should be applied.
+    pub context: Option<Vec<entities::filter::FilterContext>>,
+    /// The policy to be applied when the filter is matched.
+    pub filter_action: Option<entities::filter_v2::FilterAction>,
+    /// Number of seconds from now the filter should expire.
+    pub expires_in: Option<u64>,
+    /// Keywords to be added, updated or destroyed.
+    pub keywords_attributes: Option<Vec<FilterKeywordAttributes>>,
+}
+
+/// Keyword in [`FilterV2InputOptions`] and [`UpdateFilterV2InputOptions`].
+#[derive(Debug, Clone, Default)]
+pub struct FilterKeywordAttributes {
+    /// ID of the keyword to update or destroy. A new keyword is added when it is `None`.
+    pub id: Option<String>,
+    /// The keyword to be filtered.
+    pub keyword: Option<String>,
+    /// Whether the keyword should consider word boundaries.
+    pub whole_word: Option<bool>,
+    /// Destroy the keyword which is specified with `id`.
+    pub destroy: bool,
+}
+
 /// Input options for [`Megalodon::report`].
 #[derive(Debug, Clone, Default)]
 pub struct ReportInputOptions {
@@ -1174,6 +1369,15 @@ pub struct SearchInputOptions {
     pub exclude_unreviewed: Option<bool>,
 }
 
+/// Input options for [`Megalodon::get_trend_tags`], [`Megalodon::get_trend_statuses`] and [`Megalodon::get_trend_links`].
+#[derive(Debug, Clone, Default)]
+pub struct GetTrendsInputOptions {
+    /// Maximum number of results to return.
+    pub limit: Option<u32>,
+    /// Skip the first n results.
+    pub offset: Option<u64>,
+}
+
 /// Input options for [`Megalodon::get_instance_directory`].
 #[derive(Debug, Clone, Default)]
 pub struct GetInstanceDirectoryInputOptions {
@@ -0,0 +1,225 @@
+use crate::default::{self, DEFAULT_UA};
+use crate::error::{Error as MegalodonError, Kind};
+use crate::rate_limit::{self, RetryPolicy};
+use crate::response::Response;
+use reqwest::header::HeaderMap;
+use reqwest::{RequestBuilder, Url};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt::Debug;
+
+/// API client for Misskey. All endpoints are called with `POST`, and the access token is sent as `i` in the body.
+#[derive(Debug, Clone)]
+pub struct APIClient {
+    access_token: Option<String>,
+    base_url: String,
+    client: reqwest::Client,
+    retry_policy: Option<RetryPolicy>,
+}
+
+impl APIClient {
+    pub fn new(base_url: String, access_token: Option<String>, user_agent: Option<String>) -> Self {
+        let ua = user_agent.unwrap_or(DEFAULT_UA.to_string());
+        let client = default::http_client(&ua);
+
+        Self::with_client(base_url, access_token, client)
+    }
+
+    /// Create a new [`APIClient`] which shares the given HTTP client across all requests.
+    pub fn with_client(
+        base_url: String,
+        access_token: Option<String>,
+        client: reqwest::Client,
+    ) -> Self {
+        Self {
+            access_token,
+            base_url,
+            client,
+            retry_policy: None,
+        }
+    }
+
+    /// Set a policy to retry failed requests. Requests are not retried when it is `None`.
+    pub fn set_retry_policy(&mut self, retry_policy: Option<RetryPolicy>) {
+        self.retry_policy = retry_policy;
+    }
+
+    pub async fn post<T>(
+        &self,
+        path: &str,
+        params: &HashMap<&str, Value>,
+        headers: Option<HeaderMap>,
+    ) -> Result<Response<T>, MegalodonError>
+    where
+        T: DeserializeOwned + Debug,
+    {
+        let url_str = format!("{}{}", self.base_url, path);
+        let url = Url::parse(&url_str)?;
+        let mut req = self.client.post(url);
+        if let Some(headers) = headers {
+            req = req.headers(headers);
+        }
+        let mut body = params.clone();
+        if let Some(token) = &self.access_token {
+            body.insert("i", Value::String(token.clone()));
+        }
+
+        // All endpoints are called with POST, so we can not know which request is idempotent.
+        self.send::<T>(req.json(&body), url_str, false).await
+    }
+
+    pub async fn post_multipart<T>(
+        &self,
+        path: &str,
+        params: reqwest::multipart::Form,
+        headers: Option<HeaderMap>,
+    ) -> Result<Response<T>, MegalodonError>
+    where
+        T: DeserializeOwned + Debug,
+    {
+        let url_str = format!("{}{}", self.base_url, path);
+        let url = Url::parse(&url_str)?;
+        let mut req = self.client.post(url);
+        if let Some(headers) = headers {
+            req = req.headers(headers);
+        }
+        let mut form = params;
+        if let Some(token) = &self.access_token {
+            form = form.text("i", token.clone());
+        }
+
+        self.send::<T>(req.multipart(form), url_str, false).await
+    }
+
+    /// Send the request, and retry it according to the retry policy.
+    /// Multipart requests can not be cloned, so they are never retried.
+    async fn send<T>(
+        &self,
+        req: RequestBuilder,
+        url_str: String,
+        idempotent: bool,
+    ) -> Result<Response<T>, MegalodonError>
+    where
+        T: DeserializeOwned + Debug,
+    {
+        let mut req = req;
+        let mut attempt: u32 = 0;
+        loop {
+            let retry = self
+                .retry_policy
+                .as_ref()
+                .and_then(|policy| req.try_clone().map(|next| (policy, next)));
+            let err = match self.execute::<T>(req, &url_str).await {
+                Ok(res) => return Ok(res),
+                Err(err) => err,
+            };
+            let Some((policy, next)) = retry else {
+                return Err(err);
+            };
+            let Some(delay) = policy.retry_delay(attempt, &err, idempotent) else {
+                return Err(err);
+            };
+            log::warn!("Retrying {} in {:?} because of {}", url_str, delay, err);
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+            req = next;
+        }
+    }
+
+    async fn execute<T>(
+        &self,
+        req: RequestBuilder,
+        url_str: &str,
+    ) -> Result<Response<T>, MegalodonError>
+    where
+        T: DeserializeOwned + Debug,
+    {
+        let res = req.send().await?;
+        let status = res.status();
+        match status {
+            reqwest::StatusCode::OK
+            | reqwest::StatusCode::CREATED
+            | reqwest::StatusCode::ACCEPTED
+            | reqwest::StatusCode::NO_CONTENT => {
+                let res = Response::<T>::from_reqwest(res).await?;
+                Ok(res)
+            }
+            reqwest::StatusCode::TOO_MANY_REQUESTS => {
+                let reset_at = rate_limit::reset_at(res.headers());
+                Err(MegalodonError::new_http(
+                    res.text()
+                        .await
+                        .unwrap_or_else(|_| "Too many requests".to_string()),
+                    Kind::RateLimited { reset_at },
+                    Some(url_str.to_string()),
+                    Some(status.as_u16()),
+                ))
+            }
+            _ => Err(MegalodonError::new_http(
+                res.text()
+                    .await
+                    .unwrap_or_else(|_| "Unknown error".to_string()),
+                Kind::from_status(status.as_u16()),
+                Some(url_str.to_string()),
+                Some(status.as_u16()),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::StubServer;
+
+    #[tokio::test]
+    async fn test_post_sends_access_token_as_i() {
+        let server = StubServer::start().await;
+        server.route("POST", "/api/i", 200, r#"{"id":"8z"}"#);
+        let client = APIClient::new(server.base_url.clone(), Some("token".to_string()), None);
+
+        let mut params = HashMap::<&str, Value>::new();
+        params.insert("detail", Value::Bool(true));
+        client.post::<Value>("/api/i", &params, None).await.unwrap();
+
+        let requests = server.requests();
+        assert_eq!(requests[0].method, "POST");
+        assert_eq!(
+            requests[0].json(),
+            serde_json::json!({"detail": true, "i": "token"})
+        );
+        assert!(!requests[0].headers.contains_key("authorization"));
+    }
+
+    #[tokio::test]
+    async fn test_post_without_access_token() {
+        let server = StubServer::start().await;
+        server.route("POST", "/api/meta", 200, r#"{"name":"misskey"}"#);
+        let client = APIClient::new(server.base_url.clone(), None, None);
+
+        client
+            .post::<Value>("/api/meta", &HashMap::new(), None)
+            .await
+            .unwrap();
+
+        assert_eq!(server.requests()[0].json(), serde_json::json!({}));
+    }
+
+    #[tokio::test]
+    async fn test_post_multipart_sends_access_token_as_i() {
+        let server = StubServer::start().await;
+        server.route("POST", "/api/drive/files/create", 200, r#"{"id":"1"}"#);
+        let client = APIClient::new(server.base_url.clone(), Some("token".to_string()), None);
+
+        let form = reqwest::multipart::Form::new().text("name", "file.png");
+        client
+            .post_multipart::<Value>("/api/drive/files/create", form, None)
+            .await
+            .unwrap();
+
+        let body = &server.requests()[0].body;
+        assert!(body.contains("name=\"i\"\r\n\r\ntoken\r\n"));
+        assert!(body.contains("name=\"name\"\r\n\r\nfile.png\r\n"));
+    }
+}
@@ -0,0 +1,48 @@
+use super::note::to_html;
+use crate::entities as MegalodonEntities;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+
+/// Response item of `announcements` endpoint.
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Announcement {
+    pub id: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: Option<DateTime<Utc>>,
+    pub title: String,
+    pub text: String,
+    pub image_url: Option<String>,
+    pub is_read: Option<bool>,
+}
+
+/// Misskey announcements have a title and an image, so they are put into the content.
+/// They do not have schedules and reactions.
+impl From<Announcement> for MegalodonEntities::Announcement {
+    fn from(item: Announcement) -> Self {
+        let mut content = format!(
+            "<p><strong>{}</strong></p><p>{}</p>",
+            to_html(&item.title),
+            to_html(&item.text)
+        );
+        if let Some(image_url) = item.image_url {
+            content += &format!("<p><img src=\"{}\"></p>", to_html(&image_url));
+        }
+        MegalodonEntities::Announcement {
+            id: item.id,
+            content,
+            starts_at: None,
+            ends_at: None,
+            published: true,
+            all_day: false,
+            published_at: item.created_at,
+            updated_at: item.updated_at,
+            read: item.is_read,
+            mentions: Vec::new(),
+            statuses: Vec::new(),
+            tags: Vec::new(),
+            emojis: Vec::new(),
+            reactions: Vec::new(),
+        }
+    }
+}
@@ -0,0 +1,66 @@
+use crate::entities as MegalodonEntities;
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DriveFile {
+    pub id: String,
+    r#type: String,
+    is_sensitive: bool,
+    blurhash: Option<String>,
+    properties: Option<DriveFileProperties>,
+    url: Option<String>,
+    thumbnail_url: Option<String>,
+    comment: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+struct DriveFileProperties {
+    width: Option<u32>,
+    height: Option<u32>,
+}
+
+impl DriveFile {
+    pub fn is_sensitive(&self) -> bool {
+        self.is_sensitive
+    }
+}
+
+impl From<DriveFile> for MegalodonEntities::Attachment {
+    fn from(item: DriveFile) -> Self {
+        let r#type = match item.r#type.split('/').next() {
+            Some("image") => MegalodonEntities::attachment::AttachmentType::Image,
+            Some("video") => MegalodonEntities::attachment::AttachmentType::Video,
+            Some("audio") => MegalodonEntities::attachment::AttachmentType::Audio,
+            _ => MegalodonEntities::attachment::AttachmentType::Unknown,
+        };
+        let meta =
+            item.properties
+                .map(|properties| MegalodonEntities::attachment::AttachmentMeta {
+                    original: None,
+                    small: None,
+                    focus: None,
+                    length: None,
+                    duration: None,
+                    fps: None,
+                    size: None,
+                    width: properties.width,
+                    height: properties.height,
+                    aspect: None,
+                    audio_encode: None,
+                    audio_bitrate: None,
+                    audio_channel: None,
+                });
+        MegalodonEntities::Attachment {
+            id: item.id,
+            r#type,
+            url: item.url.unwrap_or_default(),
+            remote_url: None,
+            preview_url: item.thumbnail_url,
+            text_url: None,
+            meta,
+            description: item.comment,
+            blurhash: item.blurhash,
+        }
+    }
+}
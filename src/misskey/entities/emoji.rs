@@ -0,0 +1,53 @@
+use crate::entities as MegalodonEntities;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct Emoji {
+    name: String,
+    url: String,
+}
+
+impl From<Emoji> for MegalodonEntities::Emoji {
+    fn from(item: Emoji) -> Self {
+        MegalodonEntities::Emoji {
+            shortcode: item.name,
+            static_url: item.url.clone(),
+            url: item.url,
+            visible_in_picker: true,
+        }
+    }
+}
+
+/// Custom emojis in notes and users.
+/// Older Misskey returns them as an array, and newer one returns a map from name to URL.
+#[derive(Debug, Deserialize, Clone)]
+#[serde(untagged)]
+pub enum Emojis {
+    List(Vec<Emoji>),
+    Map(HashMap<String, String>),
+}
+
+impl Default for Emojis {
+    fn default() -> Self {
+        Emojis::List(Vec::new())
+    }
+}
+
+impl From<Emojis> for Vec<MegalodonEntities::Emoji> {
+    fn from(item: Emojis) -> Self {
+        match item {
+            Emojis::List(list) => list.into_iter().map(|i| i.into()).collect(),
+            Emojis::Map(map) => map
+                .into_iter()
+                .map(|(name, url)| Emoji { name, url }.into())
+                .collect(),
+        }
+    }
+}
+
+/// Response of `emojis` endpoint.
+#[derive(Debug, Deserialize, Clone)]
+pub struct EmojisResponse {
+    pub emojis: Vec<Emoji>,
+}
@@ -0,0 +1,8 @@
+use super::Note;
+use serde::Deserialize;
+
+/// Response of `i/favorites` endpoint.
+#[derive(Debug, Deserialize, Clone)]
+pub struct Favorite {
+    pub note: Note,
+}
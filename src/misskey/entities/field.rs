@@ -0,0 +1,18 @@
+use crate::entities as MegalodonEntities;
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct Field {
+    name: String,
+    value: String,
+}
+
+impl From<Field> for MegalodonEntities::Field {
+    fn from(item: Field) -> Self {
+        MegalodonEntities::Field {
+            name: item.name,
+            value: item.value,
+            verified_at: None,
+        }
+    }
+}
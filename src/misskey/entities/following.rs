@@ -0,0 +1,27 @@
+use super::User;
+use serde::Deserialize;
+
+/// Response of `users/followers` and `users/following` endpoints.
+#[derive(Debug, Deserialize, Clone)]
+pub struct Following {
+    pub followee: Option<User>,
+    pub follower: Option<User>,
+}
+
+/// Response of `following/requests/list` endpoint.
+#[derive(Debug, Deserialize, Clone)]
+pub struct FollowRequest {
+    pub follower: User,
+}
+
+/// Response of `blocking/list` endpoint.
+#[derive(Debug, Deserialize, Clone)]
+pub struct Blocking {
+    pub blockee: User,
+}
+
+/// Response of `mute/list` endpoint.
+#[derive(Debug, Deserialize, Clone)]
+pub struct Muting {
+    pub mutee: User,
+}
@@ -0,0 +1,16 @@
+use serde::Deserialize;
+
+/// Response of `hashtags/show` endpoint.
+#[derive(Debug, Deserialize, Clone)]
+pub struct Hashtag {
+    pub tag: String,
+}
+
+/// Response of `hashtags/trend` endpoint.
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct HashtagTrend {
+    pub tag: String,
+    pub chart: Vec<u32>,
+    pub users_count: u32,
+}
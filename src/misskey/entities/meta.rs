@@ -0,0 +1,33 @@
+use serde::Deserialize;
+
+/// Response of `meta` endpoint.
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Meta {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub maintainer_email: Option<String>,
+    pub version: String,
+    pub uri: String,
+    #[serde(default)]
+    pub langs: Vec<String>,
+    #[serde(default)]
+    pub disable_registration: bool,
+    pub max_note_text_length: u32,
+    pub banner_url: Option<String>,
+}
+
+/// Response of `stats` endpoint.
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Stats {
+    pub original_users_count: u32,
+    pub original_notes_count: u64,
+    pub instances: u32,
+}
+
+/// Response of `federation/instances` endpoint.
+#[derive(Debug, Deserialize, Clone)]
+pub struct FederationInstance {
+    pub host: String,
+}
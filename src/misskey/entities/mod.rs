@@ -0,0 +1,31 @@
+pub mod announcement;
+pub mod drive_file;
+pub mod emoji;
+pub mod favorite;
+pub mod field;
+pub mod following;
+pub mod hashtag;
+pub mod meta;
+pub mod note;
+pub mod notification;
+pub mod poll;
+pub mod reaction;
+pub mod relation;
+pub mod user;
+pub mod user_list;
+
+pub use announcement::Announcement;
+pub use drive_file::DriveFile;
+pub use emoji::{Emoji, Emojis, EmojisResponse};
+pub use favorite::Favorite;
+pub use field::Field;
+pub use following::{Blocking, FollowRequest, Following, Muting};
+pub use hashtag::{Hashtag, HashtagTrend};
+pub use meta::{FederationInstance, Meta, Stats};
+pub use note::{CreatedNote, Note, Visibility};
+pub use notification::{Notification, NotificationType};
+pub use poll::Poll;
+pub use reaction::NoteReaction;
+pub use relation::Relation;
+pub use user::User;
+pub use user_list::UserList;
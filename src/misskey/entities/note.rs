@@ -0,0 +1,195 @@
+use super::{DriveFile, Emojis, Poll, User};
+use crate::entities as MegalodonEntities;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Note {
+    pub id: String,
+    created_at: DateTime<Utc>,
+    pub user: User,
+    text: Option<String>,
+    cw: Option<String>,
+    visibility: Visibility,
+    #[serde(default)]
+    renote_count: u32,
+    #[serde(default)]
+    replies_count: u32,
+    #[serde(default)]
+    reactions: HashMap<String, u32>,
+    my_reaction: Option<String>,
+    #[serde(default)]
+    emojis: Emojis,
+    #[serde(default)]
+    files: Vec<DriveFile>,
+    reply_id: Option<String>,
+    reply: Option<Box<Note>>,
+    renote: Option<Box<Note>>,
+    uri: Option<String>,
+    url: Option<String>,
+    tags: Option<Vec<String>>,
+    poll: Option<Poll>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Visibility {
+    Public,
+    Home,
+    Followers,
+    Specified,
+}
+
+impl From<MegalodonEntities::StatusVisibility> for Visibility {
+    fn from(item: MegalodonEntities::StatusVisibility) -> Self {
+        match item {
+            MegalodonEntities::StatusVisibility::Public => Visibility::Public,
+            MegalodonEntities::StatusVisibility::Unlisted => Visibility::Home,
+            MegalodonEntities::StatusVisibility::Private => Visibility::Followers,
+            MegalodonEntities::StatusVisibility::Direct => Visibility::Specified,
+        }
+    }
+}
+
+impl From<Visibility> for MegalodonEntities::StatusVisibility {
+    fn from(item: Visibility) -> Self {
+        match item {
+            Visibility::Public => MegalodonEntities::StatusVisibility::Public,
+            Visibility::Home => MegalodonEntities::StatusVisibility::Unlisted,
+            Visibility::Followers => MegalodonEntities::StatusVisibility::Private,
+            Visibility::Specified => MegalodonEntities::StatusVisibility::Direct,
+        }
+    }
+}
+
+impl Note {
+    /// Whether the note is a pure renote, which does not have its own content.
+    pub fn is_pure_renote(&self) -> bool {
+        self.renote.is_some() && self.text.is_none() && self.files.is_empty() && self.poll.is_none()
+    }
+
+    /// Get the renoted note.
+    pub fn renote(&self) -> Option<&Note> {
+        self.renote.as_deref()
+    }
+
+    /// Convert to a status. Local notes do not have URI, so it is generated from `base_url`.
+    /// A pure renote is converted to a reblog, and a renote with text is converted to a quote.
+    pub fn into_status(self, base_url: &str) -> MegalodonEntities::Status {
+        let reblog = self.is_pure_renote();
+        let uri = self
+            .uri
+            .unwrap_or_else(|| format!("{}/notes/{}", base_url, self.id));
+        let url = self.url.unwrap_or_else(|| uri.clone());
+        let favourites_count = self.reactions.values().sum();
+        let emoji_reactions = self
+            .reactions
+            .into_iter()
+            .map(|(name, count)| MegalodonEntities::Reaction {
+                me: self.my_reaction.as_ref() == Some(&name),
+                count,
+                name,
+                accounts: None,
+            })
+            .collect();
+        let tags = self
+            .tags
+            .unwrap_or_default()
+            .into_iter()
+            .map(|tag| MegalodonEntities::Tag {
+                url: format!("{}/tags/{}", base_url, tag),
+                name: tag,
+                history: None,
+                following: None,
+            })
+            .collect();
+        let renote = self
+            .renote
+            .map(|renote| Box::new(renote.into_status(base_url)));
+        MegalodonEntities::Status {
+            id: self.id.clone(),
+            uri,
+            url: Some(url),
+            in_reply_to_id: self.reply_id,
+            in_reply_to_account_id: self.reply.map(|reply| reply.user.id),
+            content: self.text.as_deref().map(to_html).unwrap_or_default(),
+            plain_content: self.text.clone(),
+            mfm_content: self.text.clone(),
+            created_at: self.created_at,
+            emojis: self.emojis.into(),
+            replies_count: self.replies_count,
+            reblogs_count: self.renote_count,
+            favourites_count,
+            reblogged: None,
+            favourited: None,
+            muted: None,
+            sensitive: self.files.iter().any(|file| file.is_sensitive()),
+            spoiler_text: self.cw.unwrap_or_default(),
+            visibility: self.visibility.into(),
+            media_attachments: self.files.into_iter().map(|i| i.into()).collect(),
+            mentions: [].to_vec(),
+            tags,
+            card: None,
+            poll: self.poll.map(|poll| poll.into_poll(self.id)),
+            application: None,
+            language: None,
+            pinned: None,
+            emoji_reactions: Some(emoji_reactions),
+            quote: renote.is_some() && !reblog,
+            bookmarked: None,
+            filtered: None,
+            account: self.user.into_account(base_url),
+            reblog: renote,
+        }
+    }
+}
+
+/// Convert MFM text to HTML, which is expected in status content.
+pub(crate) fn to_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\n', "<br>")
+}
+
+/// Response of `notes/create` endpoint.
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CreatedNote {
+    pub created_note: Note,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_renote_into_status() {
+        let text = r#"{"id":"9a1b2c3d4e","createdAt":"2023-01-05T12:00:00.000Z","userId":"8z","user":{"id":"8z","name":null,"username":"alice","host":null,"avatarUrl":"https://misskey.example/avatar.png","isBot":false,"emojis":{}},"text":null,"cw":null,"visibility":"home","renoteCount":0,"repliesCount":0,"reactions":{},"fileIds":[],"files":[],"replyId":null,"renoteId":"9a0","renote":{"id":"9a0","createdAt":"2023-01-04T12:00:00.000Z","userId":"8y","user":{"id":"8y","name":"Bob","username":"bob","host":"remote.example","avatarUrl":null,"isBot":false,"emojis":{"blob":"https://remote.example/blob.png"}},"text":"Hello <world>\n:blob:","cw":null,"visibility":"public","renoteCount":1,"repliesCount":0,"reactions":{"👍":2,":blob@.:":1},"myReaction":"👍","emojis":{"blob":"https://remote.example/blob.png"},"files":[],"uri":"https://remote.example/notes/1"}}"#;
+
+        let status = serde_json::from_str::<Note>(text)
+            .unwrap()
+            .into_status("https://misskey.example");
+        assert_eq!(status.uri, "https://misskey.example/notes/9a1b2c3d4e");
+        assert!(matches!(
+            status.visibility,
+            MegalodonEntities::StatusVisibility::Unlisted
+        ));
+        assert!(!status.quote);
+        assert_eq!(status.account.acct, "alice");
+
+        let reblog = status.reblog.unwrap();
+        assert_eq!(reblog.uri, "https://remote.example/notes/1");
+        assert_eq!(reblog.content, "Hello &lt;world&gt;<br>:blob:");
+        assert_eq!(reblog.account.acct, "bob@remote.example");
+        assert_eq!(reblog.emojis.len(), 1);
+        assert_eq!(reblog.favourites_count, 3);
+        let reactions = reblog.emoji_reactions.unwrap();
+        assert!(reactions
+            .iter()
+            .any(|r| r.name == "👍" && r.count == 2 && r.me));
+    }
+}
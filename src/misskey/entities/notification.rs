@@ -0,0 +1,111 @@
+use super::{Note, User};
+use crate::entities as MegalodonEntities;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Notification {
+    id: String,
+    created_at: DateTime<Utc>,
+    r#type: NotificationType,
+    user: Option<User>,
+    note: Option<Note>,
+    reaction: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum NotificationType {
+    Follow,
+    Mention,
+    Reply,
+    Renote,
+    Quote,
+    Reaction,
+    PollVote,
+    PollEnded,
+    ReceiveFollowRequest,
+    FollowRequestAccepted,
+    #[serde(other)]
+    Unknown,
+}
+
+impl NotificationType {
+    /// Get Misskey notification types which correspond to the megalodon notification type.
+    pub fn from_megalodon(
+        item: &MegalodonEntities::notification::NotificationType,
+    ) -> Vec<&'static str> {
+        match item {
+            MegalodonEntities::notification::NotificationType::Follow => {
+                ["follow", "followRequestAccepted"].to_vec()
+            }
+            MegalodonEntities::notification::NotificationType::FollowRequest => {
+                ["receiveFollowRequest"].to_vec()
+            }
+            MegalodonEntities::notification::NotificationType::Mention => {
+                ["mention", "reply", "quote"].to_vec()
+            }
+            MegalodonEntities::notification::NotificationType::Reblog => ["renote"].to_vec(),
+            MegalodonEntities::notification::NotificationType::EmojiReaction => {
+                ["reaction"].to_vec()
+            }
+            MegalodonEntities::notification::NotificationType::PollVote => ["pollVote"].to_vec(),
+            MegalodonEntities::notification::NotificationType::PollExpired => {
+                ["pollEnded"].to_vec()
+            }
+            MegalodonEntities::notification::NotificationType::Favourite
+            | MegalodonEntities::notification::NotificationType::Status => [].to_vec(),
+        }
+    }
+
+    fn into_megalodon(self) -> Option<MegalodonEntities::notification::NotificationType> {
+        match self {
+            NotificationType::Follow | NotificationType::FollowRequestAccepted => {
+                Some(MegalodonEntities::notification::NotificationType::Follow)
+            }
+            NotificationType::Mention | NotificationType::Reply | NotificationType::Quote => {
+                Some(MegalodonEntities::notification::NotificationType::Mention)
+            }
+            NotificationType::Renote => {
+                Some(MegalodonEntities::notification::NotificationType::Reblog)
+            }
+            NotificationType::Reaction => {
+                Some(MegalodonEntities::notification::NotificationType::EmojiReaction)
+            }
+            NotificationType::PollVote => {
+                Some(MegalodonEntities::notification::NotificationType::PollVote)
+            }
+            NotificationType::PollEnded => {
+                Some(MegalodonEntities::notification::NotificationType::PollExpired)
+            }
+            NotificationType::ReceiveFollowRequest => {
+                Some(MegalodonEntities::notification::NotificationType::FollowRequest)
+            }
+            NotificationType::Unknown => None,
+        }
+    }
+}
+
+impl Notification {
+    /// Convert to a notification. Returns `None` for notifications which megalodon does not support,
+    /// such as notifications from applications and achievements.
+    pub fn into_notification(self, base_url: &str) -> Option<MegalodonEntities::Notification> {
+        let renote = self.r#type == NotificationType::Renote;
+        let r#type = self.r#type.into_megalodon()?;
+        let account = self.user?.into_account(base_url);
+        // A renote notification has the renote itself, but megalodon expects the renoted status.
+        let status = self.note.map(|note| match (renote, note.renote()) {
+            (true, Some(renoted)) => renoted.clone().into_status(base_url),
+            _ => note.into_status(base_url),
+        });
+        Some(MegalodonEntities::Notification {
+            account,
+            created_at: self.created_at,
+            id: self.id,
+            status,
+            emoji: self.reaction,
+            r#type,
+        })
+    }
+}
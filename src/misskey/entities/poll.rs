@@ -0,0 +1,50 @@
+use crate::entities as MegalodonEntities;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Poll {
+    multiple: bool,
+    expires_at: Option<DateTime<Utc>>,
+    choices: Vec<Choice>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct Choice {
+    text: String,
+    votes: u32,
+    is_voted: Option<bool>,
+}
+
+impl Poll {
+    /// Convert to a poll. Misskey polls do not have ID, so the note ID is used instead.
+    pub fn into_poll(self, note_id: String) -> MegalodonEntities::Poll {
+        let voted = self
+            .choices
+            .iter()
+            .any(|choice| choice.is_voted.unwrap_or(false));
+        MegalodonEntities::Poll {
+            id: note_id,
+            expired: self
+                .expires_at
+                .map(|expires_at| expires_at < Utc::now())
+                .unwrap_or(false),
+            expires_at: self.expires_at,
+            multiple: self.multiple,
+            votes_count: self.choices.iter().map(|choice| choice.votes).sum(),
+            voters_count: None,
+            options: self
+                .choices
+                .into_iter()
+                .map(|choice| MegalodonEntities::PollOption {
+                    title: choice.text,
+                    votes_count: Some(choice.votes),
+                })
+                .collect(),
+            voted: Some(voted),
+            emojis: [].to_vec(),
+        }
+    }
+}
@@ -0,0 +1,9 @@
+use super::User;
+use serde::Deserialize;
+
+/// Response of `notes/reactions` endpoint.
+#[derive(Debug, Deserialize, Clone)]
+pub struct NoteReaction {
+    pub user: User,
+    pub r#type: String,
+}
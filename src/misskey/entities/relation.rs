@@ -0,0 +1,35 @@
+use crate::entities as MegalodonEntities;
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Relation {
+    id: String,
+    is_following: bool,
+    has_pending_follow_request_from_you: bool,
+    is_followed: bool,
+    is_blocking: bool,
+    is_blocked: bool,
+    is_muted: bool,
+    is_renote_muted: Option<bool>,
+}
+
+impl From<Relation> for MegalodonEntities::Relationship {
+    fn from(item: Relation) -> Self {
+        MegalodonEntities::Relationship {
+            id: item.id,
+            following: item.is_following,
+            followed_by: item.is_followed,
+            delivery_following: None,
+            blocking: item.is_blocking,
+            blocked_by: item.is_blocked,
+            muting: item.is_muted,
+            muting_notifications: item.is_muted,
+            requested: item.has_pending_follow_request_from_you,
+            domain_blocking: false,
+            showing_reblogs: !item.is_renote_muted.unwrap_or(false),
+            endorsed: false,
+            notifying: false,
+        }
+    }
+}
@@ -0,0 +1,74 @@
+use super::{Emojis, Field, Note};
+use crate::entities as MegalodonEntities;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct User {
+    pub id: String,
+    name: Option<String>,
+    pub username: String,
+    pub host: Option<String>,
+    avatar_url: Option<String>,
+    is_bot: Option<bool>,
+    #[serde(default)]
+    emojis: Emojis,
+    url: Option<String>,
+    uri: Option<String>,
+    created_at: Option<DateTime<Utc>>,
+    banner_url: Option<String>,
+    is_locked: Option<bool>,
+    description: Option<String>,
+    fields: Option<Vec<Field>>,
+    followers_count: Option<i32>,
+    following_count: Option<i32>,
+    notes_count: Option<i32>,
+    pub pinned_notes: Option<Vec<Note>>,
+    pub muted_instances: Option<Vec<String>>,
+}
+
+impl User {
+    /// Get the account name, which contains the host for remote users.
+    pub fn acct(&self) -> String {
+        match &self.host {
+            Some(host) => format!("{}@{}", self.username, host),
+            None => self.username.clone(),
+        }
+    }
+
+    /// Convert to an account. Local users do not have URL, so it is generated from `base_url`.
+    pub fn into_account(self, base_url: &str) -> MegalodonEntities::Account {
+        let acct = self.acct();
+        let url = self
+            .url
+            .or(self.uri)
+            .unwrap_or_else(|| format!("{}/@{}", base_url, acct));
+        let avatar = self.avatar_url.unwrap_or_default();
+        let header = self.banner_url.unwrap_or_default();
+        MegalodonEntities::Account {
+            id: self.id,
+            display_name: self.name.unwrap_or_else(|| self.username.clone()),
+            username: self.username,
+            acct,
+            locked: self.is_locked.unwrap_or(false),
+            created_at: self.created_at.unwrap_or_else(Utc::now),
+            followers_count: self.followers_count.unwrap_or(0),
+            following_count: self.following_count.unwrap_or(0),
+            statuses_count: self.notes_count.unwrap_or(0),
+            note: self.description.unwrap_or_default(),
+            url,
+            avatar_static: avatar.clone(),
+            avatar,
+            header_static: header.clone(),
+            header,
+            emojis: self.emojis.into(),
+            moved: None,
+            fields: self
+                .fields
+                .map(|i| i.into_iter().map(|j| j.into()).collect()),
+            bot: self.is_bot,
+            source: None,
+        }
+    }
+}
@@ -0,0 +1,20 @@
+use crate::entities as MegalodonEntities;
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct UserList {
+    id: String,
+    name: String,
+    #[serde(default)]
+    pub user_ids: Vec<String>,
+}
+
+impl From<UserList> for MegalodonEntities::List {
+    fn from(item: UserList) -> Self {
+        MegalodonEntities::List {
+            id: item.id,
+            title: item.name,
+        }
+    }
+}
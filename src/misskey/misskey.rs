@@ -0,0 +1,2641 @@
+use super::api_client::APIClient;
+use super::entities;
+use super::oauth;
+use super::web_socket::WebSocket;
+use crate::capabilities::Capabilities;
+use crate::error::Kind;
+use crate::megalodon::Megalodon;
+use crate::rate_limit::RetryPolicy;
+use crate::streaming::{MultiplexedStreaming, StreamType, StreamingOptions};
+use crate::{
+    default, entities as MegalodonEntities, error::Error, megalodon, oauth as MegalodonOAuth,
+    response::Response,
+};
+use crate::{error, Streaming, SNS};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use rand::Rng;
+use serde_json::Value;
+use sha1::{Digest, Sha1};
+use std::collections::HashMap;
+use std::time::SystemTime;
+use tokio::fs::File;
+use tokio_util::codec::{BytesCodec, FramedRead};
+
+/// Permissions which are requested when the application does not specify scopes.
+const DEFAULT_PERMISSIONS: [&str; 20] = [
+    "read:account",
+    "write:account",
+    "read:blocks",
+    "write:blocks",
+    "read:drive",
+    "write:drive",
+    "read:favorites",
+    "write:favorites",
+    "read:following",
+    "write:following",
+    "read:mutes",
+    "write:mutes",
+    "write:notes",
+    "read:notifications",
+    "write:notifications",
+    "read:reactions",
+    "write:reactions",
+    "write:votes",
+    "read:channels",
+    "write:channels",
+];
+
+/// Misskey API Client which satisfies megalodon trait.
+#[derive(Debug, Clone)]
+pub struct Misskey {
+    client: APIClient,
+    base_url: String,
+    access_token: Option<String>,
+    user_agent: Option<String>,
+    streaming_options: StreamingOptions,
+}
+
+impl Misskey {
+    /// Create a new [`Misskey`].
+    pub fn new(
+        base_url: String,
+        access_token: Option<String>,
+        user_agent: Option<String>,
+    ) -> Misskey {
+        let client = APIClient::new(base_url.clone(), access_token.clone(), user_agent.clone());
+        Misskey {
+            client,
+            base_url,
+            access_token,
+            user_agent,
+            streaming_options: StreamingOptions::default(),
+        }
+    }
+
+    /// Create a new [`Misskey`] which sends all requests with the given HTTP client.
+    /// The user agent is used for streaming connections, because the HTTP client has its own one.
+    pub fn with_client(
+        base_url: String,
+        access_token: Option<String>,
+        user_agent: Option<String>,
+        http_client: reqwest::Client,
+    ) -> Misskey {
+        let client = APIClient::with_client(base_url.clone(), access_token.clone(), http_client);
+        Misskey {
+            client,
+            base_url,
+            access_token,
+            user_agent,
+            streaming_options: StreamingOptions::default(),
+        }
+    }
+
+    /// Set a policy to retry failed requests. Requests are not retried when it is `None`.
+    pub fn set_retry_policy(&mut self, retry_policy: Option<RetryPolicy>) {
+        self.client.set_retry_policy(retry_policy);
+    }
+
+    /// Set options of streaming connections, which are used for streaming objects created after this call.
+    pub fn set_streaming_options(&mut self, streaming_options: StreamingOptions) {
+        self.streaming_options = streaming_options;
+    }
+
+    fn streaming(&self, streaming_url: String, streams: Vec<StreamType>) -> WebSocket {
+        WebSocket::new(
+            streaming_url + "/streaming",
+            self.base_url.clone(),
+            streams,
+            self.access_token.clone(),
+            self.user_agent.clone(),
+            self.streaming_options.clone(),
+        )
+    }
+
+    fn not_supported(&self) -> Error {
+        Error::new_own(
+            "Misskey does not support".to_string(),
+            error::Kind::NoImplementedError,
+            None,
+            None,
+        )
+    }
+
+    async fn get_relationship(
+        &self,
+        id: String,
+    ) -> Result<Response<MegalodonEntities::Relationship>, Error> {
+        let res = self.get_relationships(Vec::<String>::from([id])).await?;
+        let Some(relationship) = res.json.into_iter().next() else {
+            return Err(Error::new_own(
+                "Relationship is not found".to_string(),
+                Kind::NotFoundError,
+                None,
+                Some(res.status),
+            ));
+        };
+        Ok(Response::<MegalodonEntities::Relationship>::new(
+            relationship,
+            res.status,
+            res.status_text,
+            res.header,
+        ))
+    }
+
+    async fn get_users(
+        &self,
+        ids: Vec<String>,
+    ) -> Result<Response<Vec<MegalodonEntities::Account>>, Error> {
+        let mut params = HashMap::<&str, Value>::new();
+        params.insert("userIds", Value::from(ids));
+        let res = self
+            .client
+            .post::<Vec<entities::User>>("/api/users/show", &params, None)
+            .await?;
+        Ok(self.accounts(res))
+    }
+
+    async fn get_me(&self) -> Result<Response<entities::User>, Error> {
+        let params = HashMap::<&str, Value>::new();
+        self.client
+            .post::<entities::User>("/api/i", &params, None)
+            .await
+    }
+
+    async fn upload_file(
+        &self,
+        file_path: String,
+        comment: Option<String>,
+    ) -> Result<Response<entities::DriveFile>, Error> {
+        let file = File::open(file_path.clone()).await?;
+
+        let file_name = hex::encode(Sha1::digest(file_path.as_bytes()));
+
+        let stream = FramedRead::new(file, BytesCodec::new());
+        let file_body = reqwest::Body::wrap_stream(stream);
+        let part = reqwest::multipart::Part::stream(file_body).file_name(file_name);
+
+        let mut form = reqwest::multipart::Form::new().part("file", part);
+        if let Some(comment) = comment {
+            form = form.text("comment", comment);
+        }
+
+        self.client
+            .post_multipart::<entities::DriveFile>("/api/drive/files/create", form, None)
+            .await
+    }
+
+    async fn update_muted_instances(
+        &self,
+        update: impl FnOnce(&mut Vec<String>),
+    ) -> Result<Response<()>, Error> {
+        let me = self.get_me().await?;
+        let mut muted_instances = me.json.muted_instances.unwrap_or_default();
+        update(&mut muted_instances);
+
+        let mut params = HashMap::<&str, Value>::new();
+        params.insert("mutedInstances", Value::from(muted_instances));
+        let res = self
+            .client
+            .post::<entities::User>("/api/i/update", &params, None)
+            .await?;
+        Ok(Response::<()>::new(
+            (),
+            res.status,
+            res.status_text,
+            res.header,
+        ))
+    }
+
+    fn accounts(
+        &self,
+        res: Response<Vec<entities::User>>,
+    ) -> Response<Vec<MegalodonEntities::Account>> {
+        Response::<Vec<MegalodonEntities::Account>>::new(
+            res.json
+                .into_iter()
+                .map(|user| user.into_account(&self.base_url))
+                .collect(),
+            res.status,
+            res.status_text,
+            res.header,
+        )
+    }
+
+    fn statuses(
+        &self,
+        res: Response<Vec<entities::Note>>,
+    ) -> Response<Vec<MegalodonEntities::Status>> {
+        Response::<Vec<MegalodonEntities::Status>>::new(
+            res.json
+                .into_iter()
+                .map(|note| note.into_status(&self.base_url))
+                .collect(),
+            res.status,
+            res.status_text,
+            res.header,
+        )
+    }
+
+    async fn note_action(
+        &self,
+        path: &str,
+        id: String,
+    ) -> Result<Response<MegalodonEntities::Status>, Error> {
+        let mut params = HashMap::<&str, Value>::new();
+        params.insert("noteId", Value::String(id.clone()));
+        self.client.post::<()>(path, &params, None).await?;
+        self.get_status(id).await
+    }
+}
+
+/// Build parameters for pagination. Misskey has `untilId` and `sinceId` instead of `max_id` and `since_id`.
+fn pagination_params(
+    limit: Option<u32>,
+    max_id: Option<&String>,
+    since_id: Option<&String>,
+) -> HashMap<&'static str, Value> {
+    let mut params = HashMap::<&str, Value>::new();
+    if let Some(limit) = limit {
+        params.insert("limit", Value::from(limit));
+    }
+    if let Some(max_id) = max_id {
+        params.insert("untilId", Value::String(max_id.clone()));
+    }
+    if let Some(since_id) = since_id {
+        params.insert("sinceId", Value::String(since_id.clone()));
+    }
+    params
+}
+
+/// Convert OAuth scopes to MiAuth permissions. Misskey permissions can also be passed as is.
+fn permissions(scopes: &Option<Vec<String>>) -> Vec<String> {
+    let Some(scopes) = scopes else {
+        return DEFAULT_PERMISSIONS.iter().map(|p| p.to_string()).collect();
+    };
+    let mut permissions = Vec::<String>::new();
+    for scope in scopes {
+        let expanded: Vec<String> = match scope.as_str() {
+            "read" | "write" => DEFAULT_PERMISSIONS
+                .iter()
+                .filter(|p| p.starts_with(&format!("{}:", scope)))
+                .map(|p| p.to_string())
+                .collect(),
+            "follow" => [
+                "read:following",
+                "write:following",
+                "read:blocks",
+                "write:blocks",
+                "read:mutes",
+                "write:mutes",
+            ]
+            .iter()
+            .map(|p| p.to_string())
+            .collect(),
+            "push" => Vec::new(),
+            permission => Vec::from([permission.to_string()]),
+        };
+        for permission in expanded {
+            if !permissions.contains(&permission) {
+                permissions.push(permission);
+            }
+        }
+    }
+    permissions
+}
+
+/// Generate a random UUID v4, which is used for MiAuth session.
+fn generate_session() -> String {
+    let mut bytes: [u8; 16] = rand::thread_rng().gen();
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    let hex = hex::encode(bytes);
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[0..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..32]
+    )
+}
+
+#[async_trait]
+impl megalodon::Megalodon for Misskey {
+    async fn register_app(
+        &self,
+        client_name: String,
+        options: &megalodon::AppInputOptions,
+    ) -> Result<MegalodonOAuth::AppData, Error> {
+        let mut app = self.create_app(client_name.clone(), options).await?;
+
+        let mut params = Vec::<String>::from([
+            format!("name={}", urlencoding::encode(&client_name)),
+            format!("permission={}", permissions(&options.scopes).join(",")),
+        ]);
+        if app.redirect_uri != default::NO_REDIRECT {
+            params.push(format!(
+                "callback={}",
+                urlencoding::encode(&app.redirect_uri)
+            ));
+        }
+        app.url = Some(format!(
+            "{}/miauth/{}?{}",
+            self.base_url,
+            app.client_id,
+            params.join("&")
+        ));
+        Ok(app)
+    }
+
+    /// Misskey does not register applications with MiAuth, so this method only generates a new session.
+    /// The session is used as `client_id`, and `client_secret` is empty.
+    async fn create_app(
+        &self,
+        client_name: String,
+        options: &megalodon::AppInputOptions,
+    ) -> Result<MegalodonOAuth::AppData, Error> {
+        let mut redirect_uris = default::NO_REDIRECT;
+        if let Some(uris) = &options.redirect_uris {
+            redirect_uris = uris.as_ref();
+        }
+        let session = generate_session();
+
+        Ok(MegalodonOAuth::AppData::new(
+            session.clone(),
+            client_name,
+            options.website.clone(),
+            redirect_uris.to_string(),
+            session,
+            String::new(),
+        ))
+    }
+
+    /// Check the MiAuth session which is given as `client_id`. Other arguments are ignored.
+    async fn fetch_access_token(
+        &self,
+        client_id: String,
+        _client_secret: String,
+        _code: String,
+        _redirect_uri: String,
+        _code_verifier: Option<String>,
+    ) -> Result<MegalodonOAuth::TokenData, Error> {
+        let params = HashMap::<&str, Value>::new();
+        let path = format!("/api/miauth/{}/check", client_id);
+        let res = self
+            .client
+            .post::<oauth::MiAuthCheck>(path.as_str(), &params, None)
+            .await?;
+        let (true, Some(token)) = (res.json.ok, res.json.token) else {
+            return Err(Error::new_own(
+                "The session is not authorized".to_string(),
+                Kind::UnauthorizedError,
+                Some(format!("{}{}", self.base_url, path)),
+                Some(res.status),
+            ));
+        };
+        let created_at = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        Ok(MegalodonOAuth::TokenData::new(
+            token,
+            "Bearer".to_string(),
+            String::new(),
+            created_at,
+            None,
+            None,
+        ))
+    }
+
+    async fn refresh_access_token(
+        &self,
+        _client_id: String,
+        _client_secret: String,
+        _refresh_token: String,
+    ) -> Result<MegalodonOAuth::TokenData, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn fetch_app_token(
+        &self,
+        _client_id: String,
+        _client_secret: String,
+        _scopes: Vec<String>,
+    ) -> Result<MegalodonOAuth::TokenData, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn revoke_access_token(
+        &self,
+        _client_id: String,
+        _client_secret: String,
+        _access_token: String,
+    ) -> Result<Response<()>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn verify_app_credentials(
+        &self,
+    ) -> Result<Response<MegalodonEntities::Application>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn register_account(
+        &self,
+        _username: String,
+        _email: String,
+        _password: String,
+        _agreement: String,
+        _locale: String,
+        _reason: Option<String>,
+    ) -> Result<Response<MegalodonEntities::Token>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn verify_account_credentials(
+        &self,
+    ) -> Result<Response<MegalodonEntities::Account>, Error> {
+        let res = self.get_me().await?;
+
+        Ok(Response::<MegalodonEntities::Account>::new(
+            res.json.into_account(&self.base_url),
+            res.status,
+            res.status_text,
+            res.header,
+        ))
+    }
+
+    async fn update_credentials(
+        &self,
+        options: Option<&megalodon::UpdateCredentialsInputOptions>,
+    ) -> Result<Response<MegalodonEntities::Account>, Error> {
+        let mut params = HashMap::<&str, Value>::new();
+        if let Some(options) = options {
+            if let Some(discoverable) = options.discoverable {
+                params.insert("isExplorable", Value::Bool(discoverable));
+            }
+            if let Some(bot) = options.bot {
+                params.insert("isBot", Value::Bool(bot));
+            }
+            if let Some(display_name) = &options.display_name {
+                params.insert("name", Value::String(display_name.clone()));
+            }
+            if let Some(note) = &options.note {
+                params.insert("description", Value::String(note.clone()));
+            }
+            if let Some(avatar) = &options.avatar {
+                let file = self.upload_file(avatar.clone(), None).await?;
+                params.insert("avatarId", Value::String(file.json.id));
+            }
+            if let Some(header) = &options.header {
+                let file = self.upload_file(header.clone(), None).await?;
+                params.insert("bannerId", Value::String(file.json.id));
+            }
+            if let Some(locked) = options.locked {
+                params.insert("isLocked", Value::Bool(locked));
+            }
+            if let Some(fields_attributes) = &options.fields_attributes {
+                params.insert("fields", serde_json::to_value(fields_attributes)?);
+            }
+        }
+
+        let res = self
+            .client
+            .post::<entities::User>("/api/i/update", &params, None)
+            .await?;
+
+        Ok(Response::<MegalodonEntities::Account>::new(
+            res.json.into_account(&self.base_url),
+            res.status,
+            res.status_text,
+            res.header,
+        ))
+    }
+
+    async fn get_account(&self, id: String) -> Result<Response<MegalodonEntities::Account>, Error> {
+        let mut params = HashMap::<&str, Value>::new();
+        params.insert("userId", Value::String(id));
+        let res = self
+            .client
+            .post::<entities::User>("/api/users/show", &params, None)
+            .await?;
+
+        Ok(Response::<MegalodonEntities::Account>::new(
+            res.json.into_account(&self.base_url),
+            res.status,
+            res.status_text,
+            res.header,
+        ))
+    }
+
+    async fn get_account_statuses(
+        &self,
+        id: String,
+        options: Option<&megalodon::GetAccountStatusesInputOptions>,
+    ) -> Result<Response<Vec<MegalodonEntities::Status>>, Error> {
+        if let Some(true) = options.and_then(|o| o.pinned) {
+            let mut params = HashMap::<&str, Value>::new();
+            params.insert("userId", Value::String(id));
+            let res = self
+                .client
+                .post::<entities::User>("/api/users/show", &params, None)
+                .await?;
+            return Ok(self.statuses(Response::<Vec<entities::Note>>::new(
+                res.json.pinned_notes.unwrap_or_default(),
+                res.status,
+                res.status_text,
+                res.header,
+            )));
+        }
+
+        let mut params = HashMap::<&str, Value>::new();
+        if let Some(options) = options {
+            params = pagination_params(
+                options.limit,
+                options.max_id.as_ref(),
+                options.since_id.as_ref(),
+            );
+            if let Some(exclude_replies) = options.exclude_replies {
+                params.insert("includeReplies", Value::Bool(!exclude_replies));
+            }
+            if let Some(exclude_reblogs) = options.exclude_reblogs {
+                params.insert("withRenotes", Value::Bool(!exclude_reblogs));
+            }
+            if let Some(only_media) = options.only_media {
+                params.insert("withFiles", Value::Bool(only_media));
+            }
+        }
+        params.insert("userId", Value::String(id));
+        let res = self
+            .client
+            .post::<Vec<entities::Note>>("/api/users/notes", &params, None)
+            .await?;
+
+        Ok(self.statuses(res))
+    }
+
+    async fn subscribe_account(
+        &self,
+        _id: String,
+    ) -> Result<Response<MegalodonEntities::Relationship>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn unsubscribe_account(
+        &self,
+        _id: String,
+    ) -> Result<Response<MegalodonEntities::Relationship>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn get_account_followers(
+        &self,
+        id: String,
+        options: Option<&megalodon::AccountFollowersInputOptions>,
+    ) -> Result<Response<Vec<MegalodonEntities::Account>>, Error> {
+        let mut params = HashMap::<&str, Value>::new();
+        if let Some(options) = options {
+            params = pagination_params(
+                options.limit,
+                options.max_id.as_ref(),
+                options.since_id.as_ref(),
+            );
+        }
+        params.insert("userId", Value::String(id));
+        let res = self
+            .client
+            .post::<Vec<entities::Following>>("/api/users/followers", &params, None)
+            .await?;
+
+        Ok(self.accounts(Response::<Vec<entities::User>>::new(
+            res.json.into_iter().filter_map(|f| f.follower).collect(),
+            res.status,
+            res.status_text,
+            res.header,
+        )))
+    }
+
+    async fn get_account_following(
+        &self,
+        id: String,
+        options: Option<&megalodon::AccountFollowersInputOptions>,
+    ) -> Result<Response<Vec<MegalodonEntities::Account>>, Error> {
+        let mut params = HashMap::<&str, Value>::new();
+        if let Some(options) = options {
+            params = pagination_params(
+                options.limit,
+                options.max_id.as_ref(),
+                options.since_id.as_ref(),
+            );
+        }
+        params.insert("userId", Value::String(id));
+        let res = self
+            .client
+            .post::<Vec<entities::Following>>("/api/users/following", &params, None)
+            .await?;
+
+        Ok(self.accounts(Response::<Vec<entities::User>>::new(
+            res.json.into_iter().filter_map(|f| f.followee).collect(),
+            res.status,
+            res.status_text,
+            res.header,
+        )))
+    }
+
+    async fn get_account_lists(
+        &self,
+        id: String,
+    ) -> Result<Response<Vec<MegalodonEntities::List>>, Error> {
+        let params = HashMap::<&str, Value>::new();
+        let res = self
+            .client
+            .post::<Vec<entities::UserList>>("/api/users/lists/list", &params, None)
+            .await?;
+
+        Ok(Response::<Vec<MegalodonEntities::List>>::new(
+            res.json
+                .into_iter()
+                .filter(|list| list.user_ids.contains(&id))
+                .map(|list| list.into())
+                .collect(),
+            res.status,
+            res.status_text,
+            res.header,
+        ))
+    }
+
+    async fn get_identity_proofs(
+        &self,
+        _id: String,
+    ) -> Result<Response<Vec<MegalodonEntities::IdentityProof>>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn follow_account(
+        &self,
+        id: String,
+        _options: Option<&megalodon::FollowAccountInputOptions>,
+    ) -> Result<Response<MegalodonEntities::Relationship>, Error> {
+        let mut params = HashMap::<&str, Value>::new();
+        params.insert("userId", Value::String(id.clone()));
+        self.client
+            .post::<entities::User>("/api/following/create", &params, None)
+            .await?;
+        self.get_relationship(id).await
+    }
+
+    async fn unfollow_account(
+        &self,
+        id: String,
+    ) -> Result<Response<MegalodonEntities::Relationship>, Error> {
+        let mut params = HashMap::<&str, Value>::new();
+        params.insert("userId", Value::String(id.clone()));
+        self.client
+            .post::<entities::User>("/api/following/delete", &params, None)
+            .await?;
+        self.get_relationship(id).await
+    }
+
+    async fn block_account(
+        &self,
+        id: String,
+    ) -> Result<Response<MegalodonEntities::Relationship>, Error> {
+        let mut params = HashMap::<&str, Value>::new();
+        params.insert("userId", Value::String(id.clone()));
+        self.client
+            .post::<entities::User>("/api/blocking/create", &params, None)
+            .await?;
+        self.get_relationship(id).await
+    }
+
+    async fn unblock_account(
+        &self,
+        id: String,
+    ) -> Result<Response<MegalodonEntities::Relationship>, Error> {
+        let mut params = HashMap::<&str, Value>::new();
+        params.insert("userId", Value::String(id.clone()));
+        self.client
+            .post::<entities::User>("/api/blocking/delete", &params, None)
+            .await?;
+        self.get_relationship(id).await
+    }
+
+    async fn mute_account(
+        &self,
+        id: String,
+        _notifications: bool,
+    ) -> Result<Response<MegalodonEntities::Relationship>, Error> {
+        let mut params = HashMap::<&str, Value>::new();
+        params.insert("userId", Value::String(id.clone()));
+        self.client
+            .post::<()>("/api/mute/create", &params, None)
+            .await?;
+        self.get_relationship(id).await
+    }
+
+    async fn unmute_account(
+        &self,
+        id: String,
+    ) -> Result<Response<MegalodonEntities::Relationship>, Error> {
+        let mut params = HashMap::<&str, Value>::new();
+        params.insert("userId", Value::String(id.clone()));
+        self.client
+            .post::<()>("/api/mute/delete", &params, None)
+            .await?;
+        self.get_relationship(id).await
+    }
+
+    async fn pin_account(
+        &self,
+        _id: String,
+    ) -> Result<Response<MegalodonEntities::Relationship>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn unpin_account(
+        &self,
+        _id: String,
+    ) -> Result<Response<MegalodonEntities::Relationship>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn get_relationships(
+        &self,
+        ids: Vec<String>,
+    ) -> Result<Response<Vec<MegalodonEntities::Relationship>>, Error> {
+        let mut params = HashMap::<&str, Value>::new();
+        params.insert("userId", Value::from(ids));
+        let res = self
+            .client
+            .post::<Vec<entities::Relation>>("/api/users/relation", &params, None)
+            .await?;
+
+        Ok(Response::<Vec<MegalodonEntities::Relationship>>::new(
+            res.json.into_iter().map(|i| i.into()).collect(),
+            res.status,
+            res.status_text,
+            res.header,
+        ))
+    }
+
+    async fn search_account(
+        &self,
+        q: String,
+        options: Option<&megalodon::SearchAccountInputOptions>,
+    ) -> Result<Response<Vec<MegalodonEntities::Account>>, Error> {
+        let mut params = HashMap::<&str, Value>::new();
+        params.insert("query", Value::String(q));
+        if let Some(limit) = options.and_then(|o| o.limit) {
+            params.insert("limit", Value::from(limit));
+        }
+        let res = self
+            .client
+            .post::<Vec<entities::User>>("/api/users/search", &params, None)
+            .await?;
+
+        Ok(self.accounts(res))
+    }
+
+    async fn get_bookmarks(
+        &self,
+        _options: Option<&megalodon::GetBookmarksInputOptions>,
+    ) -> Result<Response<Vec<MegalodonEntities::Status>>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn get_favourites(
+        &self,
+        options: Option<&megalodon::GetFavouritesInputOptions>,
+    ) -> Result<Response<Vec<MegalodonEntities::Status>>, Error> {
+        let mut params = HashMap::<&str, Value>::new();
+        if let Some(options) = options {
+            params = pagination_params(
+                options.limit,
+                options.max_id.as_ref(),
+                options.min_id.as_ref(),
+            );
+        }
+        let res = self
+            .client
+            .post::<Vec<entities::Favorite>>("/api/i/favorites", &params, None)
+            .await?;
+
+        Ok(self.statuses(Response::<Vec<entities::Note>>::new(
+            res.json.into_iter().map(|f| f.note).collect(),
+            res.status,
+            res.status_text,
+            res.header,
+        )))
+    }
+
+    async fn get_mutes(
+        &self,
+        options: Option<&megalodon::GetMutesInputOptions>,
+    ) -> Result<Response<Vec<MegalodonEntities::Account>>, Error> {
+        let mut params = HashMap::<&str, Value>::new();
+        if let Some(options) = options {
+            params = pagination_params(
+                options.limit,
+                options.max_id.as_ref(),
+                options.min_id.as_ref(),
+            );
+        }
+        let res = self
+            .client
+            .post::<Vec<entities::Muting>>("/api/mute/list", &params, None)
+            .await?;
+
+        Ok(self.accounts(Response::<Vec<entities::User>>::new(
+            res.json.into_iter().map(|m| m.mutee).collect(),
+            res.status,
+            res.status_text,
+            res.header,
+        )))
+    }
+
+    async fn get_blocks(
+        &self,
+        options: Option<&megalodon::GetBlocksInputOptions>,
+    ) -> Result<Response<Vec<MegalodonEntities::Account>>, Error> {
+        let mut params = HashMap::<&str, Value>::new();
+        if let Some(options) = options {
+            params = pagination_params(
+                options.limit,
+                options.max_id.as_ref(),
+                options.min_id.as_ref(),
+            );
+        }
+        let res = self
+            .client
+            .post::<Vec<entities::Blocking>>("/api/blocking/list", &params, None)
+            .await?;
+
+        Ok(self.accounts(Response::<Vec<entities::User>>::new(
+            res.json.into_iter().map(|b| b.blockee).collect(),
+            res.status,
+            res.status_text,
+            res.header,
+        )))
+    }
+
+    /// Get instances which are muted by the user, because Misskey mutes domains instead of blocking them.
+    async fn get_domain_blocks(
+        &self,
+        _options: Option<&megalodon::GetDomainBlocksInputOptions>,
+    ) -> Result<Response<Vec<String>>, Error> {
+        let res = self.get_me().await?;
+
+        Ok(Response::<Vec<String>>::new(
+            res.json.muted_instances.unwrap_or_default(),
+            res.status,
+            res.status_text,
+            res.header,
+        ))
+    }
+
+    async fn block_domain(&self, domain: String) -> Result<Response<()>, Error> {
+        self.update_muted_instances(|muted_instances| {
+            if !muted_instances.contains(&domain) {
+                muted_instances.push(domain);
+            }
+        })
+        .await
+    }
+
+    async fn unblock_domain(&self, domain: String) -> Result<Response<()>, Error> {
+        self.update_muted_instances(|muted_instances| {
+            muted_instances.retain(|d| d != &domain);
+        })
+        .await
+    }
+
+    async fn get_filters(&self) -> Result<Response<Vec<MegalodonEntities::Filter>>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn get_filter(&self, _id: String) -> Result<Response<MegalodonEntities::Filter>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn create_filter(
+        &self,
+        _phrase: String,
+        _context: Vec<MegalodonEntities::filter::FilterContext>,
+        _options: Option<&megalodon::FilterInputOptions>,
+    ) -> Result<Response<MegalodonEntities::Filter>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn update_filter(
+        &self,
+        _id: String,
+        _phrase: String,
+        _context: Vec<MegalodonEntities::filter::FilterContext>,
+        _options: Option<&megalodon::FilterInputOptions>,
+    ) -> Result<Response<MegalodonEntities::Filter>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn delete_filter(&self, _id: String) -> Result<Response<()>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn get_filters_v2(&self) -> Result<Response<Vec<MegalodonEntities::FilterV2>>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn get_filter_v2(
+        &self,
+        _id: String,
+    ) -> Result<Response<MegalodonEntities::FilterV2>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn create_filter_v2(
+        &self,
+        _title: String,
+        _context: Vec<MegalodonEntities::filter::FilterContext>,
+        _options: Option<&megalodon::FilterV2InputOptions>,
+    ) -> Result<Response<MegalodonEntities::FilterV2>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn update_filter_v2(
+        &self,
+        _id: String,
+        _options: Option<&megalodon::UpdateFilterV2InputOptions>,
+    ) -> Result<Response<MegalodonEntities::FilterV2>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn delete_filter_v2(&self, _id: String) -> Result<Response<()>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn get_filter_keywords(
+        &self,
+        _filter_id: String,
+    ) -> Result<Response<Vec<MegalodonEntities::FilterKeyword>>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn add_filter_keyword(
+        &self,
+        _filter_id: String,
+        _keyword: String,
+        _whole_word: Option<bool>,
+    ) -> Result<Response<MegalodonEntities::FilterKeyword>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn get_filter_keyword(
+        &self,
+        _id: String,
+    ) -> Result<Response<MegalodonEntities::FilterKeyword>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn update_filter_keyword(
+        &self,
+        _id: String,
+        _keyword: String,
+        _whole_word: Option<bool>,
+    ) -> Result<Response<MegalodonEntities::FilterKeyword>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn remove_filter_keyword(&self, _id: String) -> Result<Response<()>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn get_filter_statuses(
+        &self,
+        _filter_id: String,
+    ) -> Result<Response<Vec<MegalodonEntities::FilterStatus>>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn add_filter_status(
+        &self,
+        _filter_id: String,
+        _status_id: String,
+    ) -> Result<Response<MegalodonEntities::FilterStatus>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn get_filter_status(
+        &self,
+        _id: String,
+    ) -> Result<Response<MegalodonEntities::FilterStatus>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn remove_filter_status(&self, _id: String) -> Result<Response<()>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn report(
+        &self,
+        account_id: String,
+        options: Option<&megalodon::ReportInputOptions>,
+    ) -> Result<Response<MegalodonEntities::Report>, Error> {
+        let comment = options.and_then(|o| o.comment.clone()).unwrap_or_default();
+        let mut params = HashMap::<&str, Value>::new();
+        params.insert("userId", Value::String(account_id.clone()));
+        params.insert("comment", Value::String(comment.clone()));
+        self.client
+            .post::<()>("/api/users/report-abuse", &params, None)
+            .await?;
+
+        // Misskey does not return the report, so it is built from the request.
+        let account = self.get_account(account_id).await?;
+        Ok(Response::<MegalodonEntities::Report>::new(
+            MegalodonEntities::Report {
+                id: String::new(),
+                action_taken: false,
+                category: options
+                    .and_then(|o| o.category.clone())
+                    .unwrap_or(MegalodonEntities::report::Category::Other),
+                comment,
+                forwarded: false,
+                status_ids: options.and_then(|o| o.status_ids.clone()),
+                rule_ids: None,
+                target_account: account.json,
+            },
+            account.status,
+            account.status_text,
+            account.header,
+        ))
+    }
+
+    async fn get_follow_requests(
+        &self,
+        limit: Option<u32>,
+    ) -> Result<Response<Vec<MegalodonEntities::Account>>, Error> {
+        let params = pagination_params(limit, None, None);
+        let res = self
+            .client
+            .post::<Vec<entities::FollowRequest>>("/api/following/requests/list", &params, None)
+            .await?;
+
+        Ok(self.accounts(Response::<Vec<entities::User>>::new(
+            res.json.into_iter().map(|r| r.follower).collect(),
+            res.status,
+            res.status_text,
+            res.header,
+        )))
+    }
+
+    async fn accept_follow_request(
+        &self,
+        id: String,
+    ) -> Result<Response<MegalodonEntities::Relationship>, Error> {
+        let mut params = HashMap::<&str, Value>::new();
+        params.insert("userId", Value::String(id.clone()));
+        self.client
+            .post::<()>("/api/following/requests/accept", &params, None)
+            .await?;
+        self.get_relationship(id).await
+    }
+
+    async fn reject_follow_request(
+        &self,
+        id: String,
+    ) -> Result<Response<MegalodonEntities::Relationship>, Error> {
+        let mut params = HashMap::<&str, Value>::new();
+        params.insert("userId", Value::String(id.clone()));
+        self.client
+            .post::<()>("/api/following/requests/reject", &params, None)
+            .await?;
+        self.get_relationship(id).await
+    }
+
+    async fn get_endorsements(
+        &self,
+        _options: Option<&megalodon::GetEndorsementsInputOptions>,
+    ) -> Result<Response<Vec<MegalodonEntities::Account>>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn get_featured_tags(
+        &self,
+    ) -> Result<Response<Vec<MegalodonEntities::FeaturedTag>>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn create_featured_tag(
+        &self,
+        _name: String,
+    ) -> Result<Response<MegalodonEntities::FeaturedTag>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn delete_featured_tag(&self, _id: String) -> Result<Response<()>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn get_suggested_tags(&self) -> Result<Response<Vec<MegalodonEntities::Tag>>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn get_preferences(&self) -> Result<Response<MegalodonEntities::Preferences>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn get_suggestions(
+        &self,
+        limit: Option<u32>,
+    ) -> Result<Response<Vec<MegalodonEntities::Account>>, Error> {
+        let params = pagination_params(limit, None, None);
+        let res = self
+            .client
+            .post::<Vec<entities::User>>("/api/users/recommendation", &params, None)
+            .await?;
+
+        Ok(self.accounts(res))
+    }
+
+    async fn get_tag(&self, id: String) -> Result<Response<MegalodonEntities::Tag>, Error> {
+        let mut params = HashMap::<&str, Value>::new();
+        params.insert("tag", Value::String(id));
+        let res = self
+            .client
+            .post::<entities::Hashtag>("/api/hashtags/show", &params, None)
+            .await?;
+
+        Ok(Response::<MegalodonEntities::Tag>::new(
+            MegalodonEntities::Tag {
+                url: format!("{}/tags/{}", self.base_url, res.json.tag),
+                name: res.json.tag,
+                history: None,
+                following: None,
+            },
+            res.status,
+            res.status_text,
+            res.header,
+        ))
+    }
+
+    async fn follow_tag(&self, _id: String) -> Result<Response<MegalodonEntities::Tag>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn unfollow_tag(&self, _id: String) -> Result<Response<MegalodonEntities::Tag>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn post_status(
+        &self,
+        status: String,
+        options: Option<&megalodon::PostStatusInputOptions>,
+    ) -> Result<Response<MegalodonEntities::Status>, Error> {
+        let mut params = HashMap::<&str, Value>::new();
+        params.insert("text", Value::String(status));
+        if let Some(options) = options {
+            if options.scheduled_at.is_some() {
+                return Err(self.not_supported());
+            }
+            if let Some(media_ids) = &options.media_ids {
+                params.insert("fileIds", Value::from(media_ids.clone()));
+            }
+            if let Some(poll) = &options.poll {
+                let mut poll_params = serde_json::Map::<String, Value>::new();
+                poll_params.insert("choices".to_string(), Value::from(poll.options.clone()));
+                if let Some(expires_in) = poll.expires_in {
+                    poll_params.insert("expiredAfter".to_string(), Value::from(expires_in * 1000));
+                }
+                if let Some(multiple) = poll.multiple {
+                    poll_params.insert("multiple".to_string(), Value::Bool(multiple));
+                }
+                params.insert("poll", Value::Object(poll_params));
+            }
+            if let Some(in_reply_to_id) = &options.in_reply_to_id {
+                params.insert("replyId", Value::String(in_reply_to_id.clone()));
+            }
+            if let Some(spoiler_text) = &options.spoiler_text {
+                params.insert("cw", Value::String(spoiler_text.clone()));
+            }
+            if let Some(visibility) = &options.visibility {
+                let visibility: entities::Visibility = visibility.clone().into();
+                params.insert("visibility", serde_json::to_value(visibility)?);
+            }
+            if let Some(quote_id) = &options.quote_id {
+                params.insert("renoteId", Value::String(quote_id.clone()));
+            }
+        }
+
+        let res = self
+            .client
+            .post::<entities::CreatedNote>("/api/notes/create", &params, None)
+            .await?;
+
+        Ok(Response::<MegalodonEntities::Status>::new(
+            res.json.created_note.into_status(&self.base_url),
+            res.status,
+            res.status_text,
+            res.header,
+        ))
+    }
+
+    async fn get_status(&self, id: String) -> Result<Response<MegalodonEntities::Status>, Error> {
+        let mut params = HashMap::<&str, Value>::new();
+        params.insert("noteId", Value::String(id));
+        let res = self
+            .client
+            .post::<entities::Note>("/api/notes/show", &params, None)
+            .await?;
+
+        Ok(Response::<MegalodonEntities::Status>::new(
+            res.json.into_status(&self.base_url),
+            res.status,
+            res.status_text,
+            res.header,
+        ))
+    }
+
+    async fn edit_status(
+        &self,
+        _id: String,
+        _options: &megalodon::EditStatusInputOptions,
+    ) -> Result<Response<MegalodonEntities::Status>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn delete_status(&self, id: String) -> Result<Response<()>, Error> {
+        let mut params = HashMap::<&str, Value>::new();
+        params.insert("noteId", Value::String(id));
+        self.client
+            .post::<()>("/api/notes/delete", &params, None)
+            .await
+    }
+
+    async fn get_status_context(
+        &self,
+        id: String,
+        options: Option<&megalodon::GetStatusContextInputOptions>,
+    ) -> Result<Response<MegalodonEntities::Context>, Error> {
+        let mut params = HashMap::<&str, Value>::new();
+        if let Some(options) = options {
+            params = pagination_params(
+                options.limit,
+                options.max_id.as_ref(),
+                options.since_id.as_ref(),
+            );
+        }
+        params.insert("noteId", Value::String(id.clone()));
+        let children = self
+            .client
+            .post::<Vec<entities::Note>>("/api/notes/children", &params, None)
+            .await?;
+
+        let mut params = HashMap::<&str, Value>::new();
+        params.insert("noteId", Value::String(id));
+        let conversation = self
+            .client
+            .post::<Vec<entities::Note>>("/api/notes/conversation", &params, None)
+            .await?;
+
+        // The conversation is sorted from the parent, but ancestors are sorted from the root.
+        let mut ancestors = self.statuses(conversation).json;
+        ancestors.reverse();
+        let descendants = self.statuses(children);
+        Ok(Response::<MegalodonEntities::Context>::new(
+            MegalodonEntities::Context {
+                ancestors,
+                descendants: descendants.json,
+            },
+            descendants.status,
+            descendants.status_text,
+            descendants.header,
+        ))
+    }
+
+    async fn get_status_reblogged_by(
+        &self,
+        id: String,
+    ) -> Result<Response<Vec<MegalodonEntities::Account>>, Error> {
+        let mut params = HashMap::<&str, Value>::new();
+        params.insert("noteId", Value::String(id));
+        let res = self
+            .client
+            .post::<Vec<entities::Note>>("/api/notes/renotes", &params, None)
+            .await?;
+
+        Ok(self.accounts(Response::<Vec<entities::User>>::new(
+            res.json.into_iter().map(|note| note.user).collect(),
+            res.status,
+            res.status_text,
+            res.header,
+        )))
+    }
+
+    async fn get_status_favourited_by(
+        &self,
+        _id: String,
+    ) -> Result<Response<Vec<MegalodonEntities::Account>>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn favourite_status(
+        &self,
+        id: String,
+    ) -> Result<Response<MegalodonEntities::Status>, Error> {
+        let mut res = self.note_action("/api/notes/favorites/create", id).await?;
+        res.json.favourited = Some(true);
+        Ok(res)
+    }
+
+    async fn unfavourite_status(
+        &self,
+        id: String,
+    ) -> Result<Response<MegalodonEntities::Status>, Error> {
+        let mut res = self.note_action("/api/notes/favorites/delete", id).await?;
+        res.json.favourited = Some(false);
+        Ok(res)
+    }
+
+    async fn reblog_status(
+        &self,
+        id: String,
+    ) -> Result<Response<MegalodonEntities::Status>, Error> {
+        let mut params = HashMap::<&str, Value>::new();
+        params.insert("renoteId", Value::String(id));
+        let res = self
+            .client
+            .post::<entities::CreatedNote>("/api/notes/create", &params, None)
+            .await?;
+
+        Ok(Response::<MegalodonEntities::Status>::new(
+            res.json.created_note.into_status(&self.base_url),
+            res.status,
+            res.status_text,
+            res.header,
+        ))
+    }
+
+    async fn unreblog_status(
+        &self,
+        id: String,
+    ) -> Result<Response<MegalodonEntities::Status>, Error> {
+        self.note_action("/api/notes/unrenote", id).await
+    }
+
+    async fn bookmark_status(
+        &self,
+        _id: String,
+    ) -> Result<Response<MegalodonEntities::Status>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn unbookmark_status(
+        &self,
+        _id: String,
+    ) -> Result<Response<MegalodonEntities::Status>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn mute_status(&self, id: String) -> Result<Response<MegalodonEntities::Status>, Error> {
+        let mut res = self
+            .note_action("/api/notes/thread-muting/create", id)
+            .await?;
+        res.json.muted = Some(true);
+        Ok(res)
+    }
+
+    async fn unmute_status(
+        &self,
+        id: String,
+    ) -> Result<Response<MegalodonEntities::Status>, Error> {
+        let mut res = self
+            .note_action("/api/notes/thread-muting/delete", id)
+            .await?;
+        res.json.muted = Some(false);
+        Ok(res)
+    }
+
+    async fn pin_status(&self, id: String) -> Result<Response<MegalodonEntities::Status>, Error> {
+        let mut res = self.note_action("/api/i/pin", id).await?;
+        res.json.pinned = Some(true);
+        Ok(res)
+    }
+
+    async fn unpin_status(&self, id: String) -> Result<Response<MegalodonEntities::Status>, Error> {
+        let mut res = self.note_action("/api/i/unpin", id).await?;
+        res.json.pinned = Some(false);
+        Ok(res)
+    }
+
+    async fn upload_media(
+        &self,
+        file_path: String,
+        options: Option<&megalodon::UploadMediaInputOptions>,
+    ) -> Result<Response<MegalodonEntities::UploadMedia>, Error> {
+        let res = self
+            .upload_file(file_path, options.and_then(|o| o.description.clone()))
+            .await?;
+
+        Ok(Response::<MegalodonEntities::UploadMedia>::new(
+            MegalodonEntities::UploadMedia::Attachment(res.json.into()),
+            res.status,
+            res.status_text,
+            res.header,
+        ))
+    }
+
+    async fn get_media(
+        &self,
+        id: String,
+    ) -> Result<Response<MegalodonEntities::Attachment>, Error> {
+        let mut params = HashMap::<&str, Value>::new();
+        params.insert("fileId", Value::String(id));
+        let res = self
+            .client
+            .post::<entities::DriveFile>("/api/drive/files/show", &params, None)
+            .await?;
+
+        Ok(Response::<MegalodonEntities::Attachment>::new(
+            res.json.into(),
+            res.status,
+            res.status_text,
+            res.header,
+        ))
+    }
+
+    async fn update_media(
+        &self,
+        id: String,
+        options: Option<&megalodon::UpdateMediaInputOptions>,
+    ) -> Result<Response<MegalodonEntities::Attachment>, Error> {
+        let mut params = HashMap::<&str, Value>::new();
+        params.insert("fileId", Value::String(id));
+        if let Some(options) = options {
+            // Files in the drive can not be replaced.
+            if options.file_path.is_some() {
+                return Err(self.not_supported());
+            }
+            if let Some(description) = &options.description {
+                params.insert("comment", Value::String(description.clone()));
+            }
+        }
+        let res = self
+            .client
+            .post::<entities::DriveFile>("/api/drive/files/update", &params, None)
+            .await?;
+
+        Ok(Response::<MegalodonEntities::Attachment>::new(
+            res.json.into(),
+            res.status,
+            res.status_text,
+            res.header,
+        ))
+    }
+
+    /// Get a poll of the note. Misskey polls do not have ID, so specify ID of the note.
+    async fn get_poll(&self, id: String) -> Result<Response<MegalodonEntities::Poll>, Error> {
+        let res = self.get_status(id).await?;
+        let Some(poll) = res.json.poll else {
+            return Err(Error::new_own(
+                "The status does not have a poll".to_string(),
+                Kind::NotFoundError,
+                None,
+                Some(res.status),
+            ));
+        };
+
+        Ok(Response::<MegalodonEntities::Poll>::new(
+            poll,
+            res.status,
+            res.status_text,
+            res.header,
+        ))
+    }
+
+    /// Vote a poll of the note. Misskey polls do not have ID, so specify ID of the note.
+    async fn vote_poll(
+        &self,
+        id: String,
+        choices: Vec<u32>,
+    ) -> Result<Response<MegalodonEntities::Poll>, Error> {
+        for choice in choices {
+            let mut params = HashMap::<&str, Value>::new();
+            params.insert("noteId", Value::String(id.clone()));
+            params.insert("choice", Value::from(choice));
+            self.client
+                .post::<()>("/api/notes/polls/vote", &params, None)
+                .await?;
+        }
+        self.get_poll(id).await
+    }
+
+    async fn get_scheduled_statuses(
+        &self,
+        _options: Option<&megalodon::GetScheduledStatusesInputOptions>,
+    ) -> Result<Response<Vec<MegalodonEntities::ScheduledStatus>>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn get_scheduled_status(
+        &self,
+        _id: String,
+    ) -> Result<Response<MegalodonEntities::ScheduledStatus>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn schedule_status(
+        &self,
+        _id: String,
+        _scheduled_at: Option<DateTime<Utc>>,
+    ) -> Result<Response<MegalodonEntities::ScheduledStatus>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn cancel_scheduled_status(&self, _id: String) -> Result<Response<()>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn get_public_timeline(
+        &self,
+        options: Option<&megalodon::GetPublicTimelineInputOptions>,
+    ) -> Result<Response<Vec<MegalodonEntities::Status>>, Error> {
+        let mut params = HashMap::<&str, Value>::new();
+        if let Some(options) = options {
+            params = pagination_params(
+                options.limit,
+                options.max_id.as_ref(),
+                options.since_id.as_ref().or(options.min_id.as_ref()),
+            );
+            if let Some(only_media) = options.only_media {
+                params.insert("withFiles", Value::Bool(only_media));
+            }
+        }
+        let res = self
+            .client
+            .post::<Vec<entities::Note>>("/api/notes/global-timeline", &params, None)
+            .await?;
+
+        Ok(self.statuses(res))
+    }
+
+    async fn get_local_timeline(
+        &self,
+        options: Option<&megalodon::GetLocalTimelineInputOptions>,
+    ) -> Result<Response<Vec<MegalodonEntities::Status>>, Error> {
+        let mut params = HashMap::<&str, Value>::new();
+        if let Some(options) = options {
+            params = pagination_params(
+                options.limit,
+                options.max_id.as_ref(),
+                options.since_id.as_ref().or(options.min_id.as_ref()),
+            );
+            if let Some(only_media) = options.only_media {
+                params.insert("withFiles", Value::Bool(only_media));
+            }
+        }
+        let res = self
+            .client
+            .post::<Vec<entities::Note>>("/api/notes/local-timeline", &params, None)
+            .await?;
+
+        Ok(self.statuses(res))
+    }
+
+    async fn get_bubble_timeline(
+        &self,
+        _options: Option<&megalodon::GetLocalTimelineInputOptions>,
+    ) -> Result<Response<Vec<MegalodonEntities::Status>>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn get_tag_timeline(
+        &self,
+        hashtag: String,
+        options: Option<&megalodon::GetTagTimelineInputOptions>,
+    ) -> Result<Response<Vec<MegalodonEntities::Status>>, Error> {
+        let mut params = HashMap::<&str, Value>::new();
+        if let Some(options) = options {
+            params = pagination_params(
+                options.limit,
+                options.max_id.as_ref(),
+                options.since_id.as_ref().or(options.min_id.as_ref()),
+            );
+            if let Some(only_media) = options.only_media {
+                params.insert("withFiles", Value::Bool(only_media));
+            }
+        }
+        params.insert("tag", Value::String(hashtag));
+        let res = self
+            .client
+            .post::<Vec<entities::Note>>("/api/notes/search-by-tag", &params, None)
+            .await?;
+
+        Ok(self.statuses(res))
+    }
+
+    async fn get_home_timeline(
+        &self,
+        options: Option<&megalodon::GetHomeTimelineInputOptions>,
+    ) -> Result<Response<Vec<MegalodonEntities::Status>>, Error> {
+        let mut params = HashMap::<&str, Value>::new();
+        if let Some(options) = options {
+            params = pagination_params(
+                options.limit,
+                options.max_id.as_ref(),
+                options.since_id.as_ref().or(options.min_id.as_ref()),
+            );
+            if let Some(only_media) = options.only_media {
+                params.insert("withFiles", Value::Bool(only_media));
+            }
+        }
+        let res = self
+            .client
+            .post::<Vec<entities::Note>>("/api/notes/timeline", &params, None)
+            .await?;
+
+        Ok(self.statuses(res))
+    }
+
+    async fn get_list_timeline(
+        &self,
+        list_id: String,
+        options: Option<&megalodon::GetListTimelineInputOptions>,
+    ) -> Result<Response<Vec<MegalodonEntities::Status>>, Error> {
+        let mut params = HashMap::<&str, Value>::new();
+        if let Some(options) = options {
+            params = pagination_params(
+                options.limit,
+                options.max_id.as_ref(),
+                options.since_id.as_ref().or(options.min_id.as_ref()),
+            );
+        }
+        params.insert("listId", Value::String(list_id));
+        let res = self
+            .client
+            .post::<Vec<entities::Note>>("/api/notes/user-list-timeline", &params, None)
+            .await?;
+
+        Ok(self.statuses(res))
+    }
+
+    /// Get direct notes which mention the user, because Misskey does not have conversations.
+    async fn get_conversation_timeline(
+        &self,
+        options: Option<&megalodon::GetConversationTimelineInputOptions>,
+    ) -> Result<Response<Vec<MegalodonEntities::Status>>, Error> {
+        let mut params = HashMap::<&str, Value>::new();
+        if let Some(options) = options {
+            params = pagination_params(
+                options.limit,
+                options.max_id.as_ref(),
+                options.since_id.as_ref().or(options.min_id.as_ref()),
+            );
+        }
+        params.insert("visibility", Value::String("specified".to_string()));
+        let res = self
+            .client
+            .post::<Vec<entities::Note>>("/api/notes/mentions", &params, None)
+            .await?;
+
+        Ok(self.statuses(res))
+    }
+
+    async fn delete_conversation(&self, _id: String) -> Result<Response<()>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn read_conversation(
+        &self,
+        _id: String,
+    ) -> Result<Response<MegalodonEntities::Conversation>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn get_lists(&self) -> Result<Response<Vec<MegalodonEntities::List>>, Error> {
+        let params = HashMap::<&str, Value>::new();
+        let res = self
+            .client
+            .post::<Vec<entities::UserList>>("/api/users/lists/list", &params, None)
+            .await?;
+
+        Ok(Response::<Vec<MegalodonEntities::List>>::new(
+            res.json.into_iter().map(|i| i.into()).collect(),
+            res.status,
+            res.status_text,
+            res.header,
+        ))
+    }
+
+    async fn get_list(&self, id: String) -> Result<Response<MegalodonEntities::List>, Error> {
+        let mut params = HashMap::<&str, Value>::new();
+        params.insert("listId", Value::String(id));
+        let res = self
+            .client
+            .post::<entities::UserList>("/api/users/lists/show", &params, None)
+            .await?;
+
+        Ok(Response::<MegalodonEntities::List>::new(
+            res.json.into(),
+            res.status,
+            res.status_text,
+            res.header,
+        ))
+    }
+
+    async fn create_list(&self, title: String) -> Result<Response<MegalodonEntities::List>, Error> {
+        let mut params = HashMap::<&str, Value>::new();
+        params.insert("name", Value::String(title));
+        let res = self
+            .client
+            .post::<entities::UserList>("/api/users/lists/create", &params, None)
+            .await?;
+
+        Ok(Response::<MegalodonEntities::List>::new(
+            res.json.into(),
+            res.status,
+            res.status_text,
+            res.header,
+        ))
+    }
+
+    async fn update_list(
+        &self,
+        id: String,
+        title: String,
+    ) -> Result<Response<MegalodonEntities::List>, Error> {
+        let mut params = HashMap::<&str, Value>::new();
+        params.insert("listId", Value::String(id));
+        params.insert("name", Value::String(title));
+        let res = self
+            .client
+            .post::<entities::UserList>("/api/users/lists/update", &params, None)
+            .await?;
+
+        Ok(Response::<MegalodonEntities::List>::new(
+            res.json.into(),
+            res.status,
+            res.status_text,
+            res.header,
+        ))
+    }
+
+    async fn delete_list(&self, id: String) -> Result<Response<()>, Error> {
+        let mut params = HashMap::<&str, Value>::new();
+        params.insert("listId", Value::String(id));
+        self.client
+            .post::<()>("/api/users/lists/delete", &params, None)
+            .await
+    }
+
+    async fn get_accounts_in_list(
+        &self,
+        id: String,
+        options: Option<&megalodon::GetAccountsInListInputOptions>,
+    ) -> Result<Response<Vec<MegalodonEntities::Account>>, Error> {
+        let mut params = HashMap::<&str, Value>::new();
+        params.insert("listId", Value::String(id));
+        let res = self
+            .client
+            .post::<entities::UserList>("/api/users/lists/show", &params, None)
+            .await?;
+
+        let mut user_ids = res.json.user_ids;
+        if let Some(limit) = options.and_then(|o| o.limit) {
+            user_ids.truncate(limit as usize);
+        }
+        if user_ids.is_empty() {
+            return Ok(Response::<Vec<MegalodonEntities::Account>>::new(
+                [].to_vec(),
+                res.status,
+                res.status_text,
+                res.header,
+            ));
+        }
+        self.get_users(user_ids).await
+    }
+
+    async fn add_accounts_to_list(
+        &self,
+        id: String,
+        account_ids: Vec<String>,
+    ) -> Result<Response<MegalodonEntities::List>, Error> {
+        for account_id in account_ids {
+            let mut params = HashMap::<&str, Value>::new();
+            params.insert("listId", Value::String(id.clone()));
+            params.insert("userId", Value::String(account_id));
+            self.client
+                .post::<()>("/api/users/lists/push", &params, None)
+                .await?;
+        }
+        self.get_list(id).await
+    }
+
+    async fn delete_accounts_from_list(
+        &self,
+        id: String,
+        account_ids: Vec<String>,
+    ) -> Result<Response<()>, Error> {
+        let mut res = Response::<()>::new((), 204, String::new(), Default::default());
+        for account_id in account_ids {
+            let mut params = HashMap::<&str, Value>::new();
+            params.insert("listId", Value::String(id.clone()));
+            params.insert("userId", Value::String(account_id));
+            res = self
+                .client
+                .post::<()>("/api/users/lists/pull", &params, None)
+                .await?;
+        }
+        Ok(res)
+    }
+
+    async fn get_markers(
+        &self,
+        _timeline: Vec<String>,
+    ) -> Result<Response<MegalodonEntities::Marker>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn save_markers(
+        &self,
+        _options: Option<&megalodon::SaveMarkersInputOptions>,
+    ) -> Result<Response<MegalodonEntities::Marker>, Error> {
+        Err(self.not_supported())
+    }
+
+    /// Get notifications. Notifications which megalodon does not support, such as achievements, are skipped.
+    async fn get_notifications(
+        &self,
+        options: Option<&megalodon::GetNotificationsInputOptions>,
+    ) -> Result<Response<Vec<MegalodonEntities::Notification>>, Error> {
+        let mut params = HashMap::<&str, Value>::new();
+        let mut account_id = None;
+        if let Some(options) = options {
+            params = pagination_params(
+                options.limit,
+                options.max_id.as_ref(),
+                options.since_id.as_ref().or(options.min_id.as_ref()),
+            );
+            if let Some(exclude_types) = &options.exclude_types {
+                let types: Vec<&str> = exclude_types
+                    .iter()
+                    .flat_map(entities::NotificationType::from_megalodon)
+                    .collect();
+                params.insert("excludeTypes", Value::from(types));
+            }
+            account_id = options.account_id.clone();
+        }
+        let res = self
+            .client
+            .post::<Vec<entities::Notification>>("/api/i/notifications", &params, None)
+            .await?;
+
+        Ok(Response::<Vec<MegalodonEntities::Notification>>::new(
+            res.json
+                .into_iter()
+                .filter_map(|n| n.into_notification(&self.base_url))
+                .filter(|n| match &account_id {
+                    Some(id) => &n.account.id == id,
+                    None => true,
+                })
+                .collect(),
+            res.status,
+            res.status_text,
+            res.header,
+        ))
+    }
+
+    async fn get_notification(
+        &self,
+        _id: String,
+    ) -> Result<Response<MegalodonEntities::Notification>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn dismiss_notifications(&self) -> Result<Response<()>, Error> {
+        let params = HashMap::<&str, Value>::new();
+        self.client
+            .post::<()>("/api/notifications/mark-all-as-read", &params, None)
+            .await
+    }
+
+    async fn dismiss_notification(&self, _id: String) -> Result<Response<()>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn subscribe_push_notification(
+        &self,
+        _subscription: &megalodon::SubscribePushNotificationInputSubscription,
+        _data: Option<&megalodon::SubscribePushNotificationInputData>,
+    ) -> Result<Response<MegalodonEntities::PushSubscription>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn get_push_subscription(
+        &self,
+    ) -> Result<Response<MegalodonEntities::PushSubscription>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn update_push_subscription(
+        &self,
+        _data: Option<&megalodon::SubscribePushNotificationInputData>,
+    ) -> Result<Response<MegalodonEntities::PushSubscription>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn delete_push_subscription(&self) -> Result<Response<()>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn search(
+        &self,
+        q: String,
+        r#type: &megalodon::SearchType,
+        options: Option<&megalodon::SearchInputOptions>,
+    ) -> Result<Response<MegalodonEntities::Results>, Error> {
+        let mut params = HashMap::<&str, Value>::new();
+        if let Some(options) = options {
+            params = pagination_params(
+                options.limit,
+                options.max_id.as_ref(),
+                options.min_id.as_ref(),
+            );
+            if let Some(offset) = options.offset {
+                params.insert("offset", Value::from(offset));
+            }
+        }
+        params.insert("query", Value::String(q));
+        let mut results = MegalodonEntities::Results {
+            accounts: [].to_vec(),
+            statuses: [].to_vec(),
+            hashtags: [].to_vec(),
+        };
+
+        match r#type {
+            megalodon::SearchType::Accounts => {
+                params.remove("untilId");
+                params.remove("sinceId");
+                let res = self
+                    .client
+                    .post::<Vec<entities::User>>("/api/users/search", &params, None)
+                    .await?;
+                let res = self.accounts(res);
+                results.accounts = res.json;
+                Ok(Response::<MegalodonEntities::Results>::new(
+                    results,
+                    res.status,
+                    res.status_text,
+                    res.header,
+                ))
+            }
+            megalodon::SearchType::Statuses => {
+                if let Some(account_id) = options.and_then(|o| o.account_id.clone()) {
+                    params.insert("userId", Value::String(account_id));
+                }
+                let res = self
+                    .client
+                    .post::<Vec<entities::Note>>("/api/notes/search", &params, None)
+                    .await?;
+                let res = self.statuses(res);
+                results.statuses = res.json;
+                Ok(Response::<MegalodonEntities::Results>::new(
+                    results,
+                    res.status,
+                    res.status_text,
+                    res.header,
+                ))
+            }
+            megalodon::SearchType::Hashtags => {
+                params.remove("untilId");
+                params.remove("sinceId");
+                let res = self
+                    .client
+                    .post::<Vec<String>>("/api/hashtags/search", &params, None)
+                    .await?;
+                results.hashtags = res
+                    .json
+                    .into_iter()
+                    .map(|tag| MegalodonEntities::Tag {
+                        url: format!("{}/tags/{}", self.base_url, tag),
+                        name: tag,
+                        history: None,
+                        following: None,
+                    })
+                    .collect();
+                Ok(Response::<MegalodonEntities::Results>::new(
+                    results,
+                    res.status,
+                    res.status_text,
+                    res.header,
+                ))
+            }
+        }
+    }
+
+    async fn get_instance(&self) -> Result<Response<MegalodonEntities::Instance>, Error> {
+        let mut params = HashMap::<&str, Value>::new();
+        params.insert("detail", Value::Bool(true));
+        let meta = self
+            .client
+            .post::<entities::Meta>("/api/meta", &params, None)
+            .await?;
+        let params = HashMap::<&str, Value>::new();
+        let stats = self
+            .client
+            .post::<entities::Stats>("/api/stats", &params, None)
+            .await?;
+
+        let meta = meta.json;
+        let uri = meta
+            .uri
+            .trim_start_matches("https://")
+            .trim_start_matches("http://")
+            .to_string();
+        Ok(Response::<MegalodonEntities::Instance>::new(
+            MegalodonEntities::Instance {
+                uri,
+                title: meta.name.unwrap_or_default(),
+                description: meta.description.unwrap_or_default(),
+                email: meta.maintainer_email.unwrap_or_default(),
+                version: meta.version,
+                thumbnail: meta.banner_url,
+                urls: MegalodonEntities::URLs {
+                    streaming_api: meta.uri.replacen("http", "ws", 1),
+                },
+                stats: MegalodonEntities::Stats {
+                    user_count: stats.json.original_users_count,
+                    status_count: stats.json.original_notes_count,
+                    domain_count: stats.json.instances,
+                },
+                languages: meta.langs,
+                registrations: !meta.disable_registration,
+                approval_required: false,
+                invites_enabled: None,
+                configuration: MegalodonEntities::instance::InstanceConfig {
+                    statuses: MegalodonEntities::instance::Statuses {
+                        max_characters: meta.max_note_text_length,
+                        max_media_attachments: None,
+                    },
+                    polls: MegalodonEntities::instance::Polls {
+                        max_options: 10,
+                        max_characters_per_option: 50,
+                        min_expiration: 50,
+                        max_expiration: 2629746,
+                    },
+                },
+                contact_account: None,
+                rules: None,
+            },
+            stats.status,
+            stats.status_text,
+            stats.header,
+        ))
+    }
+
+    async fn get_instance_v2(&self) -> Result<Response<MegalodonEntities::InstanceV2>, Error> {
+        let res = self.get_instance().await?;
+
+        Ok(Response::<MegalodonEntities::InstanceV2>::new(
+            res.json.into(),
+            res.status,
+            res.status_text,
+            res.header,
+        ))
+    }
+
+    async fn capabilities(&self) -> Result<Capabilities, Error> {
+        Ok(Capabilities::new(&SNS::Misskey, "", &[]))
+    }
+
+    async fn get_instance_peers(&self) -> Result<Response<Vec<String>>, Error> {
+        let mut params = HashMap::<&str, Value>::new();
+        params.insert("limit", Value::from(100));
+        let res = self
+            .client
+            .post::<Vec<entities::FederationInstance>>("/api/federation/instances", &params, None)
+            .await?;
+
+        Ok(Response::<Vec<String>>::new(
+            res.json.into_iter().map(|i| i.host).collect(),
+            res.status,
+            res.status_text,
+            res.header,
+        ))
+    }
+
+    async fn get_instance_activity(
+        &self,
+    ) -> Result<Response<Vec<MegalodonEntities::Activity>>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn get_instance_trends(
+        &self,
+        limit: Option<u32>,
+    ) -> Result<Response<Vec<MegalodonEntities::Tag>>, Error> {
+        let params = HashMap::<&str, Value>::new();
+        let res = self
+            .client
+            .post::<Vec<entities::HashtagTrend>>("/api/hashtags/trend", &params, None)
+            .await?;
+
+        let mut trends = res.json;
+        if let Some(limit) = limit {
+            trends.truncate(limit as usize);
+        }
+        Ok(Response::<Vec<MegalodonEntities::Tag>>::new(
+            trends
+                .into_iter()
+                .map(|trend| MegalodonEntities::Tag {
+                    url: format!("{}/tags/{}", self.base_url, trend.tag),
+                    name: trend.tag,
+                    history: None,
+                    following: None,
+                })
+                .collect(),
+            res.status,
+            res.status_text,
+            res.header,
+        ))
+    }
+
+    async fn get_trend_tags(
+        &self,
+        options: Option<&megalodon::GetTrendsInputOptions>,
+    ) -> Result<Response<Vec<MegalodonEntities::Tag>>, Error> {
+        let params = HashMap::<&str, Value>::new();
+        let res = self
+            .client
+            .post::<Vec<entities::HashtagTrend>>("/api/hashtags/trend", &params, None)
+            .await?;
+
+        let mut trends = res.json;
+        if let Some(options) = options {
+            if let Some(offset) = options.offset {
+                trends.drain(..trends.len().min(offset as usize));
+            }
+            if let Some(limit) = options.limit {
+                trends.truncate(limit as usize);
+            }
+        }
+        Ok(Response::<Vec<MegalodonEntities::Tag>>::new(
+            trends
+                .into_iter()
+                .map(|trend| MegalodonEntities::Tag {
+                    url: format!("{}/tags/{}", self.base_url, trend.tag),
+                    name: trend.tag,
+                    history: None,
+                    following: None,
+                })
+                .collect(),
+            res.status,
+            res.status_text,
+            res.header,
+        ))
+    }
+
+    async fn get_trend_statuses(
+        &self,
+        options: Option<&megalodon::GetTrendsInputOptions>,
+    ) -> Result<Response<Vec<MegalodonEntities::Status>>, Error> {
+        let mut params = HashMap::<&str, Value>::new();
+        if let Some(options) = options {
+            if let Some(limit) = options.limit {
+                params.insert("limit", Value::from(limit));
+            }
+            if let Some(offset) = options.offset {
+                params.insert("offset", Value::from(offset));
+            }
+        }
+        let res = self
+            .client
+            .post::<Vec<entities::Note>>("/api/notes/featured", &params, None)
+            .await?;
+
+        Ok(self.statuses(res))
+    }
+
+    async fn get_trend_links(
+        &self,
+        _options: Option<&megalodon::GetTrendsInputOptions>,
+    ) -> Result<Response<Vec<MegalodonEntities::TrendLink>>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn get_instance_directory(
+        &self,
+        options: Option<&megalodon::GetInstanceDirectoryInputOptions>,
+    ) -> Result<Response<Vec<MegalodonEntities::Account>>, Error> {
+        let mut params = HashMap::<&str, Value>::new();
+        if let Some(options) = options {
+            if let Some(limit) = options.limit {
+                params.insert("limit", Value::from(limit));
+            }
+            if let Some(offset) = options.offset {
+                params.insert("offset", Value::from(offset));
+            }
+            if let Some(order) = &options.order {
+                let sort = match order {
+                    megalodon::Order::Active => "+updatedAt",
+                    megalodon::Order::New => "+createdAt",
+                };
+                params.insert("sort", Value::String(sort.to_string()));
+            }
+            if let Some(local) = options.local {
+                let origin = if local { "local" } else { "combined" };
+                params.insert("origin", Value::String(origin.to_string()));
+            }
+        }
+        let res = self
+            .client
+            .post::<Vec<entities::User>>("/api/users", &params, None)
+            .await?;
+
+        Ok(self.accounts(res))
+    }
+
+    async fn get_instance_custom_emojis(
+        &self,
+    ) -> Result<Response<Vec<MegalodonEntities::Emoji>>, Error> {
+        let params = HashMap::<&str, Value>::new();
+        let res = self
+            .client
+            .post::<entities::EmojisResponse>("/api/emojis", &params, None)
+            .await?;
+
+        Ok(Response::<Vec<MegalodonEntities::Emoji>>::new(
+            res.json.emojis.into_iter().map(|i| i.into()).collect(),
+            res.status,
+            res.status_text,
+            res.header,
+        ))
+    }
+
+    async fn get_announcements(
+        &self,
+    ) -> Result<Response<Vec<MegalodonEntities::Announcement>>, Error> {
+        let mut params = HashMap::<&str, Value>::new();
+        params.insert("limit", Value::from(100));
+        let res = self
+            .client
+            .post::<Vec<entities::Announcement>>("/api/announcements", &params, None)
+            .await?;
+
+        Ok(Response::<Vec<MegalodonEntities::Announcement>>::new(
+            res.json.into_iter().map(|i| i.into()).collect(),
+            res.status,
+            res.status_text,
+            res.header,
+        ))
+    }
+
+    async fn dismiss_announcement(&self, id: String) -> Result<Response<()>, Error> {
+        let mut params = HashMap::<&str, Value>::new();
+        params.insert("announcementId", Value::String(id));
+        self.client
+            .post::<()>("/api/i/read-announcement", &params, None)
+            .await
+    }
+
+    async fn add_announcement_reaction(
+        &self,
+        _id: String,
+        _name: String,
+    ) -> Result<Response<()>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn remove_announcement_reaction(
+        &self,
+        _id: String,
+        _name: String,
+    ) -> Result<Response<()>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn create_emoji_reaction(
+        &self,
+        id: String,
+        emoji: String,
+    ) -> Result<Response<MegalodonEntities::Status>, Error> {
+        let mut params = HashMap::<&str, Value>::new();
+        params.insert("noteId", Value::String(id.clone()));
+        params.insert("reaction", Value::String(emoji));
+        self.client
+            .post::<()>("/api/notes/reactions/create", &params, None)
+            .await?;
+        self.get_status(id).await
+    }
+
+    /// Remove the reaction of the user. Misskey allows only one reaction for each note, so `emoji` is ignored.
+    async fn delete_emoji_reaction(
+        &self,
+        id: String,
+        _emoji: String,
+    ) -> Result<Response<MegalodonEntities::Status>, Error> {
+        self.note_action("/api/notes/reactions/delete", id).await
+    }
+
+    async fn get_emoji_reactions(
+        &self,
+        id: String,
+    ) -> Result<Response<Vec<MegalodonEntities::Reaction>>, Error> {
+        let res = self.get_status(id).await?;
+
+        Ok(Response::<Vec<MegalodonEntities::Reaction>>::new(
+            res.json.emoji_reactions.unwrap_or_default(),
+            res.status,
+            res.status_text,
+            res.header,
+        ))
+    }
+
+    async fn get_emoji_reaction(
+        &self,
+        id: String,
+        emoji: String,
+    ) -> Result<Response<MegalodonEntities::Reaction>, Error> {
+        let status = self.get_status(id.clone()).await?;
+        let me = status
+            .json
+            .emoji_reactions
+            .unwrap_or_default()
+            .into_iter()
+            .any(|reaction| reaction.name == emoji && reaction.me);
+
+        let mut params = HashMap::<&str, Value>::new();
+        params.insert("noteId", Value::String(id));
+        params.insert("type", Value::String(emoji.clone()));
+        let res = self
+            .client
+            .post::<Vec<entities::NoteReaction>>("/api/notes/reactions", &params, None)
+            .await?;
+
+        let accounts: Vec<MegalodonEntities::Account> = res
+            .json
+            .into_iter()
+            .map(|reaction| reaction.user.into_account(&self.base_url))
+            .collect();
+        Ok(Response::<MegalodonEntities::Reaction>::new(
+            MegalodonEntities::Reaction {
+                count: accounts.len() as u32,
+                me,
+                name: emoji,
+                accounts: Some(accounts),
+            },
+            res.status,
+            res.status_text,
+            res.header,
+        ))
+    }
+
+    fn user_streaming(&self, streaming_url: String) -> Box<dyn Streaming + Send + Sync> {
+        Box::new(self.streaming(streaming_url, vec![StreamType::User]))
+    }
+
+    fn public_streaming(&self, streaming_url: String) -> Box<dyn Streaming + Send + Sync> {
+        Box::new(self.streaming(streaming_url, vec![StreamType::Public]))
+    }
+
+    fn local_streaming(&self, streaming_url: String) -> Box<dyn Streaming + Send + Sync> {
+        Box::new(self.streaming(streaming_url, vec![StreamType::PublicLocal]))
+    }
+
+    fn direct_streaming(&self, streaming_url: String) -> Box<dyn Streaming + Send + Sync> {
+        Box::new(self.streaming(streaming_url, vec![StreamType::Direct]))
+    }
+
+    fn tag_streaming(
+        &self,
+        streaming_url: String,
+        tag: String,
+    ) -> Box<dyn Streaming + Send + Sync> {
+        Box::new(self.streaming(streaming_url, vec![StreamType::Hashtag(tag)]))
+    }
+
+    fn list_streaming(
+        &self,
+        streaming_url: String,
+        list_id: String,
+    ) -> Box<dyn Streaming + Send + Sync> {
+        Box::new(self.streaming(streaming_url, vec![StreamType::List(list_id)]))
+    }
+
+    fn multiplexed_streaming(
+        &self,
+        streaming_url: String,
+    ) -> Box<dyn MultiplexedStreaming + Send + Sync> {
+        Box::new(self.streaming(streaming_url, Vec::new()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::StubServer;
+
+    const USER: &str = r#"{"id":"8y","name":"Bob","username":"bob","host":"remote.example","avatarUrl":"https://remote.example/avatar.png","isBot":false,"emojis":{},"url":"https://remote.example/@bob","uri":"https://remote.example/users/8y","createdAt":"2022-12-01T00:00:00.000Z","bannerUrl":null,"isLocked":true,"description":"Hello","fields":[{"name":"Website","value":"https://bob.example"}],"followersCount":10,"followingCount":20,"notesCount":30,"pinnedNotes":[]}"#;
+
+    const NOTE: &str = r#"{"id":"9a1","createdAt":"2023-01-05T12:00:00.000Z","userId":"8z","user":{"id":"8z","name":null,"username":"alice","host":null,"avatarUrl":null,"isBot":false,"emojis":{}},"text":"Hello **world** #rust","cw":"Spoiler","visibility":"followers","renoteCount":2,"repliesCount":1,"reactions":{"❤":3},"myReaction":"❤","emojis":{},"fileIds":[],"files":[],"replyId":null,"renoteId":null,"tags":["rust"]}"#;
+
+    const NOTIFICATIONS: &str = r#"[{"id":"n1","createdAt":"2023-01-05T12:00:00.000Z","type":"follow","userId":"8y","user":{"id":"8y","name":"Bob","username":"bob","host":"remote.example","avatarUrl":null,"isBot":false,"emojis":{}}},{"id":"n2","createdAt":"2023-01-05T12:01:00.000Z","type":"reaction","userId":"8y","user":{"id":"8y","name":"Bob","username":"bob","host":"remote.example","avatarUrl":null,"isBot":false,"emojis":{}},"note":{"id":"9a1","createdAt":"2023-01-05T12:00:00.000Z","userId":"8z","user":{"id":"8z","name":null,"username":"alice","host":null,"avatarUrl":null,"isBot":false,"emojis":{}},"text":"Hello","cw":null,"visibility":"public","renoteCount":0,"repliesCount":0,"reactions":{"👍":1},"fileIds":[],"files":[]},"reaction":"👍"},{"id":"n3","createdAt":"2023-01-05T12:02:00.000Z","type":"app","body":"Hello from the app","header":"App","icon":null}]"#;
+
+    #[tokio::test]
+    async fn test_register_app_generates_miauth_url() {
+        let client = Misskey::new("https://misskey.example".to_string(), None, None);
+        let options = megalodon::AppInputOptions {
+            scopes: Some(vec!["read".to_string(), "write:notes".to_string()]),
+            redirect_uris: Some("https://app.example/callback".to_string()),
+            website: None,
+        };
+
+        let app = client
+            .register_app("My App".to_string(), &options)
+            .await
+            .unwrap();
+        assert_eq!(app.client_id.len(), 36);
+        assert_eq!(app.client_secret, "");
+        let url = app.url.unwrap();
+        assert!(url.starts_with(&format!(
+            "https://misskey.example/miauth/{}?name=My%20App&permission=read:account,",
+            app.client_id
+        )));
+        assert!(url.contains(",write:notes&"));
+        assert!(url.ends_with("&callback=https%3A%2F%2Fapp.example%2Fcallback"));
+    }
+
+    #[tokio::test]
+    async fn test_fetch_access_token_checks_miauth_session() {
+        let server = StubServer::start().await;
+        server.route(
+            "POST",
+            "/api/miauth/session/check",
+            200,
+            r#"{"ok":true,"token":"token","user":{"id":"8z","name":null,"username":"alice","host":null,"avatarUrl":null,"isBot":false,"emojis":{}}}"#,
+        );
+        let client = Misskey::new(server.base_url.clone(), None, None);
+
+        let token = client
+            .fetch_access_token(
+                "session".to_string(),
+                String::new(),
+                String::new(),
+                default::NO_REDIRECT.to_string(),
+                None,
+            )
+            .await
+            .unwrap();
+        assert_eq!(token.access_token, "token");
+        assert_eq!(token.refresh_token, None);
+        assert_eq!(server.requests()[0].json(), serde_json::json!({}));
+    }
+
+    #[tokio::test]
+    async fn test_fetch_access_token_rejects_unauthorized_session() {
+        let server = StubServer::start().await;
+        server.route("POST", "/api/miauth/session/check", 200, r#"{"ok":false}"#);
+        let client = Misskey::new(server.base_url.clone(), None, None);
+
+        let err = client
+            .fetch_access_token(
+                "session".to_string(),
+                String::new(),
+                String::new(),
+                default::NO_REDIRECT.to_string(),
+                None,
+            )
+            .await
+            .unwrap_err();
+        let Error::OwnError(err) = err else {
+            panic!("Unexpected error: {:?}", err);
+        };
+        assert!(matches!(err.kind, Kind::UnauthorizedError));
+    }
+
+    #[tokio::test]
+    async fn test_get_status_maps_note() {
+        let server = StubServer::start().await;
+        server.route("POST", "/api/notes/show", 200, NOTE);
+        let client = Misskey::new(server.base_url.clone(), Some("token".to_string()), None);
+
+        let status = client.get_status("9a1".to_string()).await.unwrap().json;
+        assert_eq!(
+            server.requests()[0].json(),
+            serde_json::json!({"noteId": "9a1", "i": "token"})
+        );
+        assert_eq!(status.id, "9a1");
+        assert_eq!(status.uri, format!("{}/notes/9a1", server.base_url));
+        assert_eq!(status.account.acct, "alice");
+        assert_eq!(status.account.display_name, "alice");
+        assert_eq!(
+            status.plain_content,
+            Some("Hello **world** #rust".to_string())
+        );
+        assert_eq!(status.mfm_content, status.plain_content);
+        assert_eq!(status.spoiler_text, "Spoiler");
+        assert!(!status.sensitive);
+        assert!(matches!(
+            status.visibility,
+            MegalodonEntities::StatusVisibility::Private
+        ));
+        assert_eq!(status.replies_count, 1);
+        assert_eq!(status.reblogs_count, 2);
+        assert_eq!(status.favourites_count, 3);
+        assert_eq!(status.tags[0].name, "rust");
+        let reactions = status.emoji_reactions.unwrap();
+        assert!(reactions
+            .iter()
+            .any(|r| r.name == "❤" && r.count == 3 && r.me));
+    }
+
+    #[tokio::test]
+    async fn test_get_account_maps_user() {
+        let server = StubServer::start().await;
+        server.route("POST", "/api/users/show", 200, USER);
+        let client = Misskey::new(server.base_url.clone(), Some("token".to_string()), None);
+
+        let account = client.get_account("8y".to_string()).await.unwrap().json;
+        assert_eq!(
+            server.requests()[0].json(),
+            serde_json::json!({"userId": "8y", "i": "token"})
+        );
+        assert_eq!(account.id, "8y");
+        assert_eq!(account.acct, "bob@remote.example");
+        assert_eq!(account.display_name, "Bob");
+        assert_eq!(account.url, "https://remote.example/@bob");
+        assert!(account.locked);
+        assert_eq!(account.note, "Hello");
+        assert_eq!(account.followers_count, 10);
+        assert_eq!(account.following_count, 20);
+        assert_eq!(account.statuses_count, 30);
+        assert_eq!(account.avatar, "https://remote.example/avatar.png");
+        assert_eq!(account.header, "");
+        assert_eq!(account.fields.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_get_notifications_maps_notifications() {
+        let server = StubServer::start().await;
+        server.route("POST", "/api/i/notifications", 200, NOTIFICATIONS);
+        let client = Misskey::new(server.base_url.clone(), Some("token".to_string()), None);
+
+        let options = megalodon::GetNotificationsInputOptions {
+            limit: Some(10),
+            exclude_types: Some(vec![
+                MegalodonEntities::notification::NotificationType::Reblog,
+            ]),
+            ..Default::default()
+        };
+        let notifications = client.get_notifications(Some(&options)).await.unwrap().json;
+        assert_eq!(
+            server.requests()[0].json(),
+            serde_json::json!({"limit": 10, "excludeTypes": ["renote"], "i": "token"})
+        );
+
+        // Notifications from applications are not supported, so they are skipped.
+        assert_eq!(notifications.len(), 2);
+        assert!(matches!(
+            notifications[0].r#type,
+            MegalodonEntities::notification::NotificationType::Follow
+        ));
+        assert_eq!(notifications[0].account.acct, "bob@remote.example");
+        assert!(notifications[0].status.is_none());
+        assert!(matches!(
+            notifications[1].r#type,
+            MegalodonEntities::notification::NotificationType::EmojiReaction
+        ));
+        assert_eq!(notifications[1].emoji, Some("👍".to_string()));
+        assert_eq!(notifications[1].status.as_ref().unwrap().id, "9a1");
+    }
+
+    #[tokio::test]
+    async fn test_get_announcements() {
+        let server = StubServer::start().await;
+        server
+            .route(
+                "POST",
+                "/api/announcements",
+                200,
+                r#"[{"id":"9c1","createdAt":"2023-01-05T12:00:00.000Z","updatedAt":null,"title":"Maintenance","text":"Down <soon>\nSorry","imageUrl":"https://misskey.example/maintenance.png","isRead":false}]"#,
+            )
+            .route("POST", "/api/i/read-announcement", 204, "");
+        let client = Misskey::new(server.base_url.clone(), Some("token".to_string()), None);
+
+        let res = client.get_announcements().await.unwrap();
+        assert_eq!(res.json.len(), 1);
+        let announcement = &res.json[0];
+        assert_eq!(announcement.id, "9c1");
+        assert_eq!(
+            announcement.content,
+            r#"<p><strong>Maintenance</strong></p><p>Down &lt;soon&gt;<br>Sorry</p><p><img src="https://misskey.example/maintenance.png"></p>"#
+        );
+        assert_eq!(announcement.read, Some(false));
+
+        client
+            .dismiss_announcement("9c1".to_string())
+            .await
+            .unwrap();
+        let requests = server.requests();
+        assert_eq!(requests[1].path, "/api/i/read-announcement");
+        assert_eq!(requests[1].json()["announcementId"], "9c1");
+    }
+}
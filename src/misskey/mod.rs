@@ -0,0 +1,11 @@
+//! Misskey related modules
+
+mod api_client;
+pub mod entities;
+/// Misskey API client.
+#[allow(clippy::module_inception)]
+pub mod misskey;
+mod oauth;
+mod web_socket;
+
+pub use misskey::Misskey;
@@ -0,0 +1,8 @@
+use serde::Deserialize;
+
+/// Response of `miauth/{session}/check` endpoint.
+#[derive(Debug, Deserialize, Clone)]
+pub struct MiAuthCheck {
+    pub ok: bool,
+    pub token: Option<String>,
+}
@@ -0,0 +1,629 @@
+use std::fmt;
+
+use super::entities;
+use crate::default::DEFAULT_UA;
+use crate::entities as MegalodonEntities;
+use crate::error::{Error, Kind};
+use crate::streaming::{
+    self, Connection, ConnectionEnd, Message, MessageSender, MessageStream, MultiplexedHandle,
+    MultiplexedStreaming, Multiplexer, StreamType, Streaming, StreamingHandle, StreamingOptions,
+    TaggedMessage,
+};
+use async_trait::async_trait;
+use futures_util::{SinkExt, StreamExt};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::{
+    connect_async, tungstenite::protocol::frame::coding::CloseCode,
+    tungstenite::protocol::Message as WebSocketMessage,
+};
+use url::Url;
+
+/// ID of the main channel. The main channel is shared in a connection, so only one ID is used for it.
+const MAIN_CHANNEL_ID: &str = "main";
+
+#[derive(Debug, Clone)]
+pub struct WebSocket {
+    url: String,
+    base_url: String,
+    streams: Vec<StreamType>,
+    access_token: Option<String>,
+    user_agent: String,
+    options: StreamingOptions,
+}
+
+#[derive(Deserialize)]
+struct RawMessage {
+    r#type: String,
+    #[serde(default)]
+    body: Value,
+}
+
+#[derive(Deserialize)]
+struct RawChannelMessage {
+    id: String,
+    r#type: String,
+    #[serde(default)]
+    body: Value,
+}
+
+/// Misskey channel which is connected for a stream.
+#[derive(Debug, Clone, PartialEq)]
+struct Channel {
+    id: String,
+    name: &'static str,
+    params: Value,
+}
+
+impl Channel {
+    fn new(id: String, name: &'static str, params: Value) -> Self {
+        Self { id, name, params }
+    }
+
+    fn main() -> Self {
+        Self::new(MAIN_CHANNEL_ID.to_string(), "main", json!({}))
+    }
+
+    /// Get channels for a stream. Misskey does not have some streams, so they are empty.
+    fn for_stream(stream: &StreamType) -> Vec<Self> {
+        let id = channel_id(stream);
+        match stream {
+            StreamType::User => Vec::from([Self::new(id, "homeTimeline", json!({})), Self::main()]),
+            StreamType::UserNotification | StreamType::Direct => Vec::from([Self::main()]),
+            StreamType::Public => Vec::from([Self::new(id, "globalTimeline", json!({}))]),
+            StreamType::PublicMedia => {
+                Vec::from([Self::new(id, "globalTimeline", json!({"withFiles": true}))])
+            }
+            StreamType::PublicLocal => Vec::from([Self::new(id, "localTimeline", json!({}))]),
+            StreamType::PublicLocalMedia => {
+                Vec::from([Self::new(id, "localTimeline", json!({"withFiles": true}))])
+            }
+            StreamType::Hashtag(tag) => {
+                Vec::from([Self::new(id, "hashtag", json!({"q": [[tag]]}))])
+            }
+            StreamType::List(list) => {
+                Vec::from([Self::new(id, "userList", json!({"listId": list}))])
+            }
+            StreamType::PublicRemote
+            | StreamType::PublicRemoteMedia
+            | StreamType::HashtagLocal(_) => {
+                log::warn!("Misskey does not support {} stream", stream.name());
+                Vec::new()
+            }
+        }
+    }
+
+    fn connect_json(&self) -> Value {
+        json!({
+            "type": "connect",
+            "body": {"channel": self.name, "id": self.id, "params": self.params}
+        })
+    }
+
+    fn disconnect_json(&self) -> Value {
+        json!({"type": "disconnect", "body": {"id": self.id}})
+    }
+}
+
+/// Get the channel ID for a stream, which is used to find the stream of received messages.
+fn channel_id(stream: &StreamType) -> String {
+    match stream {
+        StreamType::Hashtag(param) | StreamType::HashtagLocal(param) | StreamType::List(param) => {
+            format!("{}:{}", stream.name(), param)
+        }
+        _ => stream.name().to_string(),
+    }
+}
+
+/// Get frames to connect and disconnect channels, to receive messages of the given streams.
+/// `connected` is updated to the channels after these frames are sent.
+fn sync_channels(connected: &mut Vec<Channel>, streams: &[StreamType]) -> Vec<Value> {
+    let mut channels = Vec::<Channel>::new();
+    for channel in streams.iter().flat_map(Channel::for_stream) {
+        if !channels.iter().any(|c| c.id == channel.id) {
+            channels.push(channel);
+        }
+    }
+    let mut frames: Vec<Value> = connected
+        .iter()
+        .filter(|c| !channels.iter().any(|channel| channel.id == c.id))
+        .map(Channel::disconnect_json)
+        .collect();
+    frames.extend(
+        channels
+            .iter()
+            .filter(|channel| !connected.iter().any(|c| c.id == channel.id))
+            .map(Channel::connect_json),
+    );
+    *connected = channels;
+    frames
+}
+
+impl WebSocket {
+    pub fn new(
+        url: String,
+        base_url: String,
+        streams: Vec<StreamType>,
+        access_token: Option<String>,
+        user_agent: Option<String>,
+        options: StreamingOptions,
+    ) -> Self {
+        let ua = user_agent.unwrap_or(DEFAULT_UA.to_string());
+        Self {
+            url,
+            base_url,
+            streams,
+            access_token,
+            user_agent: ua,
+            options,
+        }
+    }
+
+    /// Parse a message. Returns `None` for messages of the main channel which are not subscribed.
+    #[allow(clippy::result_large_err)]
+    fn parse(
+        &self,
+        message: WebSocketMessage,
+        streams: &[StreamType],
+    ) -> Result<Option<TaggedMessage>, Error> {
+        if message.is_ping() || message.is_pong() {
+            return Ok(Some(TaggedMessage {
+                stream: Vec::new(),
+                message: Message::Heartbeat(),
+            }));
+        }
+        if !message.is_text() {
+            return Err(Error::new_own(
+                String::from("Receiving message is not ping, pong or text"),
+                Kind::ParseError,
+                None,
+                None,
+            ));
+        }
+        let text = message.to_text()?;
+        let mes = serde_json::from_str::<RawMessage>(text)?;
+        match &*mes.r#type {
+            "channel" => {
+                let mes = parse_payload::<RawChannelMessage>("channel message", mes.body)?;
+                if mes.id == MAIN_CHANNEL_ID {
+                    Ok(self.parse_main(mes, streams)?)
+                } else {
+                    let stream = streams
+                        .iter()
+                        .filter(|s| channel_id(s) == mes.id)
+                        .cloned()
+                        .collect();
+                    let message = match &*mes.r#type {
+                        "note" => Message::Update(
+                            parse_payload::<entities::Note>("note", mes.body)?
+                                .into_status(&self.base_url),
+                        ),
+                        event => Message::Unknown {
+                            event: event.to_string(),
+                            payload: mes.body.to_string(),
+                        },
+                    };
+                    Ok(Some(TaggedMessage { stream, message }))
+                }
+            }
+            "noteUpdated" => {
+                let mes = parse_payload::<RawChannelMessage>("note update", mes.body)?;
+                let message = match &*mes.r#type {
+                    "deleted" => Message::Delete(mes.id),
+                    event => Message::Unknown {
+                        event: event.to_string(),
+                        payload: mes.body.to_string(),
+                    },
+                };
+                Ok(Some(TaggedMessage {
+                    stream: Vec::new(),
+                    message,
+                }))
+            }
+            event => Ok(Some(TaggedMessage {
+                stream: Vec::new(),
+                message: Message::Unknown {
+                    event: event.to_string(),
+                    payload: mes.body.to_string(),
+                },
+            })),
+        }
+    }
+
+    /// The main channel is shared by user, notification and direct streams,
+    /// so messages are tagged with the stream which subscribes them.
+    fn parse_main(
+        &self,
+        mes: RawChannelMessage,
+        streams: &[StreamType],
+    ) -> serde_json::Result<Option<TaggedMessage>> {
+        match &*mes.r#type {
+            "notification" => {
+                let stream: Vec<StreamType> = [StreamType::UserNotification, StreamType::User]
+                    .into_iter()
+                    .filter(|s| streams.contains(s))
+                    .collect();
+                if stream.is_empty() {
+                    return Ok(None);
+                }
+                let notification =
+                    parse_payload::<entities::Notification>("notification", mes.body)?
+                        .into_notification(&self.base_url);
+                Ok(notification.map(|notification| TaggedMessage {
+                    stream,
+                    message: Message::Notification(notification),
+                }))
+            }
+            "mention" => {
+                if !streams.contains(&StreamType::Direct) {
+                    return Ok(None);
+                }
+                let status =
+                    parse_payload::<entities::Note>("note", mes.body)?.into_status(&self.base_url);
+                if !matches!(
+                    status.visibility,
+                    MegalodonEntities::StatusVisibility::Direct
+                ) {
+                    return Ok(None);
+                }
+                Ok(Some(TaggedMessage {
+                    stream: vec![StreamType::Direct],
+                    message: Message::Update(status),
+                }))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    async fn do_connect<T>(
+        &self,
+        url: &str,
+        sender: &mut MessageSender<T>,
+        multiplexer: &mut Option<Multiplexer>,
+    ) -> Result<(), InnerError> {
+        let mut req = Url::parse(url)
+            .unwrap()
+            .into_client_request()
+            .map_err(|e| {
+                log::error!("Failed to parse url: {}", e);
+                InnerError::new(InnerKind::ConnectionError)
+            })?;
+        req.headers_mut()
+            .insert("User-Agent", self.user_agent.parse().unwrap());
+        let (mut socket, response) = connect_async(req).await.map_err(|e| {
+            log::error!("Failed to connect: {}", e);
+            InnerError::new(InnerKind::ConnectionError)
+        })?;
+
+        log::debug!("Connected to {}", url);
+        log::debug!("Response HTTP code: {}", response.status());
+        log::debug!("Response contains the following headers:");
+        for (ref header, _value) in response.headers() {
+            log::debug!("* {}", header);
+        }
+        if !sender.send(Message::Connected()).await {
+            let _ = socket.close(None).await;
+            return Ok(());
+        }
+        // Channels are lost when the socket is closed, so connect all channels again.
+        let mut streams = match multiplexer.as_mut() {
+            Some(multiplexer) => multiplexer.resubscribe(),
+            None => self.streams.clone(),
+        };
+        let mut channels = Vec::<Channel>::new();
+        for frame in sync_channels(&mut channels, &streams) {
+            socket
+                .send(WebSocketMessage::Text(frame.to_string()))
+                .await
+                .map_err(|e| {
+                    log::error!("Failed to connect channel: {}", e);
+                    InnerError::new(InnerKind::SocketReadError)
+                })?;
+        }
+
+        let mut ping = self
+            .options
+            .ping_interval
+            .map(|period| tokio::time::interval_at(tokio::time::Instant::now() + period, period));
+
+        loop {
+            let res = tokio::select! {
+                res = tokio::time::timeout(self.options.read_timeout, socket.next()) => res.map_err(|e| {
+                    log::error!("Timeout reading message: {}", e);
+                    InnerError::new(InnerKind::TimeoutError)
+                })?,
+                _ = async { ping.as_mut().unwrap().tick().await }, if ping.is_some() => {
+                    socket
+                        .send(WebSocketMessage::Ping(Vec::<u8>::new()))
+                        .await
+                        .map_err(|e| {
+                            log::error!("Failed to send ping: {}", e);
+                            InnerError::new(InnerKind::SocketReadError)
+                        })?;
+                    continue;
+                }
+                _ = async { multiplexer.as_mut().unwrap().next_command().await }, if multiplexer.is_some() => {
+                    // Subscriptions have already been changed when the command arrives.
+                    streams = multiplexer.as_ref().unwrap().subscriptions();
+                    for frame in sync_channels(&mut channels, &streams) {
+                        socket
+                            .send(WebSocketMessage::Text(frame.to_string()))
+                            .await
+                            .map_err(|e| {
+                                log::error!("Failed to change subscription: {}", e);
+                                InnerError::new(InnerKind::SocketReadError)
+                            })?;
+                    }
+                    continue;
+                }
+                _ = sender.closed() => {
+                    let _ = socket.close(None).await.map_err(|e| {
+                        log::error!("{:#?}", e);
+                        e
+                    });
+                    return Ok(());
+                }
+            };
+            let Some(r) = res else {
+                log::warn!("Connection to {} is lost", url);
+                return Err(InnerError::new(InnerKind::SocketReadError));
+            };
+            let msg = r.map_err(|e| {
+                log::error!("Failed to read message: {}", e);
+                InnerError::new(InnerKind::SocketReadError)
+            })?;
+            if msg.is_ping() {
+                let _ = socket
+                    .send(WebSocketMessage::Pong(Vec::<u8>::new()))
+                    .await
+                    .map_err(|e| {
+                        log::error!("{:#?}", e);
+                        e
+                    });
+            }
+            if msg.is_close() {
+                let _ = socket.close(None).await.map_err(|e| {
+                    log::error!("{:#?}", e);
+                    e
+                });
+                if let WebSocketMessage::Close(Some(close)) = msg {
+                    log::warn!("Connection to {} is closed because {}", url, close.code);
+                    if close.code != CloseCode::Normal {
+                        return Err(InnerError::new(InnerKind::UnusualSocketCloseError));
+                    }
+                }
+                return Ok(());
+            }
+            match self.parse(msg, &streams) {
+                Ok(Some(message)) => {
+                    // Misskey sends deletion only for captured notes.
+                    if let Message::Update(status) = &message.message {
+                        let frame = json!({"type": "subNote", "body": {"id": status.id}});
+                        let _ = socket
+                            .send(WebSocketMessage::Text(frame.to_string()))
+                            .await
+                            .map_err(|e| {
+                                log::error!("{:#?}", e);
+                                e
+                            });
+                    }
+                    if !sender.send_tagged(message).await {
+                        let _ = socket.close(None).await.map_err(|e| {
+                            log::error!("{:#?}", e);
+                            e
+                        });
+                        return Ok(());
+                    }
+                }
+                Ok(None) => {}
+                Err(err) => {
+                    log::warn!("{}", err);
+                }
+            }
+        }
+    }
+
+    fn url(&self) -> String {
+        match &self.access_token {
+            Some(access_token) => format!("{}?i={}", self.url, access_token),
+            None => self.url.clone(),
+        }
+    }
+}
+
+#[async_trait]
+impl<T: Send> Connection<T> for WebSocket {
+    async fn connect_once(
+        &self,
+        url: &str,
+        sender: &mut MessageSender<T>,
+        multiplexer: &mut Option<Multiplexer>,
+    ) -> ConnectionEnd {
+        match self.do_connect(url, sender, multiplexer).await {
+            Ok(()) => ConnectionEnd::Closed,
+            Err(err) => match err.kind {
+                InnerKind::ConnectionError => ConnectionEnd::Failed,
+                InnerKind::SocketReadError
+                | InnerKind::UnusualSocketCloseError
+                | InnerKind::TimeoutError => ConnectionEnd::Lost,
+            },
+        }
+    }
+}
+
+#[async_trait]
+impl Streaming for WebSocket {
+    fn subscribe(&self) -> (MessageStream, StreamingHandle) {
+        let url = self.url();
+        let (sender, messages, handle) = streaming::channel();
+        let ws = self.clone();
+        tokio::spawn(async move {
+            streaming::keep_connected(&ws, url.as_str(), &ws.options, sender, None).await;
+        });
+        (messages, handle)
+    }
+}
+
+impl MultiplexedStreaming for WebSocket {
+    fn connect(
+        &self,
+        streams: Vec<StreamType>,
+    ) -> (MessageStream<TaggedMessage>, MultiplexedHandle) {
+        let url = self.url();
+        let (sender, multiplexer, messages, handle) = streaming::multiplexed_channel(streams);
+        let ws = self.clone();
+        tokio::spawn(async move {
+            streaming::keep_connected(&ws, url.as_str(), &ws.options, sender, Some(multiplexer))
+                .await;
+        });
+        (messages, handle)
+    }
+}
+
+fn parse_payload<T: DeserializeOwned>(name: &str, payload: Value) -> serde_json::Result<T> {
+    serde_json::from_value::<T>(payload.clone()).map_err(|e| {
+        log::error!("failed to parse {}: {}\n{}", name, e, payload);
+        e
+    })
+}
+
+#[derive(thiserror::Error)]
+#[error("{kind}")]
+struct InnerError {
+    kind: InnerKind,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[allow(clippy::enum_variant_names)]
+enum InnerKind {
+    #[error("connection error")]
+    ConnectionError,
+    #[error("socket read error")]
+    SocketReadError,
+    #[error("unusual socket close error")]
+    UnusualSocketCloseError,
+    #[error("timeout error")]
+    TimeoutError,
+}
+
+impl InnerError {
+    pub fn new(kind: InnerKind) -> Self {
+        Self { kind }
+    }
+}
+
+impl fmt::Debug for InnerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut builder = f.debug_struct("megalodon::misskey::web_socket::InnerError");
+
+        builder.field("kind", &self.kind);
+        builder.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sync_channels() {
+        let mut connected = Vec::<Channel>::new();
+        let frames = sync_channels(
+            &mut connected,
+            &[StreamType::User, StreamType::UserNotification],
+        );
+        assert_eq!(
+            frames,
+            Vec::from([
+                json!({"type": "connect", "body": {"channel": "homeTimeline", "id": "user", "params": {}}}),
+                json!({"type": "connect", "body": {"channel": "main", "id": "main", "params": {}}}),
+            ])
+        );
+
+        let frames = sync_channels(
+            &mut connected,
+            &[
+                StreamType::UserNotification,
+                StreamType::Hashtag("rust".to_string()),
+            ],
+        );
+        assert_eq!(
+            frames,
+            Vec::from([
+                json!({"type": "disconnect", "body": {"id": "user"}}),
+                json!({"type": "connect", "body": {"channel": "hashtag", "id": "hashtag:rust", "params": {"q": [["rust"]]}}}),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_parse_channel_message() {
+        let ws = WebSocket::new(
+            "wss://misskey.example/streaming".to_string(),
+            "https://misskey.example".to_string(),
+            Vec::new(),
+            None,
+            None,
+            StreamingOptions::default(),
+        );
+        let streams = [StreamType::Hashtag("rust".to_string())];
+        let text = r##"{"type":"channel","body":{"id":"hashtag:rust","type":"note","body":{"id":"9a1","createdAt":"2023-01-05T12:00:00.000Z","user":{"id":"8z","username":"alice","host":null},"text":"#rust","cw":null,"visibility":"public"}}}"##;
+
+        let message = ws
+            .parse(WebSocketMessage::Text(text.to_string()), &streams)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            message.stream,
+            vec![StreamType::Hashtag("rust".to_string())]
+        );
+        match message.message {
+            Message::Update(status) => assert_eq!(status.id, "9a1"),
+            message => panic!("unexpected message: {:?}", message),
+        }
+
+        let text = r#"{"type":"noteUpdated","body":{"id":"9a1","type":"deleted","body":{"deletedAt":"2023-01-05T12:01:00.000Z"}}}"#;
+        let message = ws
+            .parse(WebSocketMessage::Text(text.to_string()), &streams)
+            .unwrap()
+            .unwrap();
+        match message.message {
+            Message::Delete(id) => assert_eq!(id, "9a1"),
+            message => panic!("unexpected message: {:?}", message),
+        }
+    }
+
+    #[test]
+    fn test_parse_notification_for_every_stream() {
+        let ws = WebSocket::new(
+            "wss://misskey.example/streaming".to_string(),
+            "https://misskey.example".to_string(),
+            Vec::new(),
+            None,
+            None,
+            StreamingOptions::default(),
+        );
+        let text = r#"{"type":"channel","body":{"id":"main","type":"notification","body":{"id":"n1","createdAt":"2023-01-05T12:00:00.000Z","type":"follow","userId":"8y","user":{"id":"8y","name":"Bob","username":"bob","host":"remote.example","avatarUrl":null,"isBot":false,"emojis":{}}}}}"#;
+
+        let streams = [StreamType::User, StreamType::UserNotification];
+        let message = ws
+            .parse(WebSocketMessage::Text(text.to_string()), &streams)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            message.stream,
+            vec![StreamType::UserNotification, StreamType::User]
+        );
+        assert!(matches!(message.message, Message::Notification(_)));
+
+        let message = ws
+            .parse(
+                WebSocketMessage::Text(text.to_string()),
+                &[StreamType::Direct],
+            )
+            .unwrap();
+        assert!(message.is_none());
+    }
+}
@@ -0,0 +1,178 @@
+//! NodeInfo modules, which describe the software of a server
+use crate::error::{Error, Kind};
+use serde::Deserialize;
+use serde_json::Value;
+
+const SCHEMA_2_0: &str = "http://nodeinfo.diaspora.software/ns/schema/2.0";
+const SCHEMA_2_1: &str = "http://nodeinfo.diaspora.software/ns/schema/2.1";
+
+/// NodeInfo document, which is defined in <https://nodeinfo.diaspora.software/>.
+/// Only 2.0 and 2.1 are supported.
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeInfo {
+    /// The schema version.
+    pub version: String,
+    /// Metadata about the server software.
+    pub software: Software,
+    /// The protocols supported on this server.
+    #[serde(default)]
+    pub protocols: Vec<String>,
+    /// Whether this server allows open self-registration.
+    #[serde(default)]
+    pub open_registrations: bool,
+    /// Free form key value pairs for software specific values.
+    #[serde(default)]
+    pub metadata: Value,
+}
+
+/// Metadata about the server software.
+#[derive(Debug, Deserialize, Clone)]
+pub struct Software {
+    /// The canonical name of this server software, like `mastodon`.
+    pub name: String,
+    /// The version of this server software.
+    pub version: String,
+}
+
+impl NodeInfo {
+    /// Get features in metadata. Pleroma and Akkoma list their features, like `pleroma_emoji_reactions`.
+    pub fn features(&self) -> Vec<String> {
+        self.metadata
+            .get("features")
+            .and_then(Value::as_array)
+            .map(|features| {
+                features
+                    .iter()
+                    .filter_map(|f| f.as_str().map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Deserialize)]
+struct WellKnown {
+    links: Vec<Link>,
+}
+
+#[derive(Deserialize)]
+struct Link {
+    rel: String,
+    href: String,
+}
+
+/// Fetch the NodeInfo document from `/.well-known/nodeinfo`. 2.1 is preferred over 2.0.
+pub async fn fetch(client: &reqwest::Client, url: &str) -> Result<NodeInfo, Error> {
+    let well_known = get::<WellKnown>(client, format!("{}/.well-known/nodeinfo", url)).await?;
+    let link = [SCHEMA_2_1, SCHEMA_2_0]
+        .iter()
+        .find_map(|schema| well_known.links.iter().find(|link| link.rel == *schema))
+        .ok_or_else(|| {
+            Error::new_own(
+                "NodeInfo 2.0 or 2.1 is not found".to_string(),
+                Kind::NotFoundError,
+                Some(format!("{}/.well-known/nodeinfo", url)),
+                None,
+            )
+        })?;
+    get::<NodeInfo>(client, link.href.clone()).await
+}
+
+async fn get<T: serde::de::DeserializeOwned>(
+    client: &reqwest::Client,
+    url: String,
+) -> Result<T, Error> {
+    let res = client.get(&url).send().await?;
+    let status = res.status();
+    if !status.is_success() {
+        return Err(Error::new_http(
+            res.text().await.unwrap_or_default(),
+            Kind::from_status(status.as_u16()),
+            Some(url),
+            Some(status.as_u16()),
+        ));
+    }
+    Ok(res.json::<T>().await?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::StubServer;
+
+    #[test]
+    fn test_nodeinfo_features() {
+        let text = r#"{"version":"2.1","software":{"name":"akkoma","version":"3.9.3"},"protocols":["activitypub"],"openRegistrations":false,"usage":{"users":{"total":1}},"metadata":{"features":["pleroma_api","pleroma_emoji_reactions",1]}}"#;
+
+        let nodeinfo = serde_json::from_str::<NodeInfo>(text).unwrap();
+        assert_eq!(nodeinfo.software.name, "akkoma");
+        assert_eq!(
+            nodeinfo.features(),
+            vec![
+                "pleroma_api".to_string(),
+                "pleroma_emoji_reactions".to_string()
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fetch_prefers_2_1() {
+        let server = StubServer::start().await;
+        server
+            .route(
+                "GET",
+                "/.well-known/nodeinfo",
+                200,
+                &format!(
+                    r#"{{"links":[{{"rel":"{}","href":"{}/nodeinfo/2.0"}},{{"rel":"{}","href":"{}/nodeinfo/2.1"}}]}}"#,
+                    SCHEMA_2_0, server.base_url, SCHEMA_2_1, server.base_url
+                ),
+            )
+            .route(
+                "GET",
+                "/nodeinfo/2.0",
+                200,
+                r#"{"version":"2.0","software":{"name":"mastodon","version":"4.1.0"}}"#,
+            )
+            .route(
+                "GET",
+                "/nodeinfo/2.1",
+                200,
+                r#"{"version":"2.1","software":{"name":"mastodon","version":"4.2.0"}}"#,
+            );
+
+        let nodeinfo = fetch(&reqwest::Client::new(), &server.base_url)
+            .await
+            .unwrap();
+        assert_eq!(nodeinfo.version, "2.1");
+        assert_eq!(nodeinfo.software.version, "4.2.0");
+        assert!(server
+            .requests()
+            .iter()
+            .all(|request| request.path != "/nodeinfo/2.0"));
+    }
+
+    #[tokio::test]
+    async fn test_fetch_without_supported_schema() {
+        let server = StubServer::start().await;
+        server.route(
+            "GET",
+            "/.well-known/nodeinfo",
+            200,
+            &format!(
+                r#"{{"links":[{{"rel":"http://nodeinfo.diaspora.software/ns/schema/1.0","href":"{}/nodeinfo/1.0"}}]}}"#,
+                server.base_url
+            ),
+        );
+
+        let err = fetch(&reqwest::Client::new(), &server.base_url)
+            .await
+            .unwrap_err();
+        let Error::OwnError(err) = err else {
+            panic!("Unexpected error: {:?}", err);
+        };
+        assert!(matches!(err.kind, Kind::NotFoundError));
+        assert_eq!(server.requests().len(), 1);
+    }
+}
@@ -18,6 +18,10 @@ pub struct AppData {
     pub client_secret: String,
     /// Authorize URL for the application.
     pub url: Option<String>,
+    /// `state` parameter in the authorize URL, which has to be returned to the redirect URI as it is.
+    pub state: Option<String>,
+    /// PKCE code verifier, which has to be sent with the authorization code.
+    pub code_verifier: Option<String>,
 }
 
 impl AppData {
@@ -38,6 +42,25 @@ impl AppData {
             client_id,
             client_secret,
             url: None,
+            state: None,
+            code_verifier: None,
+        }
+    }
+
+    /// Verify `state` which is returned to the redirect URI, to prevent CSRF.
+    /// It is always invalid if the authorize URL does not have `state`.
+    pub fn verify_state(&self, state: &str) -> bool {
+        match &self.state {
+            // Compare all bytes, so the time does not depend on the position of a difference.
+            Some(expected) => {
+                expected.len() == state.len()
+                    && expected
+                        .bytes()
+                        .zip(state.bytes())
+                        .fold(0, |acc, (a, b)| acc | (a ^ b))
+                        == 0
+            }
+            None => false,
         }
     }
 }
@@ -79,3 +102,26 @@ impl TokenData {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verify_state() {
+        let mut app = AppData::new(
+            "1".to_string(),
+            "megalodon".to_string(),
+            None,
+            "urn:ietf:wg:oauth:2.0:oob".to_string(),
+            "client_id".to_string(),
+            "client_secret".to_string(),
+        );
+        assert!(!app.verify_state("abcd"));
+
+        app.state = Some("abcd".to_string());
+        assert!(app.verify_state("abcd"));
+        assert!(!app.verify_state("abce"));
+        assert!(!app.verify_state("abc"));
+    }
+}
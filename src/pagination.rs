@@ -0,0 +1,743 @@
+//! Pagination modules
+//!
+//! The streams in this module follow `Link` headers of list endpoints and yield each item one by one.
+//!
+//! ```rust
+//! # use megalodon;
+//! # use megalodon::error::Error;
+//! use futures_util::StreamExt;
+//! use megalodon::pagination::{self, StreamOptions};
+//! #
+//! # async fn run() -> Result<(), Error> {
+//! let client = megalodon::generator(
+//!   megalodon::SNS::Mastodon,
+//!   String::from("https://fedibird.com"),
+//!   Some(String::from("your access token")),
+//!   None,
+//! );
+//! let mut statuses = pagination::home_timeline(
+//!   client.as_ref(),
+//!   Default::default(),
+//!   StreamOptions {
+//!     limit: Some(100),
+//!     ..Default::default()
+//!   },
+//! );
+//! while let Some(status) = statuses.next().await {
+//!   println!("{:#?}", status?);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+use crate::entities;
+use crate::error::Error;
+use crate::megalodon::{
+    AccountFollowersInputOptions, GetAccountStatusesInputOptions, GetAccountsInListInputOptions,
+    GetArrayOptions, GetArrayWithSinceOptions, GetBlocksInputOptions, GetBookmarksInputOptions,
+    GetEndorsementsInputOptions, GetFavouritesInputOptions, GetHomeTimelineInputOptions,
+    GetInstanceDirectoryInputOptions, GetMutesInputOptions, GetNotificationsInputOptions,
+    GetTimelineOptions, GetTimelineOptionsWithLocal, GetTrendsInputOptions,
+    SearchAccountInputOptions, SearchInputOptions,
+};
+use crate::response::Response;
+use crate::Megalodon;
+use chrono::{DateTime, Utc};
+use futures_util::stream::{self, BoxStream};
+use reqwest::header::{HeaderMap, LINK};
+use std::collections::VecDeque;
+use std::future::Future;
+use url::Url;
+
+/// Pagination cursor which is parsed from a `Link` header.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Cursor {
+    /// Return results older than this ID.
+    pub max_id: Option<String>,
+    /// Return results immediately newer than this ID.
+    pub min_id: Option<String>,
+    /// Return results newer than this ID.
+    pub since_id: Option<String>,
+    /// Offset in results.
+    pub offset: Option<u64>,
+}
+
+impl Cursor {
+    /// Create a [`Cursor`] from the query of a URL. Returns `None` if the URL does not contain any cursor parameter.
+    pub fn from_url(url: &str) -> Option<Self> {
+        let base = Url::parse("http://localhost/").ok()?;
+        let url = base.join(url).ok()?;
+        let mut cursor = Cursor::default();
+        for (key, value) in url.query_pairs() {
+            match key.as_ref() {
+                "max_id" => cursor.max_id = Some(value.into_owned()),
+                "min_id" => cursor.min_id = Some(value.into_owned()),
+                "since_id" => cursor.since_id = Some(value.into_owned()),
+                "offset" => cursor.offset = value.parse::<u64>().ok(),
+                _ => {}
+            }
+        }
+        if cursor == Cursor::default() {
+            None
+        } else {
+            Some(cursor)
+        }
+    }
+}
+
+/// Cursors of the next and previous pages.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Links {
+    /// Cursor of `rel="next"`, which points older results.
+    pub next: Option<Cursor>,
+    /// Cursor of `rel="prev"`, which points newer results.
+    pub prev: Option<Cursor>,
+}
+
+impl Links {
+    /// Parse `Link` headers in the headers.
+    pub fn from_headers(header: &HeaderMap) -> Self {
+        let mut links = Links::default();
+        for value in header.get_all(LINK) {
+            let Ok(value) = value.to_str() else {
+                continue;
+            };
+            let parsed = Links::parse(value);
+            if links.next.is_none() {
+                links.next = parsed.next;
+            }
+            if links.prev.is_none() {
+                links.prev = parsed.prev;
+            }
+        }
+        links
+    }
+
+    /// Parse a value of `Link` header, like `<https://example.com/api/v1/timelines/home?max_id=1>; rel="next"`.
+    pub fn parse(value: &str) -> Self {
+        let mut links = Links::default();
+        let mut rest = value;
+        while let Some(start) = rest.find('<') {
+            let Some(end) = rest[start..].find('>') else {
+                break;
+            };
+            let url = &rest[start + 1..start + end];
+            rest = &rest[start + end + 1..];
+            let params_end = rest.find('<').unwrap_or(rest.len());
+            let params = &rest[..params_end];
+
+            for param in params.split([';', ',']) {
+                let Some((key, rels)) = param.split_once('=') else {
+                    continue;
+                };
+                if !key.trim().eq_ignore_ascii_case("rel") {
+                    continue;
+                }
+                for rel in rels.trim().trim_matches('"').split_whitespace() {
+                    match rel {
+                        "next" => links.next = Cursor::from_url(url),
+                        "prev" | "previous" => links.prev = Cursor::from_url(url),
+                        _ => {}
+                    }
+                }
+            }
+        }
+        links
+    }
+}
+
+/// Input options which accept a pagination [`Cursor`].
+pub trait Paginate {
+    /// Replace pagination parameters with the cursor, and keep other options.
+    /// If the options do not have `min_id`, `min_id` of the cursor is set to `since_id`.
+    fn apply_cursor(&mut self, cursor: &Cursor);
+
+    /// Return new options which the cursor is applied.
+    fn with_cursor(mut self, cursor: &Cursor) -> Self
+    where
+        Self: Sized,
+    {
+        self.apply_cursor(cursor);
+        self
+    }
+}
+
+macro_rules! impl_paginate_with_min_id {
+    ($($t:ty),*) => {
+        $(
+            impl Paginate for $t {
+                fn apply_cursor(&mut self, cursor: &Cursor) {
+                    self.max_id = cursor.max_id.clone();
+                    self.min_id = cursor.min_id.clone();
+                    self.since_id = cursor.since_id.clone();
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! impl_paginate_without_min_id {
+    ($($t:ty),*) => {
+        $(
+            impl Paginate for $t {
+                fn apply_cursor(&mut self, cursor: &Cursor) {
+                    self.max_id = cursor.max_id.clone();
+                    self.since_id = cursor.since_id.clone().or(cursor.min_id.clone());
+                }
+            }
+        )*
+    };
+}
+
+impl_paginate_with_min_id!(
+    GetArrayWithSinceOptions,
+    GetTimelineOptions,
+    GetTimelineOptionsWithLocal,
+    GetNotificationsInputOptions
+);
+
+impl_paginate_without_min_id!(
+    GetAccountStatusesInputOptions,
+    AccountFollowersInputOptions,
+    SearchAccountInputOptions,
+    GetEndorsementsInputOptions
+);
+
+impl Paginate for GetArrayOptions {
+    fn apply_cursor(&mut self, cursor: &Cursor) {
+        self.max_id = cursor.max_id.clone();
+        self.min_id = cursor.min_id.clone().or(cursor.since_id.clone());
+    }
+}
+
+impl Paginate for SearchInputOptions {
+    fn apply_cursor(&mut self, cursor: &Cursor) {
+        self.max_id = cursor.max_id.clone();
+        self.min_id = cursor.min_id.clone().or(cursor.since_id.clone());
+        self.offset = cursor.offset;
+    }
+}
+
+impl Paginate for GetInstanceDirectoryInputOptions {
+    fn apply_cursor(&mut self, cursor: &Cursor) {
+        self.offset = cursor.offset;
+    }
+}
+
+impl Paginate for GetTrendsInputOptions {
+    fn apply_cursor(&mut self, cursor: &Cursor) {
+        self.offset = cursor.offset;
+    }
+}
+
+/// Items which are returned from paginated endpoints.
+pub trait PageItem {
+    /// ID of the item.
+    fn id(&self) -> &str;
+
+    /// Created date of the item which is used to stop streams. Returns `None` if the item is not ordered by the date.
+    fn created_at(&self) -> Option<DateTime<Utc>>;
+}
+
+impl PageItem for entities::Status {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn created_at(&self) -> Option<DateTime<Utc>> {
+        Some(self.created_at)
+    }
+}
+
+impl PageItem for entities::Notification {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn created_at(&self) -> Option<DateTime<Utc>> {
+        Some(self.created_at)
+    }
+}
+
+impl PageItem for entities::Account {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    // Accounts are ordered by the relationship, not by the created date of accounts.
+    fn created_at(&self) -> Option<DateTime<Utc>> {
+        None
+    }
+}
+
+impl PageItem for entities::Tag {
+    fn id(&self) -> &str {
+        &self.name
+    }
+
+    fn created_at(&self) -> Option<DateTime<Utc>> {
+        None
+    }
+}
+
+impl PageItem for entities::TrendLink {
+    fn id(&self) -> &str {
+        &self.card.url
+    }
+
+    fn created_at(&self) -> Option<DateTime<Utc>> {
+        None
+    }
+}
+
+/// Options to stop streams.
+#[derive(Debug, Clone, Default)]
+pub struct StreamOptions {
+    /// Maximum number of items to yield.
+    pub limit: Option<usize>,
+    /// Stop when the item of this ID is reached. The item is not yielded.
+    pub until_id: Option<String>,
+    /// Stop when an item created before this date is reached.
+    pub until_date: Option<DateTime<Utc>>,
+}
+
+impl StreamOptions {
+    fn limit_reached(&self, yielded: usize) -> bool {
+        self.limit.is_some_and(|limit| yielded >= limit)
+    }
+
+    fn should_stop<T: PageItem>(&self, item: &T, yielded: usize) -> bool {
+        if self.limit_reached(yielded) {
+            return true;
+        }
+        if let Some(until_id) = &self.until_id {
+            if item.id() == until_id {
+                return true;
+            }
+        }
+        if let (Some(until_date), Some(created_at)) = (self.until_date, item.created_at()) {
+            if created_at < until_date {
+                return true;
+            }
+        }
+        false
+    }
+}
+
+struct PageState<T, O, F> {
+    fetch: F,
+    options: Option<O>,
+    buffer: VecDeque<T>,
+    yielded: usize,
+    stop: StreamOptions,
+}
+
+/// Create a stream which calls `fetch` with the options, and follows `rel="next"` of the responses until the stop condition is satisfied.
+pub fn paginate<'a, T, O, F, Fut>(
+    options: O,
+    stop: StreamOptions,
+    fetch: F,
+) -> BoxStream<'a, Result<T, Error>>
+where
+    T: PageItem + Send + 'a,
+    O: Paginate + Clone + Send + 'a,
+    F: Fn(O) -> Fut + Send + 'a,
+    Fut: Future<Output = Result<Response<Vec<T>>, Error>> + Send + 'a,
+{
+    let state = PageState {
+        fetch,
+        options: Some(options),
+        buffer: VecDeque::new(),
+        yielded: 0,
+        stop,
+    };
+
+    Box::pin(stream::unfold(state, |mut state| async move {
+        loop {
+            if let Some(item) = state.buffer.pop_front() {
+                if state.stop.should_stop(&item, state.yielded) {
+                    return None;
+                }
+                state.yielded += 1;
+                return Some((Ok(item), state));
+            }
+
+            // Do not fetch the next page when no more items are yielded.
+            if state.stop.limit_reached(state.yielded) {
+                return None;
+            }
+            let options = state.options.take()?;
+            match (state.fetch)(options.clone()).await {
+                Ok(res) => {
+                    if !res.json.is_empty() {
+                        state.options = res.next().map(|cursor| options.with_cursor(&cursor));
+                    }
+                    state.buffer.extend(res.json);
+                    if state.buffer.is_empty() {
+                        return None;
+                    }
+                }
+                Err(err) => return Some((Err(err), state)),
+            }
+        }
+    }))
+}
+
+/// Stream statuses of [`Megalodon::get_home_timeline`].
+pub fn home_timeline<'a, C>(
+    client: &'a C,
+    options: GetHomeTimelineInputOptions,
+    stop: StreamOptions,
+) -> BoxStream<'a, Result<entities::Status, Error>>
+where
+    C: Megalodon + Sync + ?Sized,
+{
+    paginate(options, stop, move |options| async move {
+        client.get_home_timeline(Some(&options)).await
+    })
+}
+
+/// Stream statuses of [`Megalodon::get_account_statuses`].
+pub fn account_statuses<'a, C>(
+    client: &'a C,
+    id: String,
+    options: GetAccountStatusesInputOptions,
+    stop: StreamOptions,
+) -> BoxStream<'a, Result<entities::Status, Error>>
+where
+    C: Megalodon + Sync + ?Sized,
+{
+    paginate(options, stop, move |options| {
+        let id = id.clone();
+        async move { client.get_account_statuses(id, Some(&options)).await }
+    })
+}
+
+/// Stream accounts of [`Megalodon::get_account_followers`].
+pub fn account_followers<'a, C>(
+    client: &'a C,
+    id: String,
+    options: AccountFollowersInputOptions,
+    stop: StreamOptions,
+) -> BoxStream<'a, Result<entities::Account, Error>>
+where
+    C: Megalodon + Sync + ?Sized,
+{
+    paginate(options, stop, move |options| {
+        let id = id.clone();
+        async move { client.get_account_followers(id, Some(&options)).await }
+    })
+}
+
+/// Stream accounts of [`Megalodon::get_account_following`].
+pub fn account_following<'a, C>(
+    client: &'a C,
+    id: String,
+    options: AccountFollowersInputOptions,
+    stop: StreamOptions,
+) -> BoxStream<'a, Result<entities::Account, Error>>
+where
+    C: Megalodon + Sync + ?Sized,
+{
+    paginate(options, stop, move |options| {
+        let id = id.clone();
+        async move { client.get_account_following(id, Some(&options)).await }
+    })
+}
+
+/// Stream notifications of [`Megalodon::get_notifications`].
+pub fn notifications<'a, C>(
+    client: &'a C,
+    options: GetNotificationsInputOptions,
+    stop: StreamOptions,
+) -> BoxStream<'a, Result<entities::Notification, Error>>
+where
+    C: Megalodon + Sync + ?Sized,
+{
+    paginate(options, stop, move |options| async move {
+        client.get_notifications(Some(&options)).await
+    })
+}
+
+/// Stream statuses of [`Megalodon::get_bookmarks`].
+pub fn bookmarks<'a, C>(
+    client: &'a C,
+    options: GetBookmarksInputOptions,
+    stop: StreamOptions,
+) -> BoxStream<'a, Result<entities::Status, Error>>
+where
+    C: Megalodon + Sync + ?Sized,
+{
+    paginate(options, stop, move |options| async move {
+        client.get_bookmarks(Some(&options)).await
+    })
+}
+
+/// Stream statuses of [`Megalodon::get_favourites`].
+pub fn favourites<'a, C>(
+    client: &'a C,
+    options: GetFavouritesInputOptions,
+    stop: StreamOptions,
+) -> BoxStream<'a, Result<entities::Status, Error>>
+where
+    C: Megalodon + Sync + ?Sized,
+{
+    paginate(options, stop, move |options| async move {
+        client.get_favourites(Some(&options)).await
+    })
+}
+
+/// Stream accounts of [`Megalodon::get_blocks`].
+pub fn blocks<'a, C>(
+    client: &'a C,
+    options: GetBlocksInputOptions,
+    stop: StreamOptions,
+) -> BoxStream<'a, Result<entities::Account, Error>>
+where
+    C: Megalodon + Sync + ?Sized,
+{
+    paginate(options, stop, move |options| async move {
+        client.get_blocks(Some(&options)).await
+    })
+}
+
+/// Stream accounts of [`Megalodon::get_mutes`].
+pub fn mutes<'a, C>(
+    client: &'a C,
+    options: GetMutesInputOptions,
+    stop: StreamOptions,
+) -> BoxStream<'a, Result<entities::Account, Error>>
+where
+    C: Megalodon + Sync + ?Sized,
+{
+    paginate(options, stop, move |options| async move {
+        client.get_mutes(Some(&options)).await
+    })
+}
+
+/// Stream accounts of [`Megalodon::get_accounts_in_list`].
+pub fn accounts_in_list<'a, C>(
+    client: &'a C,
+    id: String,
+    options: GetAccountsInListInputOptions,
+    stop: StreamOptions,
+) -> BoxStream<'a, Result<entities::Account, Error>>
+where
+    C: Megalodon + Sync + ?Sized,
+{
+    paginate(options, stop, move |options| {
+        let id = id.clone();
+        async move { client.get_accounts_in_list(id, Some(&options)).await }
+    })
+}
+
+/// Stream tags of [`Megalodon::get_trend_tags`].
+pub fn trend_tags<'a, C>(
+    client: &'a C,
+    options: GetTrendsInputOptions,
+    stop: StreamOptions,
+) -> BoxStream<'a, Result<entities::Tag, Error>>
+where
+    C: Megalodon + Sync + ?Sized,
+{
+    paginate(options, stop, move |options| async move {
+        client.get_trend_tags(Some(&options)).await
+    })
+}
+
+/// Stream statuses of [`Megalodon::get_trend_statuses`].
+pub fn trend_statuses<'a, C>(
+    client: &'a C,
+    options: GetTrendsInputOptions,
+    stop: StreamOptions,
+) -> BoxStream<'a, Result<entities::Status, Error>>
+where
+    C: Megalodon + Sync + ?Sized,
+{
+    paginate(options, stop, move |options| async move {
+        client.get_trend_statuses(Some(&options)).await
+    })
+}
+
+/// Stream links of [`Megalodon::get_trend_links`].
+pub fn trend_links<'a, C>(
+    client: &'a C,
+    options: GetTrendsInputOptions,
+    stop: StreamOptions,
+) -> BoxStream<'a, Result<entities::TrendLink, Error>>
+where
+    C: Megalodon + Sync + ?Sized,
+{
+    paginate(options, stop, move |options| async move {
+        client.get_trend_links(Some(&options)).await
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_util::StreamExt;
+    use reqwest::header::HeaderValue;
+
+    #[derive(Debug)]
+    struct Item(String);
+
+    impl PageItem for Item {
+        fn id(&self) -> &str {
+            &self.0
+        }
+
+        fn created_at(&self) -> Option<DateTime<Utc>> {
+            None
+        }
+    }
+
+    /// Response of the page which is selected with `max_id`, linking to the next page.
+    fn page(options: &GetArrayOptions) -> Result<Response<Vec<Item>>, Error> {
+        let (ids, link) = match options.max_id.as_deref() {
+            None => (
+                vec!["1", "2"],
+                Some("<https://example.com/?max_id=2>; rel=\"next\""),
+            ),
+            Some("2") => (vec!["3", "4"], None),
+            Some(_) => (vec![], None),
+        };
+        let mut header = HeaderMap::new();
+        if let Some(link) = link {
+            header.insert(LINK, HeaderValue::from_static(link));
+        }
+        Ok(Response::new(
+            ids.into_iter().map(|id| Item(id.to_string())).collect(),
+            200,
+            "OK".to_string(),
+            header,
+        ))
+    }
+
+    #[test]
+    fn test_parse_link() {
+        let value = r#"<https://mastodon.example/api/v1/timelines/home?limit=20&max_id=109>; rel="next", <https://mastodon.example/api/v1/timelines/home?limit=20&min_id=110>; rel="prev""#;
+
+        let links = Links::parse(value);
+        assert_eq!(
+            links,
+            Links {
+                next: Some(Cursor {
+                    max_id: Some("109".to_string()),
+                    ..Default::default()
+                }),
+                prev: Some(Cursor {
+                    min_id: Some("110".to_string()),
+                    ..Default::default()
+                }),
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_link_with_offset() {
+        let value = r#"<https://mastodon.example/api/v1/directory?offset=40>; rel="next""#;
+
+        let links = Links::parse(value);
+        assert_eq!(links.next.unwrap().offset, Some(40));
+        assert_eq!(links.prev, None);
+    }
+
+    #[test]
+    fn test_apply_cursor() {
+        let options = AccountFollowersInputOptions {
+            limit: Some(40),
+            max_id: Some("1".to_string()),
+            since_id: None,
+        };
+        let cursor = Cursor {
+            min_id: Some("2".to_string()),
+            ..Default::default()
+        };
+
+        let options = options.with_cursor(&cursor);
+        assert_eq!(options.limit, Some(40));
+        assert_eq!(options.max_id, None);
+        assert_eq!(options.since_id, Some("2".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_paginate_follows_next_link() {
+        let stream = paginate(
+            GetArrayOptions::default(),
+            StreamOptions {
+                until_id: Some("3".to_string()),
+                ..Default::default()
+            },
+            |options: GetArrayOptions| async move { page(&options) },
+        );
+
+        let ids: Vec<String> = stream.map(|item| item.unwrap().0).collect().await;
+        assert_eq!(ids, vec!["1".to_string(), "2".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_paginate_does_not_fetch_after_limit() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let requests = AtomicUsize::new(0);
+        let stream = paginate(
+            GetArrayOptions::default(),
+            StreamOptions {
+                limit: Some(2),
+                ..Default::default()
+            },
+            |options: GetArrayOptions| {
+                requests.fetch_add(1, Ordering::SeqCst);
+                async move { page(&options) }
+            },
+        );
+
+        let ids: Vec<String> = stream.map(|item| item.unwrap().0).collect().await;
+        assert_eq!(ids, vec!["1".to_string(), "2".to_string()]);
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_paginate_follows_offset_link() {
+        let options = GetTrendsInputOptions {
+            limit: Some(2),
+            offset: None,
+        };
+        let stream = paginate(
+            options,
+            StreamOptions::default(),
+            |options: GetTrendsInputOptions| async move {
+                assert_eq!(options.limit, Some(2));
+                let (names, link) = match options.offset {
+                    None => (
+                        vec!["rust", "mastodon"],
+                        Some("<https://example.com/api/v1/trends/tags?limit=2&offset=2>; rel=\"next\""),
+                    ),
+                    Some(2) => (vec!["fediverse"], None),
+                    Some(_) => (vec![], None),
+                };
+                let mut header = HeaderMap::new();
+                if let Some(link) = link {
+                    header.insert(LINK, HeaderValue::from_static(link));
+                }
+                Ok(Response::new(
+                    names
+                        .into_iter()
+                        .map(|name| entities::Tag {
+                            name: name.to_string(),
+                            url: format!("https://example.com/tags/{}", name),
+                            history: None,
+                            following: None,
+                        })
+                        .collect(),
+                    200,
+                    "OK".to_string(),
+                    header,
+                ))
+            },
+        );
+
+        let names: Vec<String> = stream.map(|tag| tag.unwrap().name).collect().await;
+        assert_eq!(names, vec!["rust", "mastodon", "fediverse"]);
+    }
+}
@@ -0,0 +1,79 @@
+use super::{Emoji, Mention, Tag};
+use crate::entities as MegalodonEntities;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct Announcement {
+    id: String,
+    content: String,
+    starts_at: Option<DateTime<Utc>>,
+    ends_at: Option<DateTime<Utc>>,
+    published: bool,
+    all_day: bool,
+    published_at: DateTime<Utc>,
+    updated_at: Option<DateTime<Utc>>,
+    read: Option<bool>,
+    mentions: Vec<Mention>,
+    statuses: Vec<AnnouncementStatus>,
+    tags: Vec<Tag>,
+    emojis: Vec<Emoji>,
+    reactions: Vec<AnnouncementReaction>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct AnnouncementStatus {
+    id: String,
+    url: String,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct AnnouncementReaction {
+    name: String,
+    count: u32,
+    me: Option<bool>,
+    url: Option<String>,
+    static_url: Option<String>,
+}
+
+impl From<AnnouncementStatus> for MegalodonEntities::announcement::AnnouncementStatus {
+    fn from(item: AnnouncementStatus) -> Self {
+        MegalodonEntities::announcement::AnnouncementStatus {
+            id: item.id,
+            url: item.url,
+        }
+    }
+}
+
+impl From<AnnouncementReaction> for MegalodonEntities::announcement::AnnouncementReaction {
+    fn from(item: AnnouncementReaction) -> Self {
+        MegalodonEntities::announcement::AnnouncementReaction {
+            name: item.name,
+            count: item.count,
+            me: item.me,
+            url: item.url,
+            static_url: item.static_url,
+        }
+    }
+}
+
+impl From<Announcement> for MegalodonEntities::Announcement {
+    fn from(item: Announcement) -> Self {
+        MegalodonEntities::Announcement {
+            id: item.id,
+            content: item.content,
+            starts_at: item.starts_at,
+            ends_at: item.ends_at,
+            published: item.published,
+            all_day: item.all_day,
+            published_at: item.published_at,
+            updated_at: item.updated_at,
+            read: item.read,
+            mentions: item.mentions.into_iter().map(|i| i.into()).collect(),
+            statuses: item.statuses.into_iter().map(|i| i.into()).collect(),
+            tags: item.tags.into_iter().map(|i| i.into()).collect(),
+            emojis: item.emojis.into_iter().map(|i| i.into()).collect(),
+            reactions: item.reactions.into_iter().map(|i| i.into()).collect(),
+        }
+    }
+}
@@ -0,0 +1,54 @@
+use super::{Account, Attachment, Card, Emoji};
+use crate::entities as MegalodonEntities;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct Chat {
+    id: String,
+    account: Account,
+    unread: u32,
+    last_message: Option<ChatMessage>,
+    updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct ChatMessage {
+    id: String,
+    chat_id: String,
+    account_id: String,
+    content: Option<String>,
+    created_at: DateTime<Utc>,
+    emojis: Vec<Emoji>,
+    attachment: Option<Attachment>,
+    card: Option<Card>,
+    unread: bool,
+}
+
+impl From<ChatMessage> for MegalodonEntities::ChatMessage {
+    fn from(item: ChatMessage) -> Self {
+        MegalodonEntities::ChatMessage {
+            id: item.id,
+            chat_id: item.chat_id,
+            account_id: item.account_id,
+            content: item.content,
+            created_at: item.created_at,
+            emojis: item.emojis.into_iter().map(|i| i.into()).collect(),
+            attachment: item.attachment.map(|i| i.into()),
+            card: item.card.map(|i| i.into()),
+            unread: item.unread,
+        }
+    }
+}
+
+impl From<Chat> for MegalodonEntities::Chat {
+    fn from(item: Chat) -> Self {
+        MegalodonEntities::Chat {
+            id: item.id,
+            account: item.account.into(),
+            unread: item.unread,
+            last_message: item.last_message.map(|i| i.into()),
+            updated_at: item.updated_at,
+        }
+    }
+}
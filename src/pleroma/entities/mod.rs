@@ -1,8 +1,10 @@
 pub mod account;
 pub mod activity;
+pub mod announcement;
 pub mod application;
 pub mod attachment;
 pub mod card;
+pub mod chat;
 pub mod context;
 pub mod conversation;
 pub mod emoji;
@@ -35,9 +37,11 @@ pub mod urls;
 
 pub use account::Account;
 pub use activity::Activity;
+pub use announcement::Announcement;
 pub use application::Application;
 pub use attachment::Attachment;
 pub use card::Card;
+pub use chat::{Chat, ChatMessage};
 pub use context::Context;
 pub use conversation::Conversation;
 pub use emoji::Emoji;
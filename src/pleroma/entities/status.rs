@@ -34,6 +34,7 @@ pub struct Status {
     pub pinned: Option<bool>,
     pub bookmarked: Option<bool>,
     pub pleroma: PleromaOptions,
+    pub akkoma: Option<AkkomaOptions>,
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -60,6 +61,19 @@ pub struct PleromaOptions {
     pub thread_muted: Option<bool>,
 }
 
+/// Extension of Akkoma, which has the source of MFM content.
+#[derive(Debug, Deserialize, Clone)]
+pub struct AkkomaOptions {
+    pub source: Option<AkkomaSource>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct AkkomaSource {
+    pub content: String,
+    #[serde(rename = "mediaType")]
+    pub media_type: String,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct PleromaContent {
     pub text_plain: String,
@@ -98,6 +112,11 @@ impl Into<MegalodonEntities::Status> for Status {
             reblog: reblog_status,
             content: self.content,
             plain_content: self.pleroma.content.map(|c| c.text_plain),
+            mfm_content: self
+                .akkoma
+                .and_then(|a| a.source)
+                .filter(|s| s.media_type == "text/x.misskeymarkdown")
+                .map(|s| s.content),
             created_at: self.created_at,
             emojis: self.emojis.into_iter().map(|i| i.into()).collect(),
             replies_count: self.replies_count,
@@ -127,6 +146,7 @@ impl Into<MegalodonEntities::Status> for Status {
                 .map(|v| v.into_iter().map(|e| e.into()).collect()),
             quote: quoted,
             bookmarked: self.bookmarked,
+            filtered: None,
         }
     }
 }
@@ -232,4 +252,24 @@ mod tests {
             }
         );
     }
+
+    #[test]
+    fn test_akkoma_source_deserialize() {
+        let text = r#"{"source":{"content":"$[x2 Hello]","mediaType":"text/x.misskeymarkdown"}}"#;
+
+        let r = serde_json::from_str::<AkkomaOptions>(text).unwrap();
+        let source = r.source.unwrap();
+        assert_eq!(source.content, "$[x2 Hello]");
+        assert_eq!(source.media_type, "text/x.misskeymarkdown");
+    }
+
+    #[test]
+    fn test_akkoma_status_keeps_plain_content() {
+        let text = r#"{"id":"1","uri":"https://example.com/1","url":null,"account":{"id":"1","username":"a","acct":"a","display_name":"a","locked":false,"created_at":"2023-01-01T00:00:00Z","followers_count":0,"following_count":0,"statuses_count":0,"note":"","url":"https://example.com/users/a","avatar":"","avatar_static":"","header":"","header_static":"","emojis":[]},"in_reply_to_id":null,"in_reply_to_account_id":null,"reblog":null,"content":"<span class=\"mfm\">Hello</span>","created_at":"2023-01-01T00:00:00Z","emojis":[],"replies_count":0,"reblogs_count":0,"favourites_count":0,"reblogged":null,"favourited":null,"muted":null,"sensitive":false,"spoiler_text":"","visibility":"public","media_attachments":[],"mentions":[],"tags":[],"card":null,"poll":null,"application":null,"language":null,"pinned":null,"bookmarked":null,"pleroma":{"content":{"text/plain":"Hello"},"local":true},"akkoma":{"source":{"content":"$[x2 Hello]","mediaType":"text/x.misskeymarkdown"}}}"#;
+
+        let status: MegalodonEntities::Status =
+            serde_json::from_str::<Status>(text).unwrap().into();
+        assert_eq!(status.plain_content, Some("Hello".to_string()));
+        assert_eq!(status.mfm_content, Some("$[x2 Hello]".to_string()));
+    }
 }
@@ -1,6 +1,6 @@
 //! Pleroma related modules
 
-mod api_client;
+pub(crate) mod api_client;
 pub mod entities;
 mod oauth;
 pub mod pleroma;
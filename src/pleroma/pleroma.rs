@@ -2,24 +2,38 @@ use super::api_client::APIClient;
 use super::entities;
 use super::oauth;
 use super::web_socket::WebSocket;
-use crate::Streaming;
+use crate::capabilities::Capabilities;
+use crate::rate_limit::RetryPolicy;
+use crate::streaming::{MultiplexedStreaming, StreamingOptions};
+use crate::token_manager::{ManagedToken, TokenManager};
 use crate::{
     default, entities as MegalodonEntities, error::Error, megalodon, oauth as MegalodonOAuth,
     response::Response,
 };
+use crate::{error, Streaming, SNS};
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 use oauth2::basic::BasicClient;
 use oauth2::{
-    AuthUrl, ClientId, ClientSecret, CsrfToken, RedirectUrl, ResponseType, Scope, TokenUrl,
+    AuthUrl, ClientId, ClientSecret, CsrfToken, PkceCodeChallenge, RedirectUrl, ResponseType,
+    Scope, TokenUrl,
 };
 use serde_json::Value;
 use sha1::{Digest, Sha1};
 use std::collections::HashMap;
+use std::sync::Arc;
 use tokio::fs::File;
 use tokio_util::codec::{BytesCodec, FramedRead};
 use urlencoding::encode;
 
+/// Authorize URL with `state` and PKCE code verifier.
+#[derive(Debug)]
+struct AuthUrlWithVerifier {
+    url: String,
+    state: String,
+    code_verifier: String,
+}
+
 /// Pleroma API Client which satisfies megalodon trait.
 #[derive(Debug, Clone)]
 pub struct Pleroma {
@@ -27,6 +41,7 @@ pub struct Pleroma {
     base_url: String,
     access_token: Option<String>,
     user_agent: Option<String>,
+    streaming_options: StreamingOptions,
 }
 
 impl Pleroma {
@@ -38,34 +53,101 @@ impl Pleroma {
             base_url,
             access_token,
             user_agent,
+            streaming_options: StreamingOptions::default(),
+        }
+    }
+
+    /// Create a new [`Pleroma`] which sends all requests with the given HTTP client.
+    /// The user agent is used for streaming connections, because the HTTP client has its own one.
+    pub fn with_client(
+        base_url: String,
+        access_token: Option<String>,
+        user_agent: Option<String>,
+        http_client: reqwest::Client,
+    ) -> Self {
+        let client = APIClient::with_client(base_url.clone(), access_token.clone(), http_client);
+        Self {
+            client,
+            base_url,
+            access_token,
+            user_agent,
+            streaming_options: StreamingOptions::default(),
         }
     }
 
+    /// Set a policy to retry failed requests. Requests are not retried when it is `None`.
+    pub fn set_retry_policy(&mut self, retry_policy: Option<RetryPolicy>) {
+        self.client.set_retry_policy(retry_policy);
+    }
+
+    /// Set a manager which refreshes the access token. When it is set, the access token of the manager is used instead.
+    /// Streaming connections use the access token at the time of this call.
+    pub fn set_token_manager(&mut self, token_manager: Option<TokenManager>) {
+        let managed = token_manager.map(|manager| self.managed_token(manager));
+        self.set_managed_token(managed);
+    }
+
+    /// Set the token manager which is already bound with a refresher, so a wrapping client can share it.
+    pub(crate) fn set_managed_token(&mut self, managed: Option<ManagedToken>) {
+        if let Some(managed) = &managed {
+            self.access_token = Some(managed.token_data().access_token);
+        }
+        self.client.set_token_manager(managed);
+    }
+
+    /// Bind the token manager with a copy of this client without any token manager, which refreshes the access token with [`megalodon::Megalodon::refresh_access_token`].
+    pub(crate) fn managed_token(&self, manager: TokenManager) -> ManagedToken {
+        let mut refresher = self.clone();
+        refresher.client.set_token_manager(None);
+        ManagedToken::new(manager, Arc::new(refresher))
+    }
+
+    /// Set options of streaming connections, which are used for streaming objects created after this call.
+    pub fn set_streaming_options(&mut self, streaming_options: StreamingOptions) {
+        self.streaming_options = streaming_options;
+    }
+
+    fn not_supported(&self) -> Error {
+        Error::new_own(
+            "Pleroma does not support".to_string(),
+            error::Kind::NoImplementedError,
+            None,
+            None,
+        )
+    }
+
     async fn generate_auth_url(
         &self,
         client_id: String,
         client_secret: String,
         scope: Vec<&str>,
         redirect_uri: String,
-    ) -> Result<String, Error> {
+    ) -> Result<AuthUrlWithVerifier, Error> {
         let client = BasicClient::new(
             ClientId::new(client_id),
             Some(ClientSecret::new(client_secret)),
             AuthUrl::new(format!("{}{}", self.base_url, "/oauth/authorize").to_string())?,
             Some(TokenUrl::new(
-                format!("{}{}", self.base_url, "/oaut/token").to_string(),
+                format!("{}{}", self.base_url, "/oauth/token").to_string(),
             )?),
         )
         .set_redirect_uri(RedirectUrl::new(redirect_uri)?);
 
         let scopes: Vec<Scope> = scope.iter().map(|s| Scope::new(s.to_string())).collect();
 
-        let (auth_url, _) = client
+        let (pkce_challenge, pkce_verifier) = PkceCodeChallenge::new_random_sha256();
+
+        let (auth_url, csrf_token) = client
             .authorize_url(CsrfToken::new_random)
             .add_scopes(scopes)
             .set_response_type(&ResponseType::new("code".to_string()))
+            .set_pkce_challenge(pkce_challenge)
             .url();
-        Ok(auth_url.to_string())
+        Ok(AuthUrlWithVerifier {
+            url: auth_url.to_string(),
+            state: csrf_token.secret().to_string(),
+            code_verifier: pkce_verifier.secret().to_string(),
+        })
     }
 }
 
@@ -82,7 +164,7 @@ impl megalodon::Megalodon for Pleroma {
         }
 
         let mut app = self.create_app(client_name, options).await?;
-        let url = self
+        let auth = self
             .generate_auth_url(
                 app.client_id.clone(),
                 app.client_secret.clone(),
@@ -90,7 +172,9 @@ impl megalodon::Megalodon for Pleroma {
                 app.redirect_uri.clone(),
             )
             .await?;
-        app.url = Some(url);
+        app.url = Some(auth.url);
+        app.state = Some(auth.state);
+        app.code_verifier = Some(auth.code_verifier);
         Ok(app)
     }
 
@@ -129,12 +213,16 @@ impl megalodon::Megalodon for Pleroma {
         client_secret: String,
         code: String,
         redirect_uri: String,
+        code_verifier: Option<String>,
     ) -> Result<MegalodonOAuth::TokenData, Error> {
         let mut params = HashMap::<&str, Value>::new();
         params.insert("client_id", Value::String(client_id));
         params.insert("client_secret", Value::String(client_secret));
         params.insert("code", Value::String(code));
         params.insert("redirect_uri", Value::String(redirect_uri));
+        if let Some(code_verifier) = code_verifier {
+            params.insert("code_verifier", Value::String(code_verifier));
+        }
         params.insert(
             "grant_type",
             Value::String("authorization_code".to_string()),
@@ -157,9 +245,30 @@ impl megalodon::Megalodon for Pleroma {
         params.insert("client_id", Value::String(client_id));
         params.insert("client_secret", Value::String(client_secret));
         params.insert("refresh_token", Value::String(refresh_token));
+        params.insert("grant_type", Value::String("refresh_token".to_string()));
+
+        let res = self
+            .client
+            .post::<oauth::TokenDataFromServer>("/oauth/token", &params, None)
+            .await?;
+        Ok(res.json.into())
+    }
+
+    async fn fetch_app_token(
+        &self,
+        client_id: String,
+        client_secret: String,
+        scopes: Vec<String>,
+    ) -> Result<MegalodonOAuth::TokenData, Error> {
+        let mut params = HashMap::<&str, Value>::new();
+        params.insert("client_id", Value::String(client_id));
+        params.insert("client_secret", Value::String(client_secret));
+        if !scopes.is_empty() {
+            params.insert("scope", Value::String(scopes.join(" ")));
+        }
         params.insert(
             "grant_type",
-            Value::String("authorization_code".to_string()),
+            Value::String("client_credentials".to_string()),
         );
 
         let res = self
@@ -1066,6 +1175,100 @@ impl megalodon::Megalodon for Pleroma {
         Ok(res)
     }
 
+    async fn get_filters_v2(&self) -> Result<Response<Vec<MegalodonEntities::FilterV2>>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn get_filter_v2(
+        &self,
+        _id: String,
+    ) -> Result<Response<MegalodonEntities::FilterV2>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn create_filter_v2(
+        &self,
+        _title: String,
+        _context: Vec<MegalodonEntities::filter::FilterContext>,
+        _options: Option<&megalodon::FilterV2InputOptions>,
+    ) -> Result<Response<MegalodonEntities::FilterV2>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn update_filter_v2(
+        &self,
+        _id: String,
+        _options: Option<&megalodon::UpdateFilterV2InputOptions>,
+    ) -> Result<Response<MegalodonEntities::FilterV2>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn delete_filter_v2(&self, _id: String) -> Result<Response<()>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn get_filter_keywords(
+        &self,
+        _filter_id: String,
+    ) -> Result<Response<Vec<MegalodonEntities::FilterKeyword>>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn add_filter_keyword(
+        &self,
+        _filter_id: String,
+        _keyword: String,
+        _whole_word: Option<bool>,
+    ) -> Result<Response<MegalodonEntities::FilterKeyword>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn get_filter_keyword(
+        &self,
+        _id: String,
+    ) -> Result<Response<MegalodonEntities::FilterKeyword>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn update_filter_keyword(
+        &self,
+        _id: String,
+        _keyword: String,
+        _whole_word: Option<bool>,
+    ) -> Result<Response<MegalodonEntities::FilterKeyword>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn remove_filter_keyword(&self, _id: String) -> Result<Response<()>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn get_filter_statuses(
+        &self,
+        _filter_id: String,
+    ) -> Result<Response<Vec<MegalodonEntities::FilterStatus>>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn add_filter_status(
+        &self,
+        _filter_id: String,
+        _status_id: String,
+    ) -> Result<Response<MegalodonEntities::FilterStatus>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn get_filter_status(
+        &self,
+        _id: String,
+    ) -> Result<Response<MegalodonEntities::FilterStatus>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn remove_filter_status(&self, _id: String) -> Result<Response<()>, Error> {
+        Err(self.not_supported())
+    }
+
     async fn report(
         &self,
         account_id: String,
@@ -2080,6 +2283,13 @@ impl megalodon::Megalodon for Pleroma {
         ))
     }
 
+    async fn get_bubble_timeline(
+        &self,
+        _options: Option<&megalodon::GetLocalTimelineInputOptions>,
+    ) -> Result<Response<Vec<MegalodonEntities::Status>>, Error> {
+        Err(self.not_supported())
+    }
+
     async fn get_tag_timeline(
         &self,
         hashtag: String,
@@ -2707,6 +2917,30 @@ impl megalodon::Megalodon for Pleroma {
         ))
     }
 
+    async fn get_instance_v2(&self) -> Result<Response<MegalodonEntities::InstanceV2>, Error> {
+        // Synthesize it from v1, which has max_toot_chars and poll_limits.
+        let res = self.get_instance().await?;
+
+        Ok(Response::<MegalodonEntities::InstanceV2>::new(
+            res.json.into(),
+            res.status,
+            res.status_text,
+            res.header,
+        ))
+    }
+
+    async fn capabilities(&self) -> Result<Capabilities, Error> {
+        let res = self
+            .client
+            .get::<entities::Instance>("/api/v1/instance", None)
+            .await?;
+        Ok(Capabilities::new(
+            &SNS::Pleroma,
+            &res.json.version,
+            &res.json.pleroma.metadata.features,
+        ))
+    }
+
     async fn get_instance_peers(&self) -> Result<Response<Vec<String>>, Error> {
         let res = self
             .client
@@ -2756,6 +2990,51 @@ impl megalodon::Megalodon for Pleroma {
         ))
     }
 
+    async fn get_trend_tags(
+        &self,
+        options: Option<&megalodon::GetTrendsInputOptions>,
+    ) -> Result<Response<Vec<MegalodonEntities::Tag>>, Error> {
+        // Pleroma only has the legacy endpoint, which does not support offset.
+        let (limit, offset) = options.map_or((None, 0), |o| (o.limit, o.offset.unwrap_or(0)));
+        let mut params = Vec::<String>::new();
+        if let Some(limit) = limit {
+            params.push(format!("limit={}", limit as u64 + offset));
+        }
+        let mut path = "/api/v1/trends".to_string();
+        if !params.is_empty() {
+            path = path + "?" + params.join("&").as_str();
+        }
+        let res = self
+            .client
+            .get::<Vec<entities::Tag>>(path.as_str(), None)
+            .await?;
+
+        Ok(Response::<Vec<MegalodonEntities::Tag>>::new(
+            res.json
+                .into_iter()
+                .skip(offset as usize)
+                .map(|j| j.into())
+                .collect(),
+            res.status,
+            res.status_text,
+            res.header,
+        ))
+    }
+
+    async fn get_trend_statuses(
+        &self,
+        _options: Option<&megalodon::GetTrendsInputOptions>,
+    ) -> Result<Response<Vec<MegalodonEntities::Status>>, Error> {
+        Err(self.not_supported())
+    }
+
+    async fn get_trend_links(
+        &self,
+        _options: Option<&megalodon::GetTrendsInputOptions>,
+    ) -> Result<Response<Vec<MegalodonEntities::TrendLink>>, Error> {
+        Err(self.not_supported())
+    }
+
     async fn get_instance_directory(
         &self,
         options: Option<&megalodon::GetInstanceDirectoryInputOptions>,
@@ -2808,6 +3087,82 @@ impl megalodon::Megalodon for Pleroma {
         ))
     }
 
+    async fn get_announcements(
+        &self,
+    ) -> Result<Response<Vec<MegalodonEntities::Announcement>>, Error> {
+        let res = self
+            .client
+            .get::<Vec<entities::Announcement>>("/api/v1/announcements", None)
+            .await?;
+
+        Ok(Response::<Vec<MegalodonEntities::Announcement>>::new(
+            res.json.into_iter().map(|j| j.into()).collect(),
+            res.status,
+            res.status_text,
+            res.header,
+        ))
+    }
+
+    async fn dismiss_announcement(&self, id: String) -> Result<Response<()>, Error> {
+        let params = HashMap::<&str, Value>::new();
+        let res = self
+            .client
+            .post::<()>(
+                format!("/api/v1/announcements/{}/dismiss", id).as_str(),
+                &params,
+                None,
+            )
+            .await?;
+
+        Ok(res)
+    }
+
+    async fn add_announcement_reaction(
+        &self,
+        id: String,
+        name: String,
+    ) -> Result<Response<()>, Error> {
+        let params = HashMap::<&str, Value>::new();
+        let res = self
+            .client
+            .put::<()>(
+                format!(
+                    "/api/v1/announcements/{}/reactions/{}",
+                    id,
+                    encode(name.as_str())
+                )
+                .as_str(),
+                &params,
+                None,
+            )
+            .await?;
+
+        Ok(res)
+    }
+
+    async fn remove_announcement_reaction(
+        &self,
+        id: String,
+        name: String,
+    ) -> Result<Response<()>, Error> {
+        let params = HashMap::<&str, Value>::new();
+        let res = self
+            .client
+            .delete::<()>(
+                format!(
+                    "/api/v1/announcements/{}/reactions/{}",
+                    id,
+                    encode(name.as_str())
+                )
+                .as_str(),
+                &params,
+                None,
+            )
+            .await?;
+
+        Ok(res)
+    }
+
     async fn create_emoji_reaction(
         &self,
         id: String,
@@ -2914,6 +3269,7 @@ impl megalodon::Megalodon for Pleroma {
             Some(params),
             self.access_token.clone(),
             self.user_agent.clone(),
+            self.streaming_options.clone(),
         );
 
         Box::new(c)
@@ -2927,6 +3283,7 @@ impl megalodon::Megalodon for Pleroma {
             Some(params),
             self.access_token.clone(),
             self.user_agent.clone(),
+            self.streaming_options.clone(),
         );
 
         Box::new(c)
@@ -2940,6 +3297,7 @@ impl megalodon::Megalodon for Pleroma {
             Some(params),
             self.access_token.clone(),
             self.user_agent.clone(),
+            self.streaming_options.clone(),
         );
 
         Box::new(c)
@@ -2953,6 +3311,7 @@ impl megalodon::Megalodon for Pleroma {
             Some(params),
             self.access_token.clone(),
             self.user_agent.clone(),
+            self.streaming_options.clone(),
         );
 
         Box::new(c)
@@ -2970,6 +3329,7 @@ impl megalodon::Megalodon for Pleroma {
             Some(params),
             self.access_token.clone(),
             self.user_agent.clone(),
+            self.streaming_options.clone(),
         );
 
         Box::new(c)
@@ -2987,8 +3347,118 @@ impl megalodon::Megalodon for Pleroma {
             Some(params),
             self.access_token.clone(),
             self.user_agent.clone(),
+            self.streaming_options.clone(),
+        );
+
+        Box::new(c)
+    }
+
+    fn multiplexed_streaming(
+        &self,
+        streaming_url: String,
+    ) -> Box<dyn MultiplexedStreaming + Send + Sync> {
+        let c = WebSocket::new(
+            streaming_url + "/api/v1/streaming",
+            String::new(),
+            None,
+            self.access_token.clone(),
+            self.user_agent.clone(),
+            self.streaming_options.clone(),
         );
 
         Box::new(c)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::megalodon::Megalodon;
+    use crate::test_server::StubServer;
+    use oauth2::PkceCodeVerifier;
+
+    const APP: &str = r#"{"id":"1","name":"megalodon","website":null,"redirect_uri":"urn:ietf:wg:oauth:2.0:oob","client_id":"client_id","client_secret":"client_secret"}"#;
+
+    const TOKEN: &str =
+        r#"{"access_token":"access","token_type":"Bearer","scope":"read write","created_at":1000}"#;
+
+    #[tokio::test]
+    async fn test_register_app_generates_pkce_auth_url() {
+        let server = StubServer::start().await;
+        server.route("POST", "/api/v1/apps", 200, APP);
+        let client = Pleroma::new(server.base_url.clone(), None, None);
+
+        let app = client
+            .register_app(
+                "megalodon".to_string(),
+                &megalodon::AppInputOptions::default(),
+            )
+            .await
+            .unwrap();
+        let url = url::Url::parse(&app.url.unwrap()).unwrap();
+        assert_eq!(url.path(), "/oauth/authorize");
+        let query: HashMap<String, String> = url.query_pairs().into_owned().collect();
+        assert_eq!(query["client_id"], "client_id");
+        assert_eq!(query["response_type"], "code");
+        assert_eq!(query["code_challenge_method"], "S256");
+        assert_eq!(query["state"], app.state.unwrap());
+
+        let verifier = PkceCodeVerifier::new(app.code_verifier.unwrap());
+        let challenge = PkceCodeChallenge::from_code_verifier_sha256(&verifier);
+        assert_eq!(query["code_challenge"], challenge.as_str());
+    }
+
+    #[tokio::test]
+    async fn test_fetch_access_token_posts_code_verifier() {
+        let server = StubServer::start().await;
+        server.route("POST", "/oauth/token", 200, TOKEN);
+        let client = Pleroma::new(server.base_url.clone(), None, None);
+
+        let token = client
+            .fetch_access_token(
+                "client_id".to_string(),
+                "client_secret".to_string(),
+                "code".to_string(),
+                "urn:ietf:wg:oauth:2.0:oob".to_string(),
+                Some("verifier".to_string()),
+            )
+            .await
+            .unwrap();
+        assert_eq!(token.access_token, "access");
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 1);
+        let params = requests[0].json();
+        assert_eq!(params["grant_type"], "authorization_code");
+        assert_eq!(params["code"], "code");
+        assert_eq!(params["code_verifier"], "verifier");
+    }
+
+    #[tokio::test]
+    async fn test_fetch_app_token_uses_client_credentials() {
+        let server = StubServer::start().await;
+        server.route("POST", "/oauth/token", 200, TOKEN);
+        let client = Pleroma::new(server.base_url.clone(), None, None);
+
+        let token = client
+            .fetch_app_token(
+                "client_id".to_string(),
+                "client_secret".to_string(),
+                vec!["read".to_string(), "write".to_string()],
+            )
+            .await
+            .unwrap();
+        assert_eq!(token.access_token, "access");
+        assert_eq!(token.scope, "read write");
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 1);
+        assert!(!requests[0].headers.contains_key("authorization"));
+        let params = requests[0].json();
+        assert_eq!(params["grant_type"], "client_credentials");
+        assert_eq!(params["client_id"], "client_id");
+        assert_eq!(params["client_secret"], "client_secret");
+        assert_eq!(params["scope"], "read write");
+        assert!(params.get("code").is_none());
+    }
+}
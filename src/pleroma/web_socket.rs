@@ -1,13 +1,16 @@
 use std::fmt;
-use std::thread;
-use std::time::Duration;
 
 use super::entities;
 use crate::default::DEFAULT_UA;
 use crate::error::{Error, Kind};
-use crate::streaming::{Message, Streaming};
+use crate::streaming::{
+    self, Connection, ConnectionEnd, Message, MessageSender, MessageStream, MultiplexedHandle,
+    MultiplexedStreaming, Multiplexer, StreamType, Streaming, StreamingHandle, StreamingOptions,
+    TaggedMessage,
+};
 use async_trait::async_trait;
 use futures_util::{SinkExt, StreamExt};
+use serde::de::DeserializeOwned;
 use serde::Deserialize;
 use tokio_tungstenite::tungstenite::client::IntoClientRequest;
 use tokio_tungstenite::{
@@ -16,9 +19,6 @@ use tokio_tungstenite::{
 };
 use url::Url;
 
-const RECONNECT_INTERVAL: u64 = 5000;
-const READ_MESSAGE_TIMEOUT_SECONDS: u64 = 60;
-
 #[derive(Debug, Clone)]
 pub struct WebSocket {
     url: String,
@@ -26,12 +26,29 @@ pub struct WebSocket {
     params: Option<Vec<String>>,
     access_token: Option<String>,
     user_agent: String,
+    options: StreamingOptions,
 }
 
 #[derive(Deserialize)]
 struct RawMessage {
     event: String,
+    #[serde(default)]
     payload: String,
+    #[serde(default)]
+    stream: Option<Vec<String>>,
+}
+
+#[derive(Deserialize)]
+struct RawAnnouncementReaction {
+    announcement_id: String,
+    name: String,
+    count: u32,
+}
+
+#[derive(Deserialize)]
+struct RawRespond {
+    r#type: String,
+    result: String,
 }
 
 impl WebSocket {
@@ -41,6 +58,7 @@ impl WebSocket {
         params: Option<Vec<String>>,
         access_token: Option<String>,
         user_agent: Option<String>,
+        options: StreamingOptions,
     ) -> Self {
         let ua: String;
         match user_agent {
@@ -53,72 +71,69 @@ impl WebSocket {
             params,
             access_token,
             user_agent: ua,
+            options,
         }
     }
 
-    fn parse(&self, message: WebSocketMessage) -> Result<Message, Error> {
+    fn parse(&self, message: WebSocketMessage) -> Result<TaggedMessage, Error> {
         if message.is_ping() || message.is_pong() {
-            Ok(Message::Heartbeat())
+            Ok(TaggedMessage {
+                stream: Vec::new(),
+                message: Message::Heartbeat(),
+            })
         } else if message.is_text() {
             let text = message.to_text()?;
             let mes = serde_json::from_str::<RawMessage>(text)?;
-            match &*mes.event {
-                "update" => {
-                    let res =
-                        serde_json::from_str::<entities::Status>(&mes.payload).map_err(|e| {
-                            log::error!(
-                                "failed to parse status: {}\n{}",
-                                e.to_string(),
-                                &mes.payload
-                            );
-                            e
-                        })?;
-                    Ok(Message::Update(res.into()))
-                }
-                "notification" => {
-                    let res = serde_json::from_str::<entities::Notification>(&mes.payload)
-                        .map_err(|e| {
-                            log::error!(
-                                "failed to parse notification: {}\n{}",
-                                e.to_string(),
-                                &mes.payload
-                            );
-                            e
-                        })?;
-                    Ok(Message::Notification(res.into()))
-                }
-                "conversation" => {
-                    let res = serde_json::from_str::<entities::Conversation>(&mes.payload)
-                        .map_err(|e| {
-                            log::error!(
-                                "failed to parse conversation: {}\n{}",
-                                e.to_string(),
-                                &mes.payload
-                            );
-                            e
-                        })?;
-                    Ok(Message::Conversation(res.into()))
+            let stream = mes
+                .stream
+                .as_deref()
+                .map(StreamType::from_stream)
+                .unwrap_or_default();
+            let message = match &*mes.event {
+                "update" => Message::Update(
+                    parse_payload::<entities::Status>("status", &mes.payload)?.into(),
+                ),
+                "notification" => Message::Notification(
+                    parse_payload::<entities::Notification>("notification", &mes.payload)?.into(),
+                ),
+                "conversation" => Message::Conversation(
+                    parse_payload::<entities::Conversation>("conversation", &mes.payload)?.into(),
+                ),
+                "delete" => Message::Delete(mes.payload),
+                "status.update" => Message::StatusUpdate(
+                    parse_payload::<entities::Status>("status", &mes.payload)?.into(),
+                ),
+                "announcement" => Message::Announcement(
+                    parse_payload::<entities::Announcement>("announcement", &mes.payload)?.into(),
+                ),
+                "announcement.reaction" => {
+                    let res = parse_payload::<RawAnnouncementReaction>(
+                        "announcement reaction",
+                        &mes.payload,
+                    )?;
+                    Message::AnnouncementReaction {
+                        announcement_id: res.announcement_id,
+                        name: res.name,
+                        count: res.count,
+                    }
                 }
-                "delete" => Ok(Message::Delete(mes.payload)),
-                "status.update" => {
-                    let res =
-                        serde_json::from_str::<entities::Status>(&mes.payload).map_err(|e| {
-                            log::error!(
-                                "failed to parse status: {}\n{}",
-                                e.to_string(),
-                                &mes.payload
-                            );
-                            e
-                        })?;
-                    Ok(Message::StatusUpdate(res.into()))
+                "announcement.delete" => Message::AnnouncementDelete(mes.payload),
+                "pleroma:chat_update" => Message::ChatUpdate(
+                    parse_payload::<entities::Chat>("chat", &mes.payload)?.into(),
+                ),
+                "pleroma:respond" => {
+                    let res = parse_payload::<RawRespond>("respond", &mes.payload)?;
+                    Message::Respond {
+                        r#type: res.r#type,
+                        result: res.result,
+                    }
                 }
-                event => Err(Error::new_own(
-                    format!("Unknown event is received: {}", event),
-                    Kind::ParseError,
-                    None,
-                    None,
-                )),
-            }
+                event => Message::Unknown {
+                    event: event.to_string(),
+                    payload: mes.payload,
+                },
+            };
+            Ok(TaggedMessage { stream, message })
         } else {
             Err(Error::new_own(
                 String::from("Receiving message is not ping, pong or text"),
@@ -129,31 +144,11 @@ impl WebSocket {
         }
     }
 
-    async fn connect(&self, url: &str, callback: Box<dyn Fn(Message) + Send + Sync>) {
-        loop {
-            match self.do_connect(url, &callback).await {
-                Ok(()) => {
-                    log::info!("connection for {} is  closed", url);
-                    return;
-                }
-                Err(err) => match err.kind {
-                    InnerKind::ConnectionError
-                    | InnerKind::SocketReadError
-                    | InnerKind::UnusualSocketCloseError
-                    | InnerKind::TimeoutError => {
-                        thread::sleep(Duration::from_millis(RECONNECT_INTERVAL));
-                        log::info!("Reconnecting to {}", url);
-                        continue;
-                    }
-                },
-            }
-        }
-    }
-
-    async fn do_connect(
+    async fn do_connect<T>(
         &self,
         url: &str,
-        callback: &Box<dyn Fn(Message) + Send + Sync>,
+        sender: &mut MessageSender<T>,
+        multiplexer: &mut Option<Multiplexer>,
     ) -> Result<(), InnerError> {
         let mut req = Url::parse(url)
             .unwrap()
@@ -175,20 +170,65 @@ impl WebSocket {
         for (ref header, _value) in response.headers() {
             log::debug!("* {}", header);
         }
+        if !sender.send(Message::Connected()).await {
+            let _ = socket.close(None).await;
+            return Ok(());
+        }
+        // Subscriptions are lost when the socket is closed, so subscribe all streams again.
+        if let Some(multiplexer) = multiplexer.as_mut() {
+            for command in multiplexer.subscribe_commands() {
+                socket
+                    .send(WebSocketMessage::Text(command.to_json().to_string()))
+                    .await
+                    .map_err(|e| {
+                        log::error!("Failed to subscribe: {}", e);
+                        InnerError::new(InnerKind::SocketReadError)
+                    })?;
+            }
+        }
+
+        let mut ping = self
+            .options
+            .ping_interval
+            .map(|period| tokio::time::interval_at(tokio::time::Instant::now() + period, period));
 
         loop {
-            let res = tokio::time::timeout(
-                Duration::from_secs(READ_MESSAGE_TIMEOUT_SECONDS),
-                socket.next(),
-            )
-            .await
-            .map_err(|e| {
-                log::error!("Timeout reading message: {}", e);
-                InnerError::new(InnerKind::TimeoutError)
-            })?;
+            let res = tokio::select! {
+                res = tokio::time::timeout(self.options.read_timeout, socket.next()) => res.map_err(|e| {
+                    log::error!("Timeout reading message: {}", e);
+                    InnerError::new(InnerKind::TimeoutError)
+                })?,
+                _ = async { ping.as_mut().unwrap().tick().await }, if ping.is_some() => {
+                    socket
+                        .send(WebSocketMessage::Ping(Vec::<u8>::new()))
+                        .await
+                        .map_err(|e| {
+                            log::error!("Failed to send ping: {}", e);
+                            InnerError::new(InnerKind::SocketReadError)
+                        })?;
+                    continue;
+                }
+                command = async { multiplexer.as_mut().unwrap().next_command().await }, if multiplexer.is_some() => {
+                    socket
+                        .send(WebSocketMessage::Text(command.to_json().to_string()))
+                        .await
+                        .map_err(|e| {
+                            log::error!("Failed to change subscription: {}", e);
+                            InnerError::new(InnerKind::SocketReadError)
+                        })?;
+                    continue;
+                }
+                _ = sender.closed() => {
+                    let _ = socket.close(None).await.map_err(|e| {
+                        log::error!("{:#?}", e);
+                        e
+                    });
+                    return Ok(());
+                }
+            };
             let Some(r) = res else {
-                log::warn!("Response is empty");
-                continue;
+                log::warn!("Connection to {} is lost", url);
+                return Err(InnerError::new(InnerKind::SocketReadError));
             };
             let msg = r.map_err(|e| {
                 log::error!("Failed to read message: {}", e);
@@ -218,7 +258,13 @@ impl WebSocket {
             }
             match self.parse(msg) {
                 Ok(message) => {
-                    callback(message);
+                    if !sender.send_tagged(message).await {
+                        let _ = socket.close(None).await.map_err(|e| {
+                            log::error!("{:#?}", e);
+                            e
+                        });
+                        return Ok(());
+                    }
                 }
                 Err(err) => {
                     log::warn!("{}", err);
@@ -228,9 +274,29 @@ impl WebSocket {
     }
 }
 
+#[async_trait]
+impl<T: Send> Connection<T> for WebSocket {
+    async fn connect_once(
+        &self,
+        url: &str,
+        sender: &mut MessageSender<T>,
+        multiplexer: &mut Option<Multiplexer>,
+    ) -> ConnectionEnd {
+        match self.do_connect(url, sender, multiplexer).await {
+            Ok(()) => ConnectionEnd::Closed,
+            Err(err) => match err.kind {
+                InnerKind::ConnectionError => ConnectionEnd::Failed,
+                InnerKind::SocketReadError
+                | InnerKind::UnusualSocketCloseError
+                | InnerKind::TimeoutError => ConnectionEnd::Lost,
+            },
+        }
+    }
+}
+
 #[async_trait]
 impl Streaming for WebSocket {
-    async fn listen(&self, callback: Box<dyn Fn(Message) + Send + Sync>) {
+    fn subscribe(&self) -> (MessageStream, StreamingHandle) {
         let mut parameter = Vec::<String>::from([format!("stream={}", self.stream)]);
         if let Some(access_token) = &self.access_token {
             parameter.push(format!("access_token={}", access_token));
@@ -241,10 +307,42 @@ impl Streaming for WebSocket {
         let mut url = self.url.clone();
         url = url + "?" + parameter.join("&").as_str();
 
-        self.connect(url.as_str(), callback).await;
+        let (sender, messages, handle) = streaming::channel();
+        let ws = self.clone();
+        tokio::spawn(async move {
+            streaming::keep_connected(&ws, url.as_str(), &ws.options, sender, None).await;
+        });
+        (messages, handle)
+    }
+}
+
+impl MultiplexedStreaming for WebSocket {
+    fn connect(
+        &self,
+        streams: Vec<StreamType>,
+    ) -> (MessageStream<TaggedMessage>, MultiplexedHandle) {
+        let mut url = self.url.clone();
+        if let Some(access_token) = &self.access_token {
+            url = url + "?access_token=" + access_token.as_str();
+        }
+
+        let (sender, multiplexer, messages, handle) = streaming::multiplexed_channel(streams);
+        let ws = self.clone();
+        tokio::spawn(async move {
+            streaming::keep_connected(&ws, url.as_str(), &ws.options, sender, Some(multiplexer))
+                .await;
+        });
+        (messages, handle)
     }
 }
 
+fn parse_payload<T: DeserializeOwned>(name: &str, payload: &str) -> serde_json::Result<T> {
+    serde_json::from_str::<T>(payload).map_err(|e| {
+        log::error!("failed to parse {}: {}\n{}", name, e, payload);
+        e
+    })
+}
+
 #[derive(thiserror::Error)]
 #[error("{kind}")]
 struct InnerError {
@@ -277,3 +375,95 @@ impl fmt::Debug for InnerError {
         builder.finish()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(event: &str, payload: &str) -> TaggedMessage {
+        let ws = WebSocket::new(
+            "wss://pleroma.example/api/v1/streaming".to_string(),
+            "user".to_string(),
+            None,
+            None,
+            None,
+            StreamingOptions::default(),
+        );
+        let text = serde_json::json!({"stream": ["user"], "event": event, "payload": payload});
+        ws.parse(WebSocketMessage::Text(text.to_string())).unwrap()
+    }
+
+    #[test]
+    fn test_parse_announcement() {
+        let payload = r#"{"id":"AbC","content":"<p>Maintenance</p>","starts_at":null,"ends_at":null,"all_day":false,"published":true,"published_at":"2023-01-05T12:00:00.000Z","updated_at":"2023-01-05T12:00:00.000Z","read":false,"mentions":[],"statuses":[],"tags":[],"emojis":[],"reactions":[]}"#;
+        let message = parse("announcement", payload);
+        assert_eq!(message.stream, vec![StreamType::User]);
+        match message.message {
+            Message::Announcement(announcement) => assert_eq!(announcement.id, "AbC"),
+            message => panic!("unexpected message: {:?}", message),
+        }
+
+        let payload = r#"{"name":"👍","count":1,"announcement_id":"AbC"}"#;
+        match parse("announcement.reaction", payload).message {
+            Message::AnnouncementReaction {
+                announcement_id,
+                name,
+                count,
+            } => {
+                assert_eq!(announcement_id, "AbC");
+                assert_eq!(name, "👍");
+                assert_eq!(count, 1);
+            }
+            message => panic!("unexpected message: {:?}", message),
+        }
+
+        match parse("announcement.delete", "AbC").message {
+            Message::AnnouncementDelete(id) => assert_eq!(id, "AbC"),
+            message => panic!("unexpected message: {:?}", message),
+        }
+    }
+
+    #[test]
+    fn test_parse_chat_update() {
+        let payload = r#"{"id":"9","account":{"id":"AbD","username":"bob","acct":"bob@remote.example","display_name":"Bob","locked":false,"created_at":"2023-01-01T00:00:00.000Z","followers_count":0,"following_count":0,"statuses_count":0,"note":"","url":"https://remote.example/users/bob","avatar":"","avatar_static":"","header":"","header_static":"","emojis":[]},"unread":2,"last_message":{"id":"10","chat_id":"9","account_id":"AbD","content":"Hello","created_at":"2023-01-05T12:00:00.000Z","emojis":[],"attachment":null,"card":null,"unread":true},"updated_at":"2023-01-05T12:00:00.000Z"}"#;
+        match parse("pleroma:chat_update", payload).message {
+            Message::ChatUpdate(chat) => {
+                assert_eq!(chat.id, "9");
+                assert_eq!(chat.unread, 2);
+                assert_eq!(chat.account.acct, "bob@remote.example");
+                assert_eq!(
+                    chat.last_message.unwrap().content,
+                    Some("Hello".to_string())
+                );
+            }
+            message => panic!("unexpected message: {:?}", message),
+        }
+    }
+
+    #[test]
+    fn test_parse_respond() {
+        match parse(
+            "pleroma:respond",
+            r#"{"type":"subscribe","result":"success"}"#,
+        )
+        .message
+        {
+            Message::Respond { r#type, result } => {
+                assert_eq!(r#type, "subscribe");
+                assert_eq!(result, "success");
+            }
+            message => panic!("unexpected message: {:?}", message),
+        }
+    }
+
+    #[test]
+    fn test_parse_unknown_event() {
+        match parse("filters_changed", "").message {
+            Message::Unknown { event, payload } => {
+                assert_eq!(event, "filters_changed");
+                assert_eq!(payload, "");
+            }
+            message => panic!("unexpected message: {:?}", message),
+        }
+    }
+}
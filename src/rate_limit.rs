@@ -0,0 +1,172 @@
+//! Rate limit and retry modules
+use crate::error::{Error, Kind};
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use rand::Rng;
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use std::time::Duration;
+
+/// Rate limit status which is parsed from `X-RateLimit-*` headers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RateLimit {
+    /// Number of requests permitted per time period.
+    pub limit: u32,
+    /// Number of requests you can still make.
+    pub remaining: u32,
+    /// Timestamp when your rate limit will reset.
+    pub reset: Option<DateTime<Utc>>,
+}
+
+impl RateLimit {
+    /// Parse rate limit headers. Returns `None` if the server does not send them.
+    pub fn from_headers(header: &HeaderMap) -> Option<Self> {
+        let limit = header_str(header, "x-ratelimit-limit")?
+            .parse::<u32>()
+            .ok()?;
+        let remaining = header_str(header, "x-ratelimit-remaining")?
+            .parse::<u32>()
+            .ok()?;
+        let reset = header_str(header, "x-ratelimit-reset").and_then(parse_reset);
+        Some(Self {
+            limit,
+            remaining,
+            reset,
+        })
+    }
+}
+
+/// Get the date when the client can send requests again, from `Retry-After` or `X-RateLimit-Reset` header.
+pub fn reset_at(header: &HeaderMap) -> Option<DateTime<Utc>> {
+    if let Some(retry_after) = header.get(RETRY_AFTER).and_then(|v| v.to_str().ok()) {
+        if let Ok(seconds) = retry_after.trim().parse::<i64>() {
+            return Some(Utc::now() + ChronoDuration::seconds(seconds));
+        }
+        if let Ok(date) = DateTime::parse_from_rfc2822(retry_after.trim()) {
+            return Some(date.with_timezone(&Utc));
+        }
+    }
+    header_str(header, "x-ratelimit-reset").and_then(parse_reset)
+}
+
+fn header_str<'a>(header: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    header.get(name).and_then(|v| v.to_str().ok())
+}
+
+fn parse_reset(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value.trim())
+        .ok()
+        .map(|date| date.with_timezone(&Utc))
+}
+
+/// Policy to retry failed requests.
+///
+/// Rate limited requests are retried after the reset time for all methods.
+/// Server errors (5xx) and connection errors are retried with exponential backoff and jitter, only for idempotent methods.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Maximum number of retries.
+    pub max_retries: u32,
+    /// Backoff before the first retry. It is doubled for each retry.
+    pub initial_backoff: Duration,
+    /// Maximum delay before a retry. When the rate limit resets later than this, the request is not retried.
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(500),
+            max_delay: Duration::from_secs(60),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Get the delay before the next retry. Returns `None` if the request should not be retried.
+    pub fn retry_delay(&self, attempt: u32, err: &Error, idempotent: bool) -> Option<Duration> {
+        if attempt >= self.max_retries {
+            return None;
+        }
+        match err {
+            Error::OwnError(own) => match own.kind {
+                Kind::RateLimited { reset_at } => {
+                    let Some(reset_at) = reset_at else {
+                        return Some(self.backoff(attempt));
+                    };
+                    let delay = (reset_at - Utc::now()).to_std().unwrap_or(Duration::ZERO);
+                    if delay > self.max_delay {
+                        None
+                    } else {
+                        Some(delay)
+                    }
+                }
+                _ => match own.status {
+                    Some(status) if idempotent && status >= 500 => Some(self.backoff(attempt)),
+                    _ => None,
+                },
+            },
+            Error::RequestError(err) if idempotent && (err.is_connect() || err.is_timeout()) => {
+                Some(self.backoff(attempt))
+            }
+            _ => None,
+        }
+    }
+
+    fn backoff(&self, attempt: u32) -> Duration {
+        backoff_with_jitter(self.initial_backoff, self.max_delay, attempt)
+    }
+}
+
+/// Exponential backoff with equal jitter, which waits at least half of the backoff.
+pub(crate) fn backoff_with_jitter(initial: Duration, max: Duration, attempt: u32) -> Duration {
+    let base = initial
+        .saturating_mul(2u32.saturating_pow(attempt))
+        .min(max);
+    let half = base / 2;
+    half + half.mul_f64(rand::thread_rng().gen::<f64>())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    #[test]
+    fn test_rate_limit_from_headers() {
+        let mut header = HeaderMap::new();
+        header.insert("x-ratelimit-limit", HeaderValue::from_static("300"));
+        header.insert("x-ratelimit-remaining", HeaderValue::from_static("299"));
+        header.insert(
+            "x-ratelimit-reset",
+            HeaderValue::from_static("2022-11-01T12:00:00.000Z"),
+        );
+
+        let rate_limit = RateLimit::from_headers(&header).unwrap();
+        assert_eq!(rate_limit.limit, 300);
+        assert_eq!(rate_limit.remaining, 299);
+        assert_eq!(
+            rate_limit.reset,
+            Some(
+                DateTime::parse_from_rfc3339("2022-11-01T12:00:00Z")
+                    .unwrap()
+                    .into()
+            )
+        );
+    }
+
+    #[test]
+    fn test_retry_delay() {
+        let policy = RetryPolicy::default();
+        let server_error = Error::new_own(
+            "Internal Server Error".to_string(),
+            Kind::HTTPStatusError,
+            None,
+            Some(503),
+        );
+
+        let delay = policy.retry_delay(1, &server_error, true).unwrap();
+        assert!(delay >= Duration::from_millis(500) && delay <= Duration::from_secs(1));
+        assert_eq!(policy.retry_delay(1, &server_error, false), None);
+        assert_eq!(policy.retry_delay(3, &server_error, true), None);
+    }
+}
@@ -1,8 +1,14 @@
 //! Response modules
+use crate::error::{Error, Kind};
+use crate::pagination::{Cursor, Links};
+use crate::rate_limit::RateLimit;
 use reqwest::header::HeaderMap;
 use serde::de::DeserializeOwned;
 use std::fmt::Debug;
 
+/// Maximum length of the raw body which is included in parse errors.
+const BODY_SNIPPET_LENGTH: usize = 200;
+
 /// Response struct for API response.
 #[derive(Debug, Clone)]
 pub struct Response<T> {
@@ -28,16 +34,45 @@ impl<T> Response<T> {
     }
 
     /// Create a new Response struct from reqwest::Response.
-    pub async fn from_reqwest(response: reqwest::Response) -> Result<Response<T>, reqwest::Error>
+    /// Empty bodies and `{}` are parsed as `null`, so they are accepted for `Response<()>`.
+    pub async fn from_reqwest(response: reqwest::Response) -> Result<Response<T>, Error>
     where
         T: DeserializeOwned + Debug,
     {
         let header = response.headers().clone();
-        let status_code = response.status();
-        println!("Status: {}", status_code);
-        println!("Status: {:#?}", response.text().await?);
+        let status = response.status();
+        let url = response.url().to_string();
+        let body = response.text().await?;
+
+        let json = match serde_json::from_str::<T>(&body) {
+            Ok(json) => json,
+            Err(err) => {
+                let trimmed = body.trim();
+                let fallback = if trimmed.is_empty() || trimmed == "{}" {
+                    serde_json::from_str::<T>("null").ok()
+                } else {
+                    None
+                };
+                match fallback {
+                    Some(json) => json,
+                    None => {
+                        return Err(Error::new_own(
+                            format!("{}: {}", err, snippet(&body)),
+                            Kind::ParseError,
+                            Some(url),
+                            Some(status.as_u16()),
+                        ))
+                    }
+                }
+            }
+        };
 
-        todo!()
+        Ok(Self::new(
+            json,
+            status.as_u16(),
+            status.canonical_reason().unwrap_or("").to_string(),
+            header,
+        ))
     }
 
     /// Get json object.
@@ -47,4 +82,89 @@ impl<T> Response<T> {
     {
         self.json.clone()
     }
+
+    /// Get rate limit status of the response. Returns `None` if the server does not send rate limit headers.
+    pub fn rate_limit(&self) -> Option<RateLimit> {
+        RateLimit::from_headers(&self.header)
+    }
+}
+
+impl<T> Response<Vec<T>> {
+    /// Get parsed `Link` header of the response.
+    pub fn links(&self) -> Links {
+        Links::from_headers(&self.header)
+    }
+
+    /// Get a cursor for the next page, which returns older results.
+    pub fn next(&self) -> Option<Cursor> {
+        self.links().next
+    }
+
+    /// Get a cursor for the previous page, which returns newer results.
+    pub fn prev(&self) -> Option<Cursor> {
+        self.links().prev
+    }
+}
+
+fn snippet(body: &str) -> String {
+    match body.char_indices().nth(BODY_SNIPPET_LENGTH) {
+        Some((index, _)) => format!("{}...", &body[..index]),
+        None => body.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::StubServer;
+
+    #[test]
+    fn test_snippet() {
+        assert_eq!(snippet("short body"), "short body");
+
+        let long = "あ".repeat(BODY_SNIPPET_LENGTH + 10);
+        assert_eq!(
+            snippet(&long),
+            format!("{}...", "あ".repeat(BODY_SNIPPET_LENGTH))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_accept_empty_body_for_unit() {
+        let server = StubServer::start().await;
+        server
+            .route("POST", "/empty", 200, "")
+            .route("POST", "/object", 200, "{}");
+
+        for path in ["/empty", "/object"] {
+            let res = reqwest::Client::new()
+                .post(format!("{}{}", server.base_url, path))
+                .send()
+                .await
+                .unwrap();
+            let res = Response::<()>::from_reqwest(res).await.unwrap();
+            assert_eq!(res.status, 200);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_parse_error_has_url_and_snippet() {
+        let server = StubServer::start().await;
+        let body = format!("{{\"error\":\"{}\"}}", "x".repeat(BODY_SNIPPET_LENGTH));
+        server.route("GET", "/statuses", 200, &body);
+
+        let url = format!("{}/statuses", server.base_url);
+        let res = reqwest::get(&url).await.unwrap();
+        let err = Response::<Vec<String>>::from_reqwest(res)
+            .await
+            .unwrap_err();
+        let Error::OwnError(err) = err else {
+            panic!("Unexpected error: {:?}", err);
+        };
+        assert!(matches!(err.kind, Kind::ParseError));
+        assert_eq!(err.url, Some(url));
+        assert_eq!(err.status, Some(200));
+        assert!(err.message.ends_with(&snippet(&body)));
+        assert!(err.message.ends_with("..."));
+    }
 }
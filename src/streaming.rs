@@ -1,12 +1,544 @@
 //! Streaming modules
+//!
+//! [`Streaming::subscribe`] returns a stream of messages and a handle to close the connection.
+//!
+//! ```rust
+//! # use megalodon;
+//! use futures_util::StreamExt;
+//!
+//! # async fn run() {
+//! let client = megalodon::generator(
+//!   megalodon::SNS::Mastodon,
+//!   String::from("https://fedibird.com"),
+//!   Some(String::from("your access token")),
+//!   None,
+//! );
+//! let streaming = client.user_streaming(String::from("wss://streaming.fedibird.com"));
+//! let (mut messages, handle) = streaming.subscribe();
+//! loop {
+//!   tokio::select! {
+//!     Some(message) = messages.next() => println!("{:#?}", message),
+//!     _ = tokio::signal::ctrl_c() => {
+//!       handle.close();
+//!       break;
+//!     }
+//!   }
+//! }
+//! # }
+//! ```
+//!
+//! [`MultiplexedStreaming::connect`] subscribes multiple streams over a single connection, and each message is tagged with its stream.
+//!
+//! ```rust
+//! # use megalodon;
+//! use futures_util::StreamExt;
+//! use megalodon::streaming::StreamType;
+//!
+//! # async fn run() {
+//! # let client = megalodon::generator(
+//! #   megalodon::SNS::Mastodon,
+//! #   String::from("https://fedibird.com"),
+//! #   Some(String::from("your access token")),
+//! #   None,
+//! # );
+//! let streaming = client.multiplexed_streaming(String::from("wss://streaming.fedibird.com"));
+//! let (mut messages, handle) = streaming.connect(vec![StreamType::User]);
+//! handle.subscribe(StreamType::Hashtag(String::from("rust")));
+//! while let Some(tagged) = messages.next().await {
+//!   println!("{:?}: {:#?}", tagged.stream, tagged.message);
+//! }
+//! # }
+//! ```
 use crate::entities as MegalodonEntities;
+use crate::rate_limit::backoff_with_jitter;
 use async_trait::async_trait;
+use futures_util::{Stream, StreamExt};
+use serde_json::{json, Value};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::sync::{mpsc, watch};
+
+/// Number of messages which are buffered before the connection stops reading the socket.
+const MESSAGE_BUFFER_SIZE: usize = 64;
 
 /// Streaming interface to listen message.
 #[async_trait]
-pub trait Streaming {
+pub trait Streaming: Sync {
+    /// Start a connection in background. Messages are delivered to the returned stream until the handle closes the connection, or the stream is dropped.
+    ///
+    /// # Panics
+    ///
+    /// Panics if it is called outside of a Tokio runtime, because the connection is spawned as a task.
+    fn subscribe(&self) -> (MessageStream, StreamingHandle);
+
     /// Start listening stream messages. When receive a message, the callback function will be called.
-    async fn listen(&self, callback: Box<dyn Fn(Message) + Send + Sync>);
+    async fn listen(&self, callback: Box<dyn Fn(Message) + Send + Sync>) {
+        let (mut messages, _handle) = self.subscribe();
+        while let Some(message) = messages.next().await {
+            callback(message);
+        }
+    }
+}
+
+/// Streaming interface which carries multiple streams over a single connection.
+pub trait MultiplexedStreaming: Sync {
+    /// Start a connection in background, and subscribe the streams. Other streams can be subscribed or unsubscribed at runtime with the handle.
+    ///
+    /// # Panics
+    ///
+    /// Panics if it is called outside of a Tokio runtime, because the connection is spawned as a task.
+    fn connect(
+        &self,
+        streams: Vec<StreamType>,
+    ) -> (MessageStream<TaggedMessage>, MultiplexedHandle);
+}
+
+/// Options of streaming connections.
+#[derive(Debug, Clone)]
+pub struct StreamingOptions {
+    /// Wait before the first reconnection. It is doubled for each attempt, with jitter.
+    pub initial_backoff: Duration,
+    /// Maximum wait before a reconnection.
+    pub max_backoff: Duration,
+    /// Give up reconnecting after this number of attempts. It retries forever when it is `None`.
+    pub max_attempts: Option<u32>,
+    /// Reconnect when no message is received in this duration.
+    pub read_timeout: Duration,
+    /// Send ping frames at this interval to keep the connection alive.
+    pub ping_interval: Option<Duration>,
+}
+
+impl Default for StreamingOptions {
+    fn default() -> Self {
+        Self {
+            initial_backoff: Duration::from_secs(5),
+            max_backoff: Duration::from_secs(60),
+            max_attempts: None,
+            read_timeout: Duration::from_secs(60),
+            ping_interval: None,
+        }
+    }
+}
+
+/// Stream of messages which are received from a streaming connection.
+#[derive(Debug)]
+pub struct MessageStream<T = Message> {
+    rx: mpsc::Receiver<T>,
+}
+
+impl<T> Stream for MessageStream<T> {
+    type Item = T;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.get_mut().rx.poll_recv(cx)
+    }
+}
+
+/// Handle to close a streaming connection.
+#[derive(Debug, Clone)]
+pub struct StreamingHandle {
+    shutdown: Arc<watch::Sender<bool>>,
+}
+
+impl StreamingHandle {
+    /// Close the connection. The message stream ends after the socket is closed.
+    pub fn close(&self) {
+        let _ = self.shutdown.send(true);
+    }
+}
+
+/// Handle to close a multiplexed streaming connection, and to change subscriptions of it.
+#[derive(Debug, Clone)]
+pub struct MultiplexedHandle {
+    handle: StreamingHandle,
+    subscriptions: Arc<Mutex<Vec<StreamType>>>,
+    commands: mpsc::UnboundedSender<SubscriptionCommand>,
+}
+
+impl MultiplexedHandle {
+    /// Subscribe the stream. It is subscribed again after reconnecting.
+    pub fn subscribe(&self, stream: StreamType) {
+        // Commands are queued while the lock is held, so reconnecting can drop them consistently with the subscriptions.
+        let Ok(mut subscriptions) = self.subscriptions.lock() else {
+            return;
+        };
+        if subscriptions.contains(&stream) {
+            return;
+        }
+        subscriptions.push(stream.clone());
+        let _ = self.commands.send(SubscriptionCommand::Subscribe(stream));
+    }
+
+    /// Unsubscribe the stream.
+    pub fn unsubscribe(&self, stream: StreamType) {
+        let Ok(mut subscriptions) = self.subscriptions.lock() else {
+            return;
+        };
+        subscriptions.retain(|s| s != &stream);
+        let _ = self.commands.send(SubscriptionCommand::Unsubscribe(stream));
+    }
+
+    /// Get streams which are currently subscribed.
+    pub fn subscriptions(&self) -> Vec<StreamType> {
+        self.subscriptions
+            .lock()
+            .map(|subscriptions| subscriptions.clone())
+            .unwrap_or_default()
+    }
+
+    /// Close the connection. The message stream ends after the socket is closed.
+    pub fn close(&self) {
+        self.handle.close();
+    }
+}
+
+/// Sender side of a streaming connection, which is held by the background task.
+#[derive(Debug)]
+pub(crate) struct MessageSender<T = Message> {
+    tx: mpsc::Sender<T>,
+    shutdown: watch::Receiver<bool>,
+    convert: fn(TaggedMessage) -> T,
+}
+
+impl<T> MessageSender<T> {
+    /// Send the message which does not belong to any stream. Returns `false` if the connection should be closed.
+    pub(crate) async fn send(&mut self, message: Message) -> bool {
+        self.send_tagged(TaggedMessage {
+            stream: Vec::new(),
+            message,
+        })
+        .await
+    }
+
+    /// Send the message. Returns `false` if the connection should be closed.
+    pub(crate) async fn send_tagged(&mut self, message: TaggedMessage) -> bool {
+        let tx = self.tx.clone();
+        let message = (self.convert)(message);
+        tokio::select! {
+            res = tx.send(message) => res.is_ok(),
+            _ = self.closed() => false,
+        }
+    }
+
+    /// Wait until the handle closes the connection, or the stream is dropped.
+    pub(crate) async fn closed(&mut self) {
+        let tx = self.tx.clone();
+        tokio::select! {
+            _ = wait_shutdown(&mut self.shutdown) => {},
+            _ = tx.closed() => {},
+        }
+    }
+
+    /// Whether the handle closes the connection, or the stream is dropped.
+    pub(crate) fn is_closed(&self) -> bool {
+        *self.shutdown.borrow() || self.tx.is_closed()
+    }
+}
+
+async fn wait_shutdown(shutdown: &mut watch::Receiver<bool>) {
+    loop {
+        if *shutdown.borrow() {
+            return;
+        }
+        if shutdown.changed().await.is_err() {
+            // All handles are dropped, so the connection is never closed by handles.
+            std::future::pending::<()>().await;
+        }
+    }
+}
+
+/// Create a pair of message channel and shutdown signal for a streaming connection.
+pub(crate) fn channel() -> (MessageSender, MessageStream, StreamingHandle) {
+    tagged_channel(|tagged| tagged.message)
+}
+
+fn tagged_channel<T>(
+    convert: fn(TaggedMessage) -> T,
+) -> (MessageSender<T>, MessageStream<T>, StreamingHandle) {
+    let (tx, rx) = mpsc::channel(MESSAGE_BUFFER_SIZE);
+    let (shutdown_tx, shutdown_rx) = watch::channel(false);
+    (
+        MessageSender {
+            tx,
+            shutdown: shutdown_rx,
+            convert,
+        },
+        MessageStream { rx },
+        StreamingHandle {
+            shutdown: Arc::new(shutdown_tx),
+        },
+    )
+}
+
+/// Create channels for a multiplexed streaming connection, which subscribes the streams at first.
+pub(crate) fn multiplexed_channel(
+    streams: Vec<StreamType>,
+) -> (
+    MessageSender<TaggedMessage>,
+    Multiplexer,
+    MessageStream<TaggedMessage>,
+    MultiplexedHandle,
+) {
+    let (sender, messages, handle) = tagged_channel(|tagged| tagged);
+    let (commands_tx, commands_rx) = mpsc::unbounded_channel();
+    let mut subscriptions = Vec::<StreamType>::new();
+    for stream in streams {
+        if !subscriptions.contains(&stream) {
+            subscriptions.push(stream);
+        }
+    }
+    let subscriptions = Arc::new(Mutex::new(subscriptions));
+    (
+        sender,
+        Multiplexer {
+            subscriptions: subscriptions.clone(),
+            commands: commands_rx,
+        },
+        messages,
+        MultiplexedHandle {
+            handle,
+            subscriptions,
+            commands: commands_tx,
+        },
+    )
+}
+
+/// Command to change subscriptions of a multiplexed connection.
+#[derive(Debug, Clone)]
+pub(crate) enum SubscriptionCommand {
+    Subscribe(StreamType),
+    Unsubscribe(StreamType),
+}
+
+impl SubscriptionCommand {
+    /// Get the json which is sent to the server.
+    pub(crate) fn to_json(&self) -> Value {
+        match self {
+            SubscriptionCommand::Subscribe(stream) => stream.to_json("subscribe"),
+            SubscriptionCommand::Unsubscribe(stream) => stream.to_json("unsubscribe"),
+        }
+    }
+}
+
+/// Receiver side of subscription changes, which is held by the background task.
+#[derive(Debug)]
+pub(crate) struct Multiplexer {
+    subscriptions: Arc<Mutex<Vec<StreamType>>>,
+    commands: mpsc::UnboundedReceiver<SubscriptionCommand>,
+}
+
+impl Multiplexer {
+    /// Get commands to subscribe all current streams, which are sent after connected.
+    pub(crate) fn subscribe_commands(&mut self) -> Vec<SubscriptionCommand> {
+        self.resubscribe()
+            .into_iter()
+            .map(SubscriptionCommand::Subscribe)
+            .collect()
+    }
+
+    /// Get streams which are subscribed after connected. Queued commands are dropped,
+    /// because they are already reflected in the subscriptions.
+    pub(crate) fn resubscribe(&mut self) -> Vec<StreamType> {
+        let Ok(subscriptions) = self.subscriptions.lock() else {
+            return Vec::new();
+        };
+        while self.commands.try_recv().is_ok() {}
+        subscriptions.clone()
+    }
+
+    /// Get streams which are subscribed currently.
+    pub(crate) fn subscriptions(&self) -> Vec<StreamType> {
+        self.subscriptions
+            .lock()
+            .map(|subscriptions| subscriptions.clone())
+            .unwrap_or_default()
+    }
+
+    /// Wait for the next command. It never returns after all handles are dropped.
+    pub(crate) async fn next_command(&mut self) -> SubscriptionCommand {
+        match self.commands.recv().await {
+            Some(command) => command,
+            None => std::future::pending::<SubscriptionCommand>().await,
+        }
+    }
+}
+
+/// How a connection ends, which decides whether to reconnect.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ConnectionEnd {
+    /// The connection is closed normally, or by the handle.
+    Closed,
+    /// The connection could not be established.
+    Failed,
+    /// The established connection is lost.
+    Lost,
+}
+
+/// A streaming connection which can be established again after it ends.
+#[async_trait]
+pub(crate) trait Connection<T: Send>: Sync {
+    /// Connect to the url, and deliver messages until the connection ends.
+    async fn connect_once(
+        &self,
+        url: &str,
+        sender: &mut MessageSender<T>,
+        multiplexer: &mut Option<Multiplexer>,
+    ) -> ConnectionEnd;
+}
+
+/// Keep the connection until it is closed, reconnecting with backoff according to the options.
+/// `Disconnected` is always the last message, unless the handle closes the connection or the stream is dropped.
+pub(crate) async fn keep_connected<T: Send, C: Connection<T>>(
+    connection: &C,
+    url: &str,
+    options: &StreamingOptions,
+    mut sender: MessageSender<T>,
+    mut multiplexer: Option<Multiplexer>,
+) {
+    let mut attempt: u32 = 0;
+    loop {
+        let end = connection
+            .connect_once(url, &mut sender, &mut multiplexer)
+            .await;
+        if end == ConnectionEnd::Closed {
+            log::info!("connection for {} is  closed", url);
+            if !sender.is_closed() {
+                sender.send(Message::Disconnected()).await;
+            }
+            return;
+        }
+        if sender.is_closed() {
+            return;
+        }
+        if end == ConnectionEnd::Lost {
+            // The connection had been established, so count attempts from the beginning.
+            attempt = 0;
+            if !sender.send(Message::Disconnected()).await {
+                return;
+            }
+        }
+        if let Some(max_attempts) = options.max_attempts {
+            if attempt >= max_attempts {
+                log::error!("Give up reconnecting to {}", url);
+                if end == ConnectionEnd::Failed {
+                    sender.send(Message::Disconnected()).await;
+                }
+                return;
+            }
+        }
+        let delay = backoff_with_jitter(options.initial_backoff, options.max_backoff, attempt);
+        attempt += 1;
+        if !sender.send(Message::Reconnecting { attempt }).await {
+            return;
+        }
+        log::info!("Reconnecting to {} in {:?}", url, delay);
+        tokio::select! {
+            _ = tokio::time::sleep(delay) => {},
+            _ = sender.closed() => return,
+        }
+    }
+}
+
+/// Streams which can be subscribed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StreamType {
+    /// Events related to the current user, such as home feed updates and notifications.
+    User,
+    /// Notifications for the current user.
+    UserNotification,
+    /// All public posts known to the server.
+    Public,
+    /// All public posts known to the server, filtered for media attachments.
+    PublicMedia,
+    /// All public posts originating from this server.
+    PublicLocal,
+    /// All public posts originating from this server, filtered for media attachments.
+    PublicLocalMedia,
+    /// All public posts originating from other servers.
+    PublicRemote,
+    /// All public posts originating from other servers, filtered for media attachments.
+    PublicRemoteMedia,
+    /// All public posts using the hashtag.
+    Hashtag(String),
+    /// All public posts using the hashtag, originating from this server.
+    HashtagLocal(String),
+    /// Updates to the list.
+    List(String),
+    /// Updates to direct conversations.
+    Direct,
+}
+
+impl StreamType {
+    /// Name of the stream.
+    pub fn name(&self) -> &'static str {
+        match self {
+            StreamType::User => "user",
+            StreamType::UserNotification => "user:notification",
+            StreamType::Public => "public",
+            StreamType::PublicMedia => "public:media",
+            StreamType::PublicLocal => "public:local",
+            StreamType::PublicLocalMedia => "public:local:media",
+            StreamType::PublicRemote => "public:remote",
+            StreamType::PublicRemoteMedia => "public:remote:media",
+            StreamType::Hashtag(_) => "hashtag",
+            StreamType::HashtagLocal(_) => "hashtag:local",
+            StreamType::List(_) => "list",
+            StreamType::Direct => "direct",
+        }
+    }
+
+    /// Parse `stream` of an event, like `["hashtag", "rust"]`. It may report multiple streams, like `["user", "user:notification"]`.
+    pub fn from_stream(stream: &[String]) -> Vec<Self> {
+        let mut streams = Vec::new();
+        let mut names = stream.iter();
+        while let Some(name) = names.next() {
+            let stream = match name.as_str() {
+                "user" => StreamType::User,
+                "user:notification" => StreamType::UserNotification,
+                "public" => StreamType::Public,
+                "public:media" => StreamType::PublicMedia,
+                "public:local" => StreamType::PublicLocal,
+                "public:local:media" => StreamType::PublicLocalMedia,
+                "public:remote" => StreamType::PublicRemote,
+                "public:remote:media" => StreamType::PublicRemoteMedia,
+                "hashtag" | "hashtag:local" | "list" => {
+                    let Some(param) = names.next().cloned() else {
+                        break;
+                    };
+                    match name.as_str() {
+                        "hashtag" => StreamType::Hashtag(param),
+                        "hashtag:local" => StreamType::HashtagLocal(param),
+                        _ => StreamType::List(param),
+                    }
+                }
+                "direct" => StreamType::Direct,
+                _ => continue,
+            };
+            streams.push(stream);
+        }
+        streams
+    }
+
+    fn to_json(&self, r#type: &str) -> Value {
+        match self {
+            StreamType::Hashtag(tag) | StreamType::HashtagLocal(tag) => {
+                json!({"type": r#type, "stream": self.name(), "tag": tag})
+            }
+            StreamType::List(list) => json!({"type": r#type, "stream": self.name(), "list": list}),
+            _ => json!({"type": r#type, "stream": self.name()}),
+        }
+    }
+}
+
+/// Message with the streams which it came from.
+#[derive(Debug, Clone)]
+pub struct TaggedMessage {
+    /// The streams of the message. It is empty for connection events and heartbeats.
+    pub stream: Vec<StreamType>,
+    /// The message.
+    pub message: Message,
 }
 
 /// Stream message definitions.
@@ -22,6 +554,231 @@ pub enum Message {
     Delete(String),
     /// StatusUpdate message of `status.update` event.
     StatusUpdate(MegalodonEntities::Status),
+    /// FiltersChanged message for `filters_changed` event. Filters should be fetched again.
+    FiltersChanged(),
+    /// Announcement message for `announcement` event.
+    Announcement(MegalodonEntities::Announcement),
+    /// AnnouncementReaction message for `announcement.reaction` event.
+    AnnouncementReaction {
+        /// ID of the announcement which is reacted.
+        announcement_id: String,
+        /// The emoji used for the reaction.
+        name: String,
+        /// The total number of users who have added this reaction.
+        count: u32,
+    },
+    /// AnnouncementDelete message for `announcement.delete` event, which contains ID of the announcement.
+    AnnouncementDelete(String),
+    /// EncryptedMessage message for `encrypted_message` event.
+    EncryptedMessage(MegalodonEntities::EncryptedMessage),
+    /// NotificationsMerged message for `notifications_merged` event. Notifications should be fetched again.
+    NotificationsMerged(),
+    /// ChatUpdate message for `pleroma:chat_update` event.
+    ChatUpdate(MegalodonEntities::Chat),
+    /// Respond message for `pleroma:respond` event, which is the result of a subscription request.
+    Respond {
+        /// Type of the request, like `subscribe`.
+        r#type: String,
+        /// Result of the request, like `success` or `error`.
+        result: String,
+    },
+    /// Message for an event which is not supported by this library.
+    Unknown {
+        /// Name of the event.
+        event: String,
+        /// Raw payload of the event.
+        payload: String,
+    },
     /// Heartbeat for streaming connection.
     Heartbeat(),
+    /// The connection is established.
+    Connected(),
+    /// The connection is reconnecting. `attempt` starts from 1, and is reset after connected.
+    Reconnecting {
+        /// Number of attempts since the connection is lost.
+        attempt: u32,
+    },
+    /// The connection is lost or closed.
+    Disconnected(),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stream_type_from_stream() {
+        assert_eq!(
+            StreamType::from_stream(&[String::from("public:local")]),
+            vec![StreamType::PublicLocal]
+        );
+        assert_eq!(
+            StreamType::from_stream(&[String::from("hashtag"), String::from("rust")]),
+            vec![StreamType::Hashtag(String::from("rust"))]
+        );
+        assert_eq!(StreamType::from_stream(&[String::from("list")]), vec![]);
+        assert_eq!(StreamType::from_stream(&[]), vec![]);
+    }
+
+    #[test]
+    fn test_stream_type_from_multiple_streams() {
+        let stream = [
+            "user",
+            "user:notification",
+            "list",
+            "12",
+            "unknown",
+            "public",
+        ]
+        .map(String::from);
+        assert_eq!(
+            StreamType::from_stream(&stream),
+            vec![
+                StreamType::User,
+                StreamType::UserNotification,
+                StreamType::List(String::from("12")),
+                StreamType::Public
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_commands_drop_queued_commands() {
+        let (_sender, mut multiplexer, _messages, handle) =
+            multiplexed_channel(vec![StreamType::User, StreamType::Direct]);
+        handle.subscribe(StreamType::Public);
+        handle.subscribe(StreamType::Public);
+        handle.unsubscribe(StreamType::Direct);
+
+        let commands: Vec<Value> = multiplexer
+            .subscribe_commands()
+            .iter()
+            .map(SubscriptionCommand::to_json)
+            .collect();
+        assert_eq!(
+            commands,
+            vec![
+                json!({"type": "subscribe", "stream": "user"}),
+                json!({"type": "subscribe", "stream": "public"}),
+            ]
+        );
+
+        // Commands after connected are delivered.
+        handle.subscribe(StreamType::PublicLocal);
+        assert_eq!(
+            multiplexer.next_command().await.to_json(),
+            json!({"type": "subscribe", "stream": "public:local"})
+        );
+    }
+
+    #[test]
+    fn test_subscription_command_to_json() {
+        assert_eq!(
+            SubscriptionCommand::Subscribe(StreamType::User).to_json(),
+            json!({"type": "subscribe", "stream": "user"})
+        );
+        assert_eq!(
+            SubscriptionCommand::Unsubscribe(StreamType::List(String::from("12"))).to_json(),
+            json!({"type": "unsubscribe", "stream": "list", "list": "12"})
+        );
+    }
+
+    /// Connection which ends as scripted, and is closed after the script runs out.
+    struct ScriptedConnection {
+        ends: Mutex<Vec<ConnectionEnd>>,
+        calls: Mutex<u32>,
+    }
+
+    impl ScriptedConnection {
+        fn new(ends: Vec<ConnectionEnd>) -> Self {
+            Self {
+                ends: Mutex::new(ends.into_iter().rev().collect()),
+                calls: Mutex::new(0),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl Connection<Message> for ScriptedConnection {
+        async fn connect_once(
+            &self,
+            _url: &str,
+            _sender: &mut MessageSender<Message>,
+            _multiplexer: &mut Option<Multiplexer>,
+        ) -> ConnectionEnd {
+            *self.calls.lock().unwrap() += 1;
+            self.ends
+                .lock()
+                .unwrap()
+                .pop()
+                .unwrap_or(ConnectionEnd::Closed)
+        }
+    }
+
+    fn options(max_attempts: Option<u32>) -> StreamingOptions {
+        StreamingOptions {
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(1),
+            max_attempts,
+            ..Default::default()
+        }
+    }
+
+    async fn run(connection: &ScriptedConnection, options: StreamingOptions) -> Vec<Message> {
+        let (sender, messages, _handle) = channel();
+        keep_connected(connection, "wss://example.com", &options, sender, None).await;
+        messages.collect().await
+    }
+
+    fn lifecycle(messages: &[Message]) -> Vec<Option<u32>> {
+        messages
+            .iter()
+            .map(|message| match message {
+                Message::Reconnecting { attempt } => Some(*attempt),
+                Message::Disconnected() => None,
+                other => panic!("unexpected message: {:?}", other),
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_keep_connected_counts_attempts() {
+        let connection =
+            ScriptedConnection::new(vec![ConnectionEnd::Failed, ConnectionEnd::Failed]);
+        let messages = run(&connection, options(None)).await;
+        assert_eq!(lifecycle(&messages), vec![Some(1), Some(2), None]);
+        assert_eq!(*connection.calls.lock().unwrap(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_keep_connected_resets_attempts_after_established() {
+        let connection = ScriptedConnection::new(vec![
+            ConnectionEnd::Failed,
+            ConnectionEnd::Failed,
+            ConnectionEnd::Lost,
+            ConnectionEnd::Failed,
+        ]);
+        let messages = run(&connection, options(Some(2))).await;
+        assert_eq!(
+            lifecycle(&messages),
+            vec![Some(1), Some(2), None, Some(1), Some(2), None]
+        );
+        assert_eq!(*connection.calls.lock().unwrap(), 5);
+    }
+
+    #[tokio::test]
+    async fn test_keep_connected_gives_up() {
+        let connection = ScriptedConnection::new(vec![ConnectionEnd::Failed; 10]);
+        let messages = run(&connection, options(Some(3))).await;
+        assert_eq!(lifecycle(&messages), vec![Some(1), Some(2), Some(3), None]);
+        assert_eq!(*connection.calls.lock().unwrap(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_keep_connected_gives_up_after_lost() {
+        let connection = ScriptedConnection::new(vec![ConnectionEnd::Lost]);
+        let messages = run(&connection, options(Some(0))).await;
+        assert_eq!(lifecycle(&messages), vec![None]);
+        assert_eq!(*connection.calls.lock().unwrap(), 1);
+    }
 }
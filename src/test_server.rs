@@ -0,0 +1,142 @@
+//! Stub HTTP server for tests, which replays recorded responses
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+
+/// Request which the server received.
+#[derive(Debug, Clone)]
+pub(crate) struct Request {
+    pub method: String,
+    /// Path with the query.
+    pub path: String,
+    /// Headers with lowercase names.
+    pub headers: HashMap<String, String>,
+    pub body: String,
+}
+
+impl Request {
+    /// Parse the body as JSON.
+    pub fn json(&self) -> serde_json::Value {
+        serde_json::from_str(&self.body).unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Route {
+    method: String,
+    path: String,
+    status: u16,
+    body: String,
+}
+
+/// Server which responds recorded bodies to requests of the method and the path, ignoring the query.
+/// When a route is registered several times, the responses are returned in order and the last one is repeated.
+#[derive(Debug, Clone)]
+pub(crate) struct StubServer {
+    pub base_url: String,
+    routes: Arc<Mutex<Vec<Route>>>,
+    requests: Arc<Mutex<Vec<Request>>>,
+}
+
+impl StubServer {
+    pub async fn start() -> Self {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).await.unwrap();
+        let server = Self {
+            base_url: format!("http://{}", listener.local_addr().unwrap()),
+            routes: Arc::new(Mutex::new(Vec::new())),
+            requests: Arc::new(Mutex::new(Vec::new())),
+        };
+        let handler = server.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let handler = handler.clone();
+                tokio::spawn(async move { handler.handle(stream).await });
+            }
+        });
+        server
+    }
+
+    /// Register a response for the method and the path.
+    pub fn route(&self, method: &str, path: &str, status: u16, body: &str) -> &Self {
+        self.routes.lock().unwrap().push(Route {
+            method: method.to_string(),
+            path: path.to_string(),
+            status,
+            body: body.to_string(),
+        });
+        self
+    }
+
+    /// Requests which the server received.
+    pub fn requests(&self) -> Vec<Request> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    async fn handle(&self, mut stream: TcpStream) {
+        let Some(request) = read_request(&mut stream).await else {
+            return;
+        };
+        let route = {
+            let mut routes = self.routes.lock().unwrap();
+            let path = request.path.split('?').next().unwrap_or_default();
+            let matched: Vec<usize> = routes
+                .iter()
+                .enumerate()
+                .filter(|(_, r)| r.method == request.method && r.path == path)
+                .map(|(i, _)| i)
+                .collect();
+            match matched.len() {
+                0 => None,
+                1 => Some(routes[matched[0]].clone()),
+                _ => Some(routes.remove(matched[0])),
+            }
+        };
+        self.requests.lock().unwrap().push(request);
+        let (status, body) = match route {
+            Some(route) => (route.status, route.body),
+            None => (404, r#"{"error":"Record not found"}"#.to_string()),
+        };
+        let response = format!(
+            "HTTP/1.1 {} Stub\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            status,
+            body.len(),
+            body
+        );
+        let _ = stream.write_all(response.as_bytes()).await;
+        let _ = stream.shutdown().await;
+    }
+}
+
+async fn read_request(stream: &mut TcpStream) -> Option<Request> {
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    reader.read_line(&mut line).await.ok()?;
+    let mut parts = line.split_whitespace();
+    let method = parts.next()?.to_string();
+    let path = parts.next()?.to_string();
+
+    let mut headers = HashMap::new();
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).await.ok()? == 0 || line == "\r\n" || line == "\n" {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            headers.insert(name.trim().to_lowercase(), value.trim().to_string());
+        }
+    }
+    let length = headers
+        .get("content-length")
+        .and_then(|l| l.parse::<usize>().ok())
+        .unwrap_or(0);
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).await.ok()?;
+
+    Some(Request {
+        method,
+        path,
+        headers,
+        body: String::from_utf8_lossy(&body).into_owned(),
+    })
+}
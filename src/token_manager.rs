@@ -0,0 +1,321 @@
+//! Token manager modules, which refresh access tokens automatically
+use crate::error::{Error, Kind};
+use crate::megalodon::Megalodon;
+use crate::oauth::TokenData;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+/// Callback which is called with the new [`TokenData`] after the access token is refreshed.
+pub type RefreshCallback = Arc<dyn Fn(&TokenData) + Send + Sync>;
+
+/// Manager of an access token, which is set to the client with [`crate::ClientBuilder::token_manager`].
+///
+/// The client sends requests with the access token of the manager.
+/// The token is refreshed with [`Megalodon::refresh_access_token`] of the client before it expires, or when the server responds 401.
+/// After refreshing, the new [`TokenData`] is passed to the callback, so the application can persist it.
+/// Clones share the same token.
+///
+/// Streaming connections use the access token at the time they are created, so they are not affected by refreshing.
+#[derive(Clone)]
+pub struct TokenManager {
+    client_id: String,
+    client_secret: String,
+    token: Arc<Mutex<TokenData>>,
+    refreshing: Arc<tokio::sync::Mutex<()>>,
+    on_refresh: Option<RefreshCallback>,
+    refresh_margin: Duration,
+}
+
+impl fmt::Debug for TokenManager {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TokenManager")
+            .field("client_id", &self.client_id)
+            .field("refresh_margin", &self.refresh_margin)
+            .finish_non_exhaustive()
+    }
+}
+
+impl TokenManager {
+    /// Create a new [`TokenManager`] with the application credentials and the current token.
+    pub fn new(client_id: String, client_secret: String, token: TokenData) -> Self {
+        Self {
+            client_id,
+            client_secret,
+            token: Arc::new(Mutex::new(token)),
+            refreshing: Arc::new(tokio::sync::Mutex::new(())),
+            on_refresh: None,
+            refresh_margin: Duration::from_secs(60),
+        }
+    }
+
+    /// Set a callback which is called with the new [`TokenData`] after the access token is refreshed.
+    pub fn on_refresh(mut self, callback: impl Fn(&TokenData) + Send + Sync + 'static) -> Self {
+        self.on_refresh = Some(Arc::new(callback));
+        self
+    }
+
+    /// Set how long before the expiry the access token is refreshed. Default is 60 seconds.
+    pub fn refresh_margin(mut self, margin: Duration) -> Self {
+        self.refresh_margin = margin;
+        self
+    }
+
+    /// Get the current token.
+    pub fn token_data(&self) -> TokenData {
+        self.token.lock().unwrap().clone()
+    }
+
+    /// Get the access token for a request, and refresh it beforehand if it expires soon.
+    /// When proactive refreshing fails, the current access token is used as it is.
+    async fn access_token(&self, refresher: &(dyn Megalodon + Send + Sync)) -> String {
+        let token = self.token_data();
+        if !needs_refresh(&token, now(), self.refresh_margin) {
+            return token.access_token;
+        }
+        let _guard = self.refreshing.lock().await;
+        let token = self.token_data();
+        if !needs_refresh(&token, now(), self.refresh_margin) {
+            return token.access_token;
+        }
+        match self.refresh(refresher, &token).await {
+            Ok(token) => token.access_token,
+            Err(err) => {
+                log::warn!("Failed to refresh the access token: {}", err);
+                token.access_token
+            }
+        }
+    }
+
+    /// Refresh the access token which is rejected by the server.
+    /// Returns `false` if it can not be refreshed because there is no refresh token.
+    async fn refresh_rejected(
+        &self,
+        refresher: &(dyn Megalodon + Send + Sync),
+        rejected: &str,
+    ) -> Result<bool, Error> {
+        let _guard = self.refreshing.lock().await;
+        let token = self.token_data();
+        // Another request has already refreshed it.
+        if token.access_token != rejected {
+            return Ok(true);
+        }
+        if token.refresh_token.is_none() {
+            return Ok(false);
+        }
+        self.refresh(refresher, &token).await?;
+        Ok(true)
+    }
+
+    async fn refresh(
+        &self,
+        refresher: &(dyn Megalodon + Send + Sync),
+        current: &TokenData,
+    ) -> Result<TokenData, Error> {
+        let Some(refresh_token) = &current.refresh_token else {
+            return Err(Error::new_own(
+                "There is no refresh token".to_string(),
+                Kind::UnauthorizedError,
+                None,
+                None,
+            ));
+        };
+        let refreshed = refresher
+            .refresh_access_token(
+                self.client_id.clone(),
+                self.client_secret.clone(),
+                refresh_token.clone(),
+            )
+            .await?;
+        let refreshed = keep_refresh_token(refreshed, current);
+
+        *self.token.lock().unwrap() = refreshed.clone();
+        if let Some(callback) = &self.on_refresh {
+            callback(&refreshed);
+        }
+        Ok(refreshed)
+    }
+}
+
+/// [`TokenManager`] which is set to an API client, with the client to refresh the access token.
+#[derive(Clone)]
+pub(crate) struct ManagedToken {
+    manager: TokenManager,
+    /// Client which sends [`Megalodon::refresh_access_token`]. It must not have a token manager itself.
+    refresher: Arc<dyn Megalodon + Send + Sync>,
+}
+
+impl fmt::Debug for ManagedToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ManagedToken")
+            .field("manager", &self.manager)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ManagedToken {
+    pub(crate) fn new(manager: TokenManager, refresher: Arc<dyn Megalodon + Send + Sync>) -> Self {
+        Self { manager, refresher }
+    }
+
+    /// Get the current token data of the manager.
+    pub(crate) fn token_data(&self) -> TokenData {
+        self.manager.token_data()
+    }
+
+    /// Get the access token for a request, and refresh it beforehand if it expires soon.
+    pub(crate) async fn access_token(&self) -> String {
+        self.manager.access_token(self.refresher.as_ref()).await
+    }
+
+    /// Refresh the access token which is rejected by the server.
+    /// Returns `false` if it can not be refreshed because there is no refresh token.
+    pub(crate) async fn refresh_rejected(&self, rejected: &str) -> Result<bool, Error> {
+        self.manager
+            .refresh_rejected(self.refresher.as_ref(), rejected)
+            .await
+    }
+}
+
+/// Keep the current refresh token if the server does not rotate it.
+fn keep_refresh_token(refreshed: TokenData, current: &TokenData) -> TokenData {
+    TokenData {
+        refresh_token: refreshed
+            .refresh_token
+            .or_else(|| current.refresh_token.clone()),
+        ..refreshed
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Whether the token expires within the margin. Tokens without `expires_in` or refresh token are never refreshed proactively.
+fn needs_refresh(token: &TokenData, now: u64, margin: Duration) -> bool {
+    match (token.expires_in, &token.refresh_token) {
+        (Some(expires_in), Some(_)) => token.created_at + expires_in <= now + margin.as_secs(),
+        _ => false,
+    }
+}
+
+/// Whether the error is 401 response.
+pub(crate) fn is_unauthorized(err: &Error) -> bool {
+    matches!(err, Error::OwnError(own) if matches!(own.kind, Kind::UnauthorizedError))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::StubServer;
+
+    fn token(expires_in: Option<u64>, refresh_token: Option<&str>) -> TokenData {
+        TokenData::new(
+            "access".to_string(),
+            "Bearer".to_string(),
+            "read".to_string(),
+            1000,
+            expires_in,
+            refresh_token.map(str::to_string),
+        )
+    }
+
+    #[test]
+    fn test_needs_refresh() {
+        let margin = Duration::from_secs(60);
+        assert!(!needs_refresh(&token(Some(600), Some("r")), 1000, margin));
+        assert!(needs_refresh(&token(Some(600), Some("r")), 1540, margin));
+        assert!(needs_refresh(&token(Some(600), Some("r")), 2000, margin));
+        assert!(!needs_refresh(&token(Some(600), None), 2000, margin));
+        assert!(!needs_refresh(&token(None, Some("r")), 2000, margin));
+    }
+
+    #[test]
+    fn test_refreshed_token_keeps_refresh_token() {
+        let current = token(Some(600), Some("old"));
+        let refreshed = keep_refresh_token(
+            TokenData::new(
+                "new".to_string(),
+                "Bearer".to_string(),
+                "read".to_string(),
+                2000,
+                Some(600),
+                None,
+            ),
+            &current,
+        );
+        assert_eq!(refreshed.access_token, "new");
+        assert_eq!(refreshed.created_at, 2000);
+        assert_eq!(refreshed.refresh_token, Some("old".to_string()));
+
+        let rotated = keep_refresh_token(token(Some(600), Some("rotated")), &current);
+        assert_eq!(rotated.refresh_token, Some("rotated".to_string()));
+    }
+
+    const REFRESHED: &str =
+        r#"{"access_token":"new","token_type":"Bearer","scope":"read","created_at":2000}"#;
+
+    async fn client_with_manager(server: &StubServer) -> (crate::mastodon::Mastodon, TokenManager) {
+        let manager = TokenManager::new(
+            "client_id".to_string(),
+            "client_secret".to_string(),
+            token(None, Some("refresh")),
+        );
+        let mut client = crate::mastodon::Mastodon::new(server.base_url.clone(), None, None);
+        client.set_token_manager(Some(manager.clone()));
+        (client, manager)
+    }
+
+    #[tokio::test]
+    async fn test_retry_with_refreshed_token() {
+        let server = StubServer::start().await;
+        server
+            .route(
+                "GET",
+                "/api/v1/instance/peers",
+                401,
+                r#"{"error":"The access token is invalid"}"#,
+            )
+            .route("GET", "/api/v1/instance/peers", 200, r#"["example.com"]"#)
+            .route("POST", "/oauth/token", 200, REFRESHED);
+        let (client, manager) = client_with_manager(&server).await;
+
+        let res = client.get_instance_peers().await.unwrap();
+        assert_eq!(res.json, vec!["example.com".to_string()]);
+        assert_eq!(manager.token_data().access_token, "new");
+        assert_eq!(
+            manager.token_data().refresh_token,
+            Some("refresh".to_string())
+        );
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 3);
+        assert_eq!(requests[0].headers["authorization"], "Bearer access");
+        let refresh = requests[1].json();
+        assert_eq!(refresh["grant_type"], "refresh_token");
+        assert_eq!(refresh["refresh_token"], "refresh");
+        assert_eq!(refresh["client_id"], "client_id");
+        assert_eq!(requests[2].headers["authorization"], "Bearer new");
+    }
+
+    #[tokio::test]
+    async fn test_return_unauthorized_when_refresh_fails() {
+        let server = StubServer::start().await;
+        server
+            .route(
+                "GET",
+                "/api/v1/instance/peers",
+                401,
+                r#"{"error":"The access token is invalid"}"#,
+            )
+            .route("POST", "/oauth/token", 400, r#"{"error":"invalid_grant"}"#);
+        let (client, manager) = client_with_manager(&server).await;
+
+        let err = client.get_instance_peers().await.unwrap_err();
+        assert!(is_unauthorized(&err));
+        assert_eq!(manager.token_data().access_token, "access");
+    }
+}